@@ -1,12 +1,9 @@
-use std::path::PathBuf;
-
 use isle_math::{
     matrix::{Mat3, Mat4},
     rotation::Rotation,
     vector::{d2::Vec2, d3::Vec3},
 };
 use rustc_hash::FxHashMap;
-use tobj::{load_obj, LoadError, LoadOptions};
 use wgpu::util::DeviceExt;
 
 use crate::renderer::Vertex;
@@ -19,7 +16,8 @@ pub enum GeometryType {
 }
 
 pub enum GeometrySource {
-    Disk(PathBuf),
+    /// The `source://path` the asset server loaded the geometry from
+    Asset(String),
     Internal(&'static str),
     Dynamic(&'static str),
 }
@@ -73,7 +71,6 @@ pub struct GpuMesh {
 }
 
 pub enum GeometryState {
-    Disk,
     Memory(Mesh),
     Gpu(GpuMesh),
 }
@@ -176,38 +173,15 @@ impl Geometry {
     }
     pub fn name(&self) -> &str {
         match &self.source {
-            GeometrySource::Disk(path) => path.file_name().unwrap().to_str().unwrap(),
+            GeometrySource::Asset(path) => path.rsplit('/').next().unwrap_or(path),
             GeometrySource::Internal(name) => name,
             GeometrySource::Dynamic(name) => name,
         }
     }
 
-    pub fn load_to_mem(&mut self) -> Result<(), LoadError> {
-        let path = if let GeometrySource::Disk(path) = &self.source {
-            path
-        } else {
-            log::warn!(
-                "Attempted to load non-disk '{}' geometry to memory",
-                self.name()
-            );
-            return Ok(());
-        };
-
-        let (mut models, _) = load_obj(
-            path,
-            &LoadOptions {
-                single_index: true,
-                triangulate: true,
-                ignore_lines: true,
-                ignore_points: true,
-            },
-        )?;
-
-        let model = models.remove(0);
-
-        self.state = GeometryState::Memory(model.mesh.into());
-
-        Ok(())
+    /// True once `load_to_gpu` ran, only uploaded geometry can be rendered
+    pub fn is_uploaded(&self) -> bool {
+        matches!(self.state, GeometryState::Gpu(_))
     }
 
     pub fn load_to_gpu(&mut self, device: &wgpu::Device) {
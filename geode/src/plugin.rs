@@ -1,14 +1,15 @@
 use isle_engine::{
-    asset::AssetManager, executor::Executor, flow::{stages, FlowBuilder}, plugin::EngineHook, schedule::Scheduler, window::ReconfigureSurface
+    asset::Assets, executor::Executor, flow::{stages, FlowBuilder}, plugin::EngineHook, schedule::Scheduler, window::ReconfigureSurface
 };
 use isle_event::{EventReader, EventWriter};
 use wgpu::SurfaceError;
 
-use crate::renderer::Renderer;
+use crate::{geometry::Geometry, renderer::Renderer};
 
+pub mod assets;
 pub mod components;
 pub mod systems;
-pub mod protocol;
+pub mod processor;
 
 #[derive(Default)]
 struct RenderPlugin {
@@ -48,8 +49,12 @@ impl<S: Scheduler, E: Executor> EngineHook<S, E> for RenderPlugin {
         _scheduler: &mut S,
         _executor: &mut E,
     ) {
+        // A different resource than the renderer, so the two borrows never alias
+        let geometries = unsafe { &*(world as *const isle_ecs::world::World) };
+        let geometries = geometries.get_resource::<Assets<Geometry>>().unwrap();
         let renderer = unsafe { world.get_resource_mut::<Renderer>() }.unwrap();
-        if let Err(err) = renderer.render() {
+        let geometries = geometries.iter().map(|(_, geometry)| geometry);
+        if let Err(err) = renderer.render(geometries.filter(|geometry| geometry.is_uploaded())) {
             match err {
                 SurfaceError::Lost => {
                     let size = renderer.size();
@@ -64,9 +69,18 @@ impl<S: Scheduler, E: Executor> EngineHook<S, E> for RenderPlugin {
 }
 
 pub fn geode_plugin<S: Scheduler, E: Executor>(mut flow: FlowBuilder<S, E>) -> FlowBuilder<S, E> {
-    AssetManager::register_handler(protocol::GeometryHandler::default());
+    flow = flow
+        .with_asset_processor(processor::ObjProcessor)
+        .with_asset_loader(processor::PackedMeshLoader)
+        .with_asset_processor(processor::TextureProcessor)
+        .with_asset_loader(processor::PackedTextureLoader)
+        .with_asset_loader(assets::ImageLoader)
+        .with_resource(assets::GpuTextures::default());
     flow = flow.with_run_once(systems::setup);
 
+    flow = flow.with_staged_system(stages::POST_RUN, systems::upload_geometries);
+    flow = flow.with_staged_system(stages::POST_RUN, systems::upload_textures);
+
     flow = flow.with_staged_system(stages::POST_RUN, systems::update_cameras);
     flow = flow.with_staged_system(stages::POST_RUN, systems::update_lights);
     flow = flow.with_staged_system(stages::POST_RUN, systems::update_instances);
@@ -0,0 +1,43 @@
+use isle_engine::asset::{self, AssetId, AssetLoader, Handle, LoadContext};
+use rustc_hash::FxHashMap;
+
+use crate::texture::{Texture, TextureId, TextureSource};
+
+/// Decodes image files into textures in memory, `upload_textures` moves them to the GPU
+pub struct ImageLoader;
+
+impl AssetLoader for ImageLoader {
+    type Asset = Texture;
+    const EXTENSIONS: &'static [&'static str] = &["png", "jpg", "jpeg", "bmp", "tga"];
+    const MIME_TYPES: &'static [&'static str] = &["image/png", "image/jpeg", "image/bmp"];
+
+    fn load(&self, bytes: &[u8], context: &mut LoadContext) -> asset::Result<Texture> {
+        let source = TextureSource::Asset(context.path().to_string_lossy().into_owned());
+        Ok(Texture::from_memory(source, bytes)?)
+    }
+}
+
+/// GPU copies of the textures in `Assets<Texture>`, materials bind them through their `TextureId`
+#[derive(Default)]
+pub struct GpuTextures {
+    ids: FxHashMap<AssetId, TextureId>,
+}
+
+impl GpuTextures {
+    /// `None` until the texture finished loading and was uploaded
+    pub fn get(&self, handle: &Handle<Texture>) -> Option<TextureId> {
+        self.ids.get(&handle.id()).copied()
+    }
+
+    pub(crate) fn contains(&self, id: AssetId) -> bool {
+        self.ids.contains_key(&id)
+    }
+
+    pub(crate) fn insert(&mut self, id: AssetId, texture: TextureId) {
+        self.ids.insert(id, texture);
+    }
+
+    pub(crate) fn remove(&mut self, id: AssetId) -> Option<TextureId> {
+        self.ids.remove(&id)
+    }
+}
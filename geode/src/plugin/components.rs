@@ -1,7 +1,11 @@
 use isle_ecs::prelude::Component;
+use isle_engine::asset::Handle;
 use isle_math::{rotation::Angle, vector::d3::Vec3};
 
-use crate::camera::{CameraCreationSettings, CameraProjection};
+use crate::{
+    camera::{CameraCreationSettings, CameraProjection},
+    geometry::Geometry,
+};
 
 #[derive(Component)]
 pub struct Camera {
@@ -24,15 +28,16 @@ impl Camera {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Mesh {
-    pub(crate) geometry: usize,
+    pub(crate) geometry: Handle<Geometry>,
     pub(crate) instance: Option<usize>,
     pub(crate) dirty: bool,
 }
 
 impl Mesh {
-    pub fn new(geometry: usize) -> Self {
+    /// The mesh is drawn once the geometry finished loading
+    pub fn new(geometry: Handle<Geometry>) -> Self {
         Mesh {
             geometry,
             instance: None,
@@ -41,7 +46,7 @@ impl Mesh {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Material {
     pub(crate) material: usize,
     pub(crate) instance: usize,
@@ -0,0 +1,245 @@
+use std::{fmt::Display, io::Cursor, path::Path};
+
+use isle_engine::asset::{self, AssetLoader, AssetProcessor, LoadContext};
+use isle_math::vector::{d2::Vec2, d3::Vec3};
+use tobj::LoadOptions;
+
+use crate::{
+    geometry::{Geometry, GeometrySource, GeometryState, GeometryType, Mesh},
+    renderer::Vertex,
+    texture::{Texture, TextureSource},
+};
+
+const MESH_MAGIC: &[u8; 4] = b"GMSH";
+const TEXTURE_MAGIC: &[u8; 4] = b"GTEX";
+
+#[derive(Debug)]
+pub enum PackedMeshError {
+    NoModels,
+    Truncated,
+}
+
+impl std::error::Error for PackedMeshError {}
+
+impl Display for PackedMeshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoModels => write!(f, "Obj file contains no models"),
+            Self::Truncated => write!(
+                f,
+                "Packed mesh is truncated or not a packed mesh\nHint: delete the asset cache to rebuild it"
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PackedTextureError {
+    Truncated,
+}
+
+impl std::error::Error for PackedTextureError {}
+
+impl Display for PackedTextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truncated => write!(
+                f,
+                "Packed texture is truncated or not a packed texture\nHint: delete the asset cache to rebuild it"
+            ),
+        }
+    }
+}
+
+/// Parses `.obj` files once and caches their vertex and index buffers as `.mesh` artifacts
+///
+/// Layout: magic, vertex count and index count as little endian `u32`, the vertices as laid out
+/// in the vertex buffer, then the indices
+pub struct ObjProcessor;
+
+impl AssetProcessor for ObjProcessor {
+    const EXTENSIONS: &'static [&'static str] = &["obj"];
+    const OUTPUT_EXTENSION: &'static str = "mesh";
+    const VERSION: u32 = 1;
+
+    fn process(&self, bytes: &[u8], _: &Path) -> asset::Result<Vec<u8>> {
+        let (mut models, _) = tobj::load_obj_buf(
+            &mut Cursor::new(bytes),
+            &LoadOptions {
+                single_index: true,
+                triangulate: true,
+                ignore_lines: true,
+                ignore_points: true,
+            },
+            |_| Err(tobj::LoadError::OpenFileFailed),
+        )?;
+        if models.is_empty() {
+            return Err(PackedMeshError::NoModels.into());
+        }
+
+        let geometry = Geometry {
+            source: GeometrySource::Dynamic("obj"),
+            state: GeometryState::Memory(models.remove(0).mesh.into()),
+            instances: Default::default(),
+        };
+        let vertices = geometry.vertices();
+        let indices = geometry.indices();
+
+        let mut packed = Vec::with_capacity(
+            12 + std::mem::size_of_val(vertices.as_slice()) + std::mem::size_of_val(indices),
+        );
+        packed.extend_from_slice(MESH_MAGIC);
+        packed.extend_from_slice(&(vertices.len() as u32).to_le_bytes());
+        packed.extend_from_slice(&(indices.len() as u32).to_le_bytes());
+        packed.extend_from_slice(bytemuck::cast_slice(&vertices));
+        packed.extend_from_slice(bytemuck::cast_slice(indices));
+        Ok(packed)
+    }
+}
+
+/// Loads the `.mesh` artifacts written by `ObjProcessor` into memory, ready for `load_to_gpu`
+pub struct PackedMeshLoader;
+
+impl AssetLoader for PackedMeshLoader {
+    type Asset = Geometry;
+    const EXTENSIONS: &'static [&'static str] = &["mesh"];
+
+    fn load(&self, bytes: &[u8], context: &mut LoadContext) -> asset::Result<Geometry> {
+        let (header, body) = bytes.split_at_checked(12).ok_or(PackedMeshError::Truncated)?;
+        if &header[..4] != MESH_MAGIC {
+            return Err(PackedMeshError::Truncated.into());
+        }
+        let num_vertices = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+        let num_indices = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+
+        let vertex_len = num_vertices * size_of::<Vertex>();
+        if body.len() != vertex_len + num_indices * size_of::<u32>() {
+            return Err(PackedMeshError::Truncated.into());
+        }
+        let (vertices, indices) = body.split_at(vertex_len);
+        let vertices: Vec<Vertex> = bytemuck::pod_collect_to_vec(vertices);
+        let indices: Vec<u32> = bytemuck::pod_collect_to_vec(indices);
+
+        let mesh = Mesh {
+            geometry_type: GeometryType::Tris(indices),
+            vertices: vertices.iter().map(|vertex| Vec3::from(vertex.position)).collect(),
+            normals: Some(vertices.iter().map(|vertex| Vec3::from(vertex.normal)).collect()),
+            uvs: vertices.iter().map(|vertex| Vec2(vertex.uv[0], vertex.uv[1])).collect(),
+        };
+
+        Ok(Geometry {
+            source: GeometrySource::Asset(context.path().to_string_lossy().into_owned()),
+            state: GeometryState::Memory(mesh),
+            instances: Default::default(),
+        })
+    }
+}
+
+/// Decodes images once and caches them as `.mips` artifacts holding their full mip chain
+///
+/// Layout: magic, width, height and level count as little endian `u32`, then the RGBA8 pixels of
+/// every level from the full size image down to 1x1
+pub struct TextureProcessor;
+
+impl AssetProcessor for TextureProcessor {
+    const EXTENSIONS: &'static [&'static str] = &["png", "jpg", "jpeg", "bmp", "tga"];
+    const OUTPUT_EXTENSION: &'static str = "mips";
+    const VERSION: u32 = 1;
+
+    fn process(&self, bytes: &[u8], _: &Path) -> asset::Result<Vec<u8>> {
+        let levels = mip_chain(image::load_from_memory(bytes)?.to_rgba8());
+        let (width, height) = levels[0].dimensions();
+
+        let mut packed = Vec::with_capacity(16 + levels.iter().map(|level| level.len()).sum::<usize>());
+        packed.extend_from_slice(TEXTURE_MAGIC);
+        packed.extend_from_slice(&width.to_le_bytes());
+        packed.extend_from_slice(&height.to_le_bytes());
+        packed.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+        levels.iter().for_each(|level| packed.extend_from_slice(level));
+        Ok(packed)
+    }
+}
+
+/// Halves the image with a triangle filter until both sides are one pixel
+fn mip_chain(image: image::RgbaImage) -> Vec<image::RgbaImage> {
+    let mut levels = vec![image];
+    loop {
+        let (width, height) = levels.last().unwrap().dimensions();
+        if width == 1 && height == 1 {
+            return levels;
+        }
+        let level = image::imageops::resize(
+            levels.last().unwrap(),
+            (width / 2).max(1),
+            (height / 2).max(1),
+            image::imageops::FilterType::Triangle,
+        );
+        levels.push(level);
+    }
+}
+
+fn unpack_mips(bytes: &[u8]) -> Result<Vec<image::RgbaImage>, PackedTextureError> {
+    let (header, mut body) = bytes.split_at_checked(16).ok_or(PackedTextureError::Truncated)?;
+    if &header[..4] != TEXTURE_MAGIC {
+        return Err(PackedTextureError::Truncated);
+    }
+    let mut width = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    let mut height = u32::from_le_bytes(header[8..12].try_into().unwrap());
+    let num_levels = u32::from_le_bytes(header[12..16].try_into().unwrap());
+
+    let mut levels = Vec::new();
+    for _ in 0..num_levels {
+        let len = width as usize * height as usize * 4;
+        let (level, rest) = body.split_at_checked(len).ok_or(PackedTextureError::Truncated)?;
+        levels.push(image::RgbaImage::from_raw(width, height, level.to_vec()).unwrap());
+        body = rest;
+        width = (width / 2).max(1);
+        height = (height / 2).max(1);
+    }
+    if levels.is_empty() || !body.is_empty() {
+        return Err(PackedTextureError::Truncated);
+    }
+    Ok(levels)
+}
+
+/// Loads the `.mips` artifacts written by `TextureProcessor` into memory, ready for `to_gpu`
+pub struct PackedTextureLoader;
+
+impl AssetLoader for PackedTextureLoader {
+    type Asset = Texture;
+    const EXTENSIONS: &'static [&'static str] = &["mips"];
+
+    fn load(&self, bytes: &[u8], context: &mut LoadContext) -> asset::Result<Texture> {
+        Ok(Texture::from_mips(
+            TextureSource::Asset(context.path().to_string_lossy().into_owned()),
+            unpack_mips(bytes)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_mips_round_trip() {
+        let image = image::RgbaImage::from_fn(5, 2, |x, y| image::Rgba([x as u8, y as u8, 0, 255]));
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(image.clone())
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let packed = TextureProcessor.process(&png, Path::new("grid.png")).unwrap();
+        let levels = unpack_mips(&packed).unwrap();
+        let sizes: Vec<_> = levels.iter().map(|level| level.dimensions()).collect();
+        assert_eq!(sizes, [(5, 2), (2, 1), (1, 1)]);
+        assert_eq!(levels[0], image);
+
+        assert!(unpack_mips(&packed[..packed.len() - 1]).is_err());
+        assert!(unpack_mips(&[packed.as_slice(), &[0]].concat()).is_err());
+
+        let mut hostile = packed[..16].to_vec();
+        hostile[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(unpack_mips(&hostile).is_err());
+    }
+}
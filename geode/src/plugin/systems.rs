@@ -1,10 +1,28 @@
-use isle_ecs::{command::WorldCommand, ecs::ResMut, query::Query};
-use isle_engine::{prelude::Transform, window::WINDOW};
+use isle_ecs::{
+    command::WorldCommand,
+    ecs::{Res, ResMut},
+    query::Query,
+};
+use isle_engine::{
+    asset::{AssetEvent, Assets},
+    params::Event,
+    prelude::Transform,
+    window::WINDOW,
+};
 use isle_math::vector::d2::Vec2;
 
-use crate::{camera::CameraCreationSettings, lighting, renderer::Renderer};
+use crate::{
+    camera::CameraCreationSettings,
+    geometry::{Geometry, GeometryState},
+    lighting,
+    renderer::Renderer,
+    texture::Texture,
+};
 
-use super::components::{Camera, Material, Mesh, PointLight, SpotLight};
+use super::{
+    assets::GpuTextures,
+    components::{Camera, Material, Mesh, PointLight, SpotLight},
+};
 
 pub fn setup(mut command: WorldCommand) {
     let window = WINDOW.get().unwrap();
@@ -149,15 +167,79 @@ pub fn update_lights(
         });
 }
 
+/// Uploads geometry that is only in memory, both loaded and added at runtime
+///
+/// Reloaded geometry replaces the old one along with its instances, so its meshes are instantiated again
+pub fn upload_geometries(
+    mut events: Event<AssetEvent<Geometry>>,
+    meshes: Query<&mut Mesh>,
+    mut geometries: ResMut<Assets<Geometry>>,
+    renderer: Res<Renderer>,
+) {
+    for event in events.iter() {
+        if let AssetEvent::Modified(modified) = event {
+            meshes
+                .iter()
+                .filter(|mesh| mesh.geometry == modified)
+                .for_each(|mesh| {
+                    mesh.instance = None;
+                    mesh.dirty = true;
+                });
+        }
+    }
+
+    geometries
+        .iter_mut()
+        .filter(|(_, geometry)| matches!(geometry.state, GeometryState::Memory(_)))
+        .for_each(|(_, geometry)| geometry.load_to_gpu(renderer.device()));
+}
+
+/// Keeps a GPU copy of every texture in `Assets<Texture>` in the renderer, reloaded textures are
+/// written over their copy
+pub fn upload_textures(
+    mut events: Event<AssetEvent<Texture>>,
+    textures: Res<Assets<Texture>>,
+    mut gpu_textures: ResMut<GpuTextures>,
+    mut renderer: ResMut<Renderer>,
+) {
+    for event in events.iter() {
+        match event {
+            AssetEvent::Modified(modified) => {
+                let texture = gpu_textures.get(&modified);
+                if let (Some(texture), Some(source)) = (texture, textures.get(&modified)) {
+                    renderer.reload_texture(texture, source);
+                }
+            }
+            AssetEvent::Unloaded(id) => {
+                if let Some(texture) = gpu_textures.remove(id) {
+                    renderer.remove_texture(texture);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let uploaded: Vec<_> = textures
+        .iter()
+        .filter(|(id, _)| !gpu_textures.contains(*id))
+        .filter_map(|(id, texture)| Some((id, texture.to_gpu(renderer.device(), renderer.queue())?)))
+        .collect();
+    uploaded.into_iter().for_each(|(id, texture)| {
+        gpu_textures.insert(id, renderer.add_texture(texture));
+    });
+}
+
 pub fn update_instances(
     query: Query<(&mut Mesh, &Material, &Transform)>,
-    mut renderer: ResMut<Renderer>,
+    mut geometries: ResMut<Assets<Geometry>>,
 ) {
     query
         .iter()
         .filter(|(mesh, _, transform)| (transform.dirty() || mesh.dirty) && mesh.instance.is_some())
         .for_each(|(mesh, material, transform)| {
-            let geometry = renderer.geometry_mut(mesh.geometry);
+            let Some(geometry) = geometries.get_mut(&mesh.geometry) else {
+                return;
+            };
             geometry.update_instance(
                 material.material,
                 mesh.instance.unwrap(),
@@ -171,14 +253,16 @@ pub fn update_instances(
 
 pub fn create_geometries(
     instances: Query<(&mut Mesh, &Material, &Transform)>,
-    mut renderer: ResMut<Renderer>,
+    mut geometries: ResMut<Assets<Geometry>>,
 ) {
     instances
         .iter()
         .filter(|(mesh, _, _)| mesh.instance.is_none())
         .for_each(|(mesh, material, transform)| {
-            mesh.instance = Some(renderer.instantiate_geometry(
-                mesh.geometry,
+            let Some(geometry) = geometries.get_mut(&mesh.geometry) else {
+                return;
+            };
+            mesh.instance = Some(geometry.instantiate(
                 material.material,
                 material.instance,
                 transform.position(),
@@ -50,8 +50,8 @@ pub struct Renderer<'a> {
 
     lighting: Lighting,
     cameras: Vec<Camera>,
-    geometries: Vec<Geometry>,
-    textures: Vec<Texture>,
+    /// Emptied by `remove_texture`, ids of the other textures stay valid
+    textures: Vec<Option<Texture>>,
     materials: Vec<Material>,
 }
 
@@ -128,7 +128,6 @@ impl<'a> Renderer<'a> {
 
             lighting,
             cameras: Vec::new(),
-            geometries: Vec::new(),
             textures: Vec::new(),
             materials: Vec::new(),
         };
@@ -154,12 +153,8 @@ impl<'a> Renderer<'a> {
         &mut self.cameras[camera_id]
     }
 
-    pub fn geometry_mut(&mut self, geometry_id: usize) -> &mut Geometry {
-        &mut self.geometries[geometry_id]
-    }
-
     pub fn texture_mut(&mut self, texture_id: usize) -> &mut Texture {
-        &mut self.textures[texture_id]
+        self.textures[texture_id].as_mut().expect("Texture was removed")
     }
 
     pub fn material_mut(&mut self, material_id: usize) -> &mut Material {
@@ -194,16 +189,12 @@ impl<'a> Renderer<'a> {
         &self.cameras[camera_id]
     }
 
-    pub fn geometry(&self, geometry_id: usize) -> &Geometry {
-        &self.geometries[geometry_id]
-    }
-
     pub fn material(&self, material_id: usize) -> &Material {
         &self.materials[material_id]
     }
 
     pub fn texture(&self, texture_id: TextureId) -> &Texture {
-        &self.textures[texture_id.0]
+        self.textures[texture_id.0].as_ref().expect("Texture was removed")
     }
 
     pub fn main_camera(&self) -> &Camera {
@@ -218,12 +209,6 @@ impl<'a> Renderer<'a> {
         &self.lighting.bind_group_layout
     }
 
-    pub fn upload_geometry(&mut self, geometry_id: usize) {
-        let device = &self.device;
-        let geometry = &mut self.geometries[geometry_id];
-        geometry.load_to_gpu(device);
-    }
-
     pub fn resize(&mut self, new_size: Vec2) {
         if new_size.0 > 0. && new_size.0 > 0. {
             self.size = new_size;
@@ -259,11 +244,12 @@ impl<'a> Renderer<'a> {
 
     fn render_geometries_by_material<'r>(
         &self,
+        geometries: &[&Geometry],
         material_id: usize,
         instance_id: usize,
         render_pass: &mut wgpu::RenderPass<'r>,
     ) {
-        self.geometries
+        geometries
             .iter()
             .filter(|geometry| geometry.num_instances(material_id, instance_id) > 0)
             .for_each(|geometry| {
@@ -284,7 +270,12 @@ impl<'a> Renderer<'a> {
             });
     }
 
-    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+    /// Draws `geometries` from every camera, they have to be loaded to the GPU already
+    pub fn render<'g>(
+        &mut self,
+        geometries: impl IntoIterator<Item = &'g Geometry>,
+    ) -> Result<(), wgpu::SurfaceError> {
+        let geometries: Vec<_> = geometries.into_iter().collect();
         let frame = self.surface.get_current_texture()?;
         let view = frame
             .texture
@@ -328,6 +319,7 @@ impl<'a> Renderer<'a> {
                         .for_each(|(instance_id, instance)| {
                             render_pass.set_bind_group(2, &instance.bind_group, &[]);
                             self.render_geometries_by_material(
+                                &geometries,
                                 material_id,
                                 instance_id,
                                 &mut render_pass,
@@ -372,49 +364,19 @@ impl<'a> Renderer<'a> {
     }
 
     pub fn add_texture(&mut self, texture: Texture) -> TextureId {
-        self.textures.push(texture);
+        self.textures.push(Some(texture));
         TextureId(self.textures.len() - 1)
     }
 
-    pub fn add_geometry(&mut self, geometry: Geometry) -> usize {
-        self.geometries.push(geometry);
-        self.geometries.len() - 1
+    /// See `Texture::reload`
+    pub fn reload_texture(&mut self, texture_id: TextureId, source: &Texture) {
+        let texture = self.textures[texture_id.0].as_mut().expect("Texture was removed");
+        texture.reload(source, &self.device, &self.queue);
     }
 
-    pub fn instantiate_geometry(
-        &mut self,
-        geometry_id: usize,
-        material_id: usize,
-        material_instance_id: usize,
-        translation: isle_math::vector::d3::Vec3,
-        rotation: isle_math::rotation::Rotation,
-        scale: isle_math::vector::d3::Vec3,
-    ) -> usize {
-        self.geometries[geometry_id].instantiate(
-            material_id,
-            material_instance_id,
-            translation,
-            rotation,
-            scale,
-        )
-    }
-
-    pub fn update_geometry_instance(
-        &mut self,
-        geometry_id: usize,
-        material_id: usize,
-        instance_id: usize,
-        translation: isle_math::vector::d3::Vec3,
-        rotation: isle_math::rotation::Rotation,
-        scale: isle_math::vector::d3::Vec3,
-    ) {
-        self.geometries[geometry_id].update_instance(
-            material_id,
-            instance_id,
-            translation,
-            rotation,
-            scale,
-        );
+    /// Material instances bound to the texture keep using it until they are dropped
+    pub fn remove_texture(&mut self, texture_id: TextureId) -> Option<Texture> {
+        self.textures.get_mut(texture_id.0)?.take()
     }
 
     pub fn add_material(&mut self, material: Material) -> usize {
@@ -1,6 +1,4 @@
-use std::path::{Path, PathBuf};
-
-use image::{GenericImageView, ImageError};
+use image::ImageError;
 use isle_math::vector::d2::Vec2;
 
 use crate::{material::IntoBindGroup, renderer::Renderer};
@@ -18,8 +16,10 @@ impl IntoBindGroup for TextureId {
     }
 }
 
+#[derive(Clone)]
 pub enum TextureSource {
-    Disk(PathBuf),
+    /// The `source://path` the asset server loaded the texture from
+    Asset(String),
     Internal(&'static str),
     Dynamic(&'static str),
 }
@@ -34,9 +34,25 @@ pub struct AtlasedTexture {
     source_id: usize,
     uv: Vec2,
 }
+
+impl AtlasedTexture {
+    /// Region of atlas texture `source_id` starting at `uv`
+    pub fn new(source_id: usize, uv: Vec2) -> Self {
+        Self { source_id, uv }
+    }
+
+    pub fn source_id(&self) -> usize {
+        self.source_id
+    }
+
+    pub fn uv(&self) -> Vec2 {
+        self.uv
+    }
+}
+
 pub enum TextureState {
-    Disk,
-    Memory(image::DynamicImage),
+    /// Mip levels, the full size image first
+    Memory(Vec<image::RgbaImage>),
     Gpu(GpuTexture),
     Atlased(AtlasedTexture),
 }
@@ -50,43 +66,32 @@ pub struct Texture {
 impl Texture {
     pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
-    pub fn new(path: &Path) -> Self {
+    /// Decodes an image file already read into memory, such as the bytes an asset loader receives
+    pub fn from_memory(source: TextureSource, bytes: &[u8]) -> Result<Self, ImageError> {
+        let image = image::load_from_memory(bytes)?;
+        Ok(Self::from_mips(source, vec![image.to_rgba8()]))
+    }
+
+    /// Texture in memory from its mip levels, each half the size of the one before it
+    pub fn from_mips(source: TextureSource, levels: Vec<image::RgbaImage>) -> Self {
         Texture {
-            source: TextureSource::Disk(path.to_path_buf()),
-            state: TextureState::Disk,
-            size: Vec2(0.0, 0.0),
+            source,
+            size: levels[0].dimensions().into(),
+            state: TextureState::Memory(levels),
         }
     }
 
     pub fn name(&self) -> &str {
         match &self.source {
-            TextureSource::Disk(path) => path.file_name().unwrap().to_str().unwrap(),
+            TextureSource::Asset(path) => path.rsplit('/').next().unwrap_or(path),
             TextureSource::Internal(name) => name,
             TextureSource::Dynamic(name) => name,
         }
     }
 
-    pub fn load_to_mem(&mut self) -> Result<(), ImageError> {
-        let path = if let TextureSource::Disk(path) = &self.source {
-            path
-        } else {
-            log::warn!(
-                "Attempted to load non-disk '{}' texture to memory",
-                self.name()
-            );
-            return Ok(());
-        };
-
-        let image = image::open(path)?;
-        self.size = image.dimensions().into();
-        self.state = TextureState::Memory(image);
-
-        Ok(())
-    }
-
     pub fn load_to_gpu(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
-        let image = match &self.state {
-            TextureState::Memory(image) => image,
+        let levels = match &self.state {
+            TextureState::Memory(levels) => levels,
             TextureState::Gpu(_) => {
                 log::warn!(
                     "Attempted to load already loaded '{}' texture to GPU",
@@ -98,16 +103,30 @@ impl Texture {
                 log::warn!("Attempted to load atlased '{}' texture to GPU", self.name());
                 return;
             }
-            TextureState::Disk => {
-                log::error!(
-                    "Attempted to load unloaded '{}' texture to GPU",
-                    self.name()
-                );
-                panic!("Attempted to load unloaded texture to GPU");
-            }
         };
 
-        let rgba = image.to_rgba8();
+        self.state = TextureState::Gpu(self.create_gpu_texture(levels, device, queue));
+    }
+
+    /// GPU copy of a texture in memory, the texture itself stays in memory so it can be uploaded again
+    pub fn to_gpu(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        let TextureState::Memory(levels) = &self.state else {
+            return None;
+        };
+
+        Some(Self {
+            source: self.source.clone(),
+            state: TextureState::Gpu(self.create_gpu_texture(levels, device, queue)),
+            size: self.size,
+        })
+    }
+
+    fn create_gpu_texture(
+        &self,
+        levels: &[image::RgbaImage],
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> GpuTexture {
         let size = wgpu::Extent3d {
             width: self.size.0 as u32,
             height: self.size.1 as u32,
@@ -116,7 +135,7 @@ impl Texture {
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some(self.name()),
             size,
-            mip_level_count: 1,
+            mip_level_count: levels.len() as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
@@ -124,38 +143,55 @@ impl Texture {
             view_formats: &[],
         });
 
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * size.width),
-                rows_per_image: Some(size.height),
-            },
-            size,
-        );
+        write_levels(queue, &texture, levels);
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mipmap_filter = match levels.len() {
+            1 => wgpu::FilterMode::Nearest,
+            _ => wgpu::FilterMode::Linear,
+        };
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter,
             ..Default::default()
         });
 
-        self.state = TextureState::Gpu(GpuTexture {
+        GpuTexture {
             texture,
             view,
             sampler,
-        });
+        }
+    }
+
+    /// Replaces the contents of this GPU texture with `source`, a texture in memory such as a
+    /// reloaded asset
+    ///
+    /// Writes in place while the size and mip levels stay the same so material instances bound to
+    /// the texture show the new image, otherwise the texture is created again and they keep the old one
+    pub fn reload(&mut self, source: &Texture, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let TextureState::Memory(levels) = &source.state else {
+            log::warn!("Attempted to reload '{}' from a texture not in memory", self.name());
+            return;
+        };
+
+        match &self.state {
+            TextureState::Gpu(gpu)
+                if self.size == source.size && gpu.texture.mip_level_count() == levels.len() as u32 =>
+            {
+                write_levels(queue, &gpu.texture, levels)
+            }
+            _ => {
+                log::warn!(
+                    "Texture '{}' changed size, material instances bound to it keep the previous image",
+                    self.name()
+                );
+                *self = source.to_gpu(device, queue).unwrap();
+            }
+        }
     }
 
     pub fn create_depth_texture(device: &wgpu::Device, size: Vec2) -> Self {
@@ -275,6 +311,31 @@ impl Texture {
     }
 }
 
+fn write_levels(queue: &wgpu::Queue, texture: &wgpu::Texture, levels: &[image::RgbaImage]) {
+    levels.iter().enumerate().for_each(|(level, image)| {
+        let size = wgpu::Extent3d {
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1,
+        };
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture,
+                mip_level: level as u32,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            image,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * size.width),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+    });
+}
+
 impl IntoBindGroup for Texture {
     fn into_bind_group<'a>(&'a self, _: &Renderer, bindings: &mut Vec<wgpu::BindGroupEntry<'a>>) {
         let next_index = bindings.len() as u32;
@@ -0,0 +1,41 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use isle_ecs::{entity::Entity, prelude::Component, world::World};
+
+const ENTITIES: u32 = 1000;
+
+#[derive(Component)]
+struct Position(f32, f32);
+
+#[derive(Component)]
+struct Selected;
+
+#[derive(Component)]
+#[component(storage = "sparse")]
+struct SparseSelected;
+
+fn populated_world() -> World {
+    let mut world = World::new();
+    (0..ENTITIES).for_each(|i| world.store_component(Entity(0, i), Position(i as f32, 0.0)));
+    world
+}
+
+fn toggle<T: Component>(c: &mut Criterion, name: &str, make: fn() -> T) {
+    let mut world = populated_world();
+
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            (0..ENTITIES).for_each(|i| world.store_component(Entity(0, i), make()));
+            (0..ENTITIES).for_each(|i| {
+                black_box(world.remove_component::<T>(&Entity(0, i)));
+            });
+        })
+    });
+}
+
+fn toggle_components(c: &mut Criterion) {
+    toggle(c, "toggle dense", || Selected);
+    toggle(c, "toggle sparse", || SparseSelected);
+}
+
+criterion_group!(benches, toggle_components);
+criterion_main!(benches);
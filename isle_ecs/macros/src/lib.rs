@@ -1,14 +1,106 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, Index, LitStr};
 
-#[proc_macro_derive(Component)]
+#[proc_macro_derive(Component, attributes(component))]
 pub fn derive_component(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let options = match ComponentOptions::parse(&input.attrs) {
+        Ok(options) => options,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let serde = options
+        .serialize
+        .then(|| quote!(.with_serde(isle_ecs::scene::SceneSerde::new::<Self>())));
+    let snapshot = options
+        .snapshot
+        .then(|| quote!(.with_snapshot(isle_ecs::world::snapshot::SnapshotClone::new::<Self>())));
+
+    let storage = options
+        .sparse
+        .then(|| quote!(const STORAGE: isle_ecs::component::StorageType = isle_ecs::component::StorageType::Sparse;));
+
+    let fields = match &input.data {
+        Data::Struct(data) => field_infos(&data.fields),
+        _ => Vec::new(),
+    };
 
     quote! {
-        impl isle_ecs::component::Component for #name {}
+        impl #impl_generics isle_ecs::component::Component for #name #ty_generics #where_clause {
+            #storage
+
+            fn component_info() -> isle_ecs::registry::ComponentInfo {
+                isle_ecs::registry::ComponentInfo::new::<Self>(vec![#(#fields),*])
+                    #serde
+                    #snapshot
+            }
+        }
     }
     .into()
 }
+
+#[derive(Default)]
+struct ComponentOptions {
+    serialize: bool,
+    snapshot: bool,
+    sparse: bool,
+}
+
+impl ComponentOptions {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("component")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("serialize") {
+                    options.serialize = true;
+                    Ok(())
+                } else if meta.path.is_ident("snapshot") {
+                    options.snapshot = true;
+                    Ok(())
+                } else if meta.path.is_ident("storage") {
+                    let storage: LitStr = meta.value()?.parse()?;
+                    match storage.value().as_str() {
+                        "sparse" => options.sparse = true,
+                        "dense" => options.sparse = false,
+                        _ => return Err(meta.error("Expected `\"dense\"` or `\"sparse\"`")),
+                    }
+                    Ok(())
+                } else {
+                    Err(meta.error("Expected `serialize`, `snapshot` or `storage`"))
+                }
+            })?;
+        }
+
+        Ok(options)
+    }
+}
+
+fn field_infos(fields: &Fields) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let ty = &field.ty;
+            let (name, member) = match &field.ident {
+                Some(ident) => (ident.to_string(), quote!(#ident)),
+                None => {
+                    let index = Index::from(i);
+                    (i.to_string(), quote!(#index))
+                }
+            };
+
+            quote! {
+                isle_ecs::registry::FieldInfo::new::<Self, #ty>(
+                    #name,
+                    |component| &component.#member,
+                    |component| &mut component.#member,
+                )
+            }
+        })
+        .collect()
+}
@@ -1,17 +1,35 @@
-use std::sync::mpsc::Sender;
+use std::sync::{mpsc::Sender, Arc};
 
 use crate::{
     ecs::{RefType, SystemParam, TypeSet},
-    entity::Entity,
+    entity::{DefaultEntityFactory, Entity, EntityFactory},
+    prefab::{PrefabOverrides, SceneHandle},
     prelude::Component,
     world::{Command, World},
 };
 
 pub struct WorldCommand<'a> {
     sender: &'a mut Sender<Command>,
+    entity_factory: &'a Arc<DefaultEntityFactory>,
 }
 
 impl WorldCommand<'_> {
+    pub fn make_entity(&self) -> Entity {
+        self.entity_factory.make_entity()
+    }
+    pub fn spawn_scene(&mut self, handle: SceneHandle) -> Entity {
+        self.spawn_scene_with(handle, PrefabOverrides::new())
+    }
+    /// Panics when applied if an override targets an entity outside the prefab, see `World::spawn_scene`
+    pub fn spawn_scene_with(&mut self, handle: SceneHandle, overrides: PrefabOverrides) -> Entity {
+        let root = self.make_entity();
+        self.send(Box::new(move |world| {
+            if let Err(err) = world.spawn_scene_at(root, handle, overrides) {
+                panic!("Failed to spawn scene {handle:?}: {err}");
+            }
+        }));
+        root
+    }
     pub fn add_resource<T: 'static>(&mut self, resource: T) {
         self.send(Box::new(move |world| {
             world.store_resource(resource);
@@ -22,6 +40,11 @@ impl WorldCommand<'_> {
             world.store_component(entity, component);
         }));
     }
+    pub fn remove_component<T: Component>(&mut self, entity: Entity) {
+        self.send(Box::new(move |world| {
+            world.remove_component::<T>(&entity);
+        }));
+    }
     pub fn send(&mut self, command: Command) {
         self.sender.send(command).unwrap();
     }
@@ -29,7 +52,7 @@ impl WorldCommand<'_> {
 
 impl<'a> SystemParam for WorldCommand<'a> {
     type Item<'new> = WorldCommand<'new>;
-    type State = Sender<Command>;
+    type State = (Sender<Command>, Arc<DefaultEntityFactory>);
 
     fn collect_types(types: &mut impl TypeSet) {
         types.insert_type::<WorldCommand>(RefType::Immutable);
@@ -37,7 +60,7 @@ impl<'a> SystemParam for WorldCommand<'a> {
 
     fn init_state(world: &std::cell::UnsafeCell<World>) -> Self::State {
         let world = unsafe { &*world.get() };
-        world.command_sender().clone()
+        (world.command_sender().clone(), world.entity_factory().clone())
     }
 
     fn from_world<'w>(
@@ -45,6 +68,10 @@ impl<'a> SystemParam for WorldCommand<'a> {
         state: &'w mut Self::State,
         _: &str,
     ) -> Self::Item<'w> {
-        WorldCommand { sender: state }
+        let (sender, entity_factory) = state;
+        WorldCommand {
+            sender,
+            entity_factory,
+        }
     }
 }
@@ -1,3 +1,22 @@
 use std::any::Any;
 
-pub trait Component: Any + 'static {}
+use crate::registry::ComponentInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageType {
+    #[default]
+    Dense,
+    /// Stored in a sparse set indexed by entity, cheap to add and remove every frame
+    Sparse,
+}
+
+pub trait Component: Any + 'static {
+    const STORAGE: StorageType = StorageType::Dense;
+
+    fn component_info() -> ComponentInfo
+    where
+        Self: Sized,
+    {
+        ComponentInfo::new::<Self>(Vec::new())
+    }
+}
@@ -20,6 +20,18 @@ impl DefaultEntityFactory {
             next_entity: AtomicU32::new(0),
         }
     }
+
+    pub(crate) fn state(&self) -> (u32, u32) {
+        (
+            self.generation.load(Ordering::SeqCst),
+            self.next_entity.load(Ordering::SeqCst),
+        )
+    }
+
+    pub(crate) fn set_state(&self, (generation, next_entity): (u32, u32)) {
+        self.generation.store(generation, Ordering::SeqCst);
+        self.next_entity.store(next_entity, Ordering::SeqCst);
+    }
 }
 
 impl EntityFactory for DefaultEntityFactory {
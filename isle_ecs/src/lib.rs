@@ -1,13 +1,18 @@
+extern crate self as isle_ecs;
+
 pub mod command;
 pub mod component;
 pub mod ecs;
 pub mod entity;
 pub mod executor;
+pub mod prefab;
 pub mod query;
+pub mod registry;
+pub mod scene;
 pub mod schedule;
 pub mod world;
 
 pub mod prelude {
-    pub use crate::{component::*, ecs::*, entity::*, executor::*, query::*};
+    pub use crate::{component::*, ecs::*, entity::*, executor::*, prefab::*, query::*, registry::*};
     pub use isle_ecs_macros::Component;
 }
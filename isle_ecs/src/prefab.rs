@@ -0,0 +1,295 @@
+use std::{
+    any::{Any, TypeId},
+    rc::Rc,
+};
+
+use serde_json::Value;
+
+use crate::{
+    entity::Entity,
+    prelude::Component,
+    registry::ComponentRegistry,
+    scene::{Scene, SceneError, SceneSerde},
+    world::World,
+};
+
+type ComponentOverride = (usize, TypeId, fn(&mut ComponentRegistry), Box<dyn Any>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SceneHandle(pub usize);
+
+enum PrefabComponent {
+    Template {
+        make: Box<dyn Fn() -> Box<dyn Any>>,
+        register: fn(&mut ComponentRegistry),
+    },
+    Serialized {
+        serde: SceneSerde,
+        value: Value,
+    },
+}
+
+impl PrefabComponent {
+    fn template<T: Component + Clone>(component: T) -> Self {
+        Self::Template {
+            make: Box::new(move || Box::new(component.clone())),
+            register: ComponentRegistry::register::<T>,
+        }
+    }
+
+    fn make(&self, registry: &mut ComponentRegistry) -> Box<dyn Any> {
+        match self {
+            Self::Template { make, register } => {
+                register(registry);
+                make()
+            }
+            Self::Serialized { serde, value } => serde
+                .deserialize(value.clone())
+                .expect("Prefab component was validated on creation"),
+        }
+    }
+}
+
+/// A reusable set of entities, entity `Prefab::ROOT` is the root of every spawned instance
+pub struct Prefab {
+    entities: Vec<Vec<(TypeId, PrefabComponent)>>,
+}
+
+impl Default for Prefab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Prefab {
+    pub const ROOT: usize = 0;
+
+    pub fn new() -> Self {
+        Self {
+            entities: vec![Vec::new()],
+        }
+    }
+
+    /// Builds a prefab from a scene, the first scene entity becomes the root
+    pub fn from_scene(scene: &Scene, registry: &ComponentRegistry) -> Result<Self, SceneError> {
+        let mut entities = scene
+            .entities
+            .iter()
+            .map(|entity| {
+                entity
+                    .components
+                    .iter()
+                    .map(|(name, value)| {
+                        let info = registry
+                            .get_by_name(name)
+                            .ok_or_else(|| SceneError::UnknownComponent(name.clone()))?;
+                        let serde = *info.serde().ok_or(SceneError::NotSerializable(info.name()))?;
+                        serde.deserialize(value.clone())?;
+
+                        Ok((
+                            info.type_id(),
+                            PrefabComponent::Serialized {
+                                serde,
+                                value: value.clone(),
+                            },
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, SceneError>>()
+            })
+            .collect::<Result<Vec<_>, SceneError>>()?;
+
+        if entities.is_empty() {
+            entities.push(Vec::new());
+        }
+
+        Ok(Self { entities })
+    }
+
+    pub fn add_entity(&mut self) -> usize {
+        self.entities.push(Vec::new());
+        self.entities.len() - 1
+    }
+
+    pub fn add_component<T: Component + Clone>(&mut self, entity: usize, component: T) {
+        let components = &mut self.entities[entity];
+        components.retain(|(type_id, _)| *type_id != TypeId::of::<T>());
+        components.push((TypeId::of::<T>(), PrefabComponent::template(component)));
+    }
+
+    pub fn with_component<T: Component + Clone>(mut self, entity: usize, component: T) -> Self {
+        self.add_component(entity, component);
+        self
+    }
+
+    pub fn num_entities(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Nothing is spawned when an override targets an entity outside the prefab
+    pub(crate) fn spawn(
+        &self,
+        world: &mut World,
+        handle: SceneHandle,
+        root: Entity,
+        overrides: PrefabOverrides,
+    ) -> Result<Vec<Entity>, SceneError> {
+        if let Some((index, ..)) = overrides
+            .components
+            .iter()
+            .find(|(index, ..)| *index >= self.entities.len())
+        {
+            return Err(SceneError::InvalidOverride {
+                index: *index,
+                entities: self.entities.len(),
+            });
+        }
+
+        let entities: Vec<Entity> = std::iter::once(root)
+            .chain((1..self.entities.len()).map(|_| world.make_entity()))
+            .collect();
+
+        self.entities
+            .iter()
+            .zip(&entities)
+            .for_each(|(components, entity)| {
+                components.iter().for_each(|(type_id, component)| {
+                    let component = component.make(world.registry_mut());
+                    world.store_component_by_id(*entity, *type_id, component);
+                });
+            });
+
+        overrides
+            .components
+            .into_iter()
+            .for_each(|(index, type_id, register, component)| {
+                register(world.registry_mut());
+                world.store_component_by_id(entities[index], type_id, component);
+            });
+
+        entities.iter().skip(1).for_each(|entity| {
+            world.store_component(*entity, SceneMember(root));
+        });
+        world.store_component(
+            root,
+            SceneInstance {
+                handle,
+                entities: entities.clone(),
+            },
+        );
+
+        Ok(entities)
+    }
+}
+
+/// Per-instance components replacing or extending the prefab's own
+#[derive(Default)]
+pub struct PrefabOverrides {
+    components: Vec<ComponentOverride>,
+}
+
+impl PrefabOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with<T: Component>(self, component: T) -> Self {
+        self.with_entity(Prefab::ROOT, component)
+    }
+
+    pub fn with_entity<T: Component>(mut self, entity: usize, component: T) -> Self {
+        self.components.push((
+            entity,
+            TypeId::of::<T>(),
+            ComponentRegistry::register::<T>,
+            Box::new(component),
+        ));
+        self
+    }
+}
+
+#[derive(Default)]
+pub struct Prefabs {
+    prefabs: Vec<Rc<Prefab>>,
+}
+
+impl Prefabs {
+    pub fn add(&mut self, prefab: Prefab) -> SceneHandle {
+        self.prefabs.push(Rc::new(prefab));
+        SceneHandle(self.prefabs.len() - 1)
+    }
+
+    pub fn get(&self, handle: SceneHandle) -> Option<&Prefab> {
+        self.prefabs.get(handle.0).map(Rc::as_ref)
+    }
+
+    pub(crate) fn get_shared(&self, handle: SceneHandle) -> Option<Rc<Prefab>> {
+        self.prefabs.get(handle.0).cloned()
+    }
+}
+
+/// Added to the root entity of every spawned prefab
+#[derive(Component, Debug)]
+pub struct SceneInstance {
+    pub handle: SceneHandle,
+    pub entities: Vec<Entity>,
+}
+
+/// Added to every non-root entity of a spawned prefab, points to the root
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SceneMember(pub Entity);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct Health(u32);
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct Weapon(u32);
+
+    #[test]
+    fn spawn_with_overrides() {
+        let mut world = World::new();
+
+        let mut prefab = Prefab::new().with_component(Prefab::ROOT, Health(100));
+        let weapon = prefab.add_entity();
+        prefab.add_component(weapon, Weapon(10));
+
+        let mut prefabs = Prefabs::default();
+        let handle = prefabs.add(prefab);
+        world.store_resource(prefabs);
+
+        let a = world.spawn_scene(handle, PrefabOverrides::new()).unwrap();
+        let b = world
+            .spawn_scene(
+                handle,
+                PrefabOverrides::new()
+                    .with(Health(50))
+                    .with_entity(weapon, Weapon(20)),
+            )
+            .unwrap();
+
+        assert_eq!(world.get_component::<Health>(&a), Some(&Health(100)));
+        assert_eq!(world.get_component::<Health>(&b), Some(&Health(50)));
+
+        let instance = world.get_component::<SceneInstance>(&b).unwrap();
+        assert_eq!(instance.handle, handle);
+
+        let b_weapon = instance.entities[weapon];
+        assert_eq!(world.get_component::<Weapon>(&b_weapon), Some(&Weapon(20)));
+        assert_eq!(world.get_component::<SceneMember>(&b_weapon).unwrap().0, b);
+    }
+
+    #[test]
+    fn overrides_outside_the_prefab_fail() {
+        let mut world = World::new();
+        let mut prefabs = Prefabs::default();
+        let handle = prefabs.add(Prefab::new().with_component(Prefab::ROOT, Health(100)));
+        world.store_resource(prefabs);
+
+        let spawned = world.spawn_scene(handle, PrefabOverrides::new().with_entity(1, Weapon(20)));
+        assert!(matches!(spawned, Err(SceneError::InvalidOverride { index: 1, entities: 1 })));
+        assert_eq!(world.entities().count(), 0);
+    }
+}
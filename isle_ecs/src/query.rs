@@ -44,19 +44,30 @@ where
             .map(|BorrowSignature(type_id, _)| type_id)
             .collect();
 
-        interactable_components
+        let world = unsafe { &mut *self.world.get() };
+
+        // Start from the smallest required storage, optional only queries visit every match
+        let candidates = match components
             .iter()
-            .map(|BorrowSignature(type_id, _)| {
-                let world = unsafe { &mut *self.world.get() };
-                world.get_entities_with_component(type_id)
-            })
-            .flatten()
+            .map(|type_id| world.get_entities_with_component(type_id))
+            .min_by_key(Vec::len)
+        {
+            Some(candidates) => candidates,
+            None => interactable_components
+                .iter()
+                .flat_map(|BorrowSignature(type_id, _)| world.get_entities_with_component(type_id))
+                .collect(),
+        };
+
+        candidates
+            .into_iter()
             .filter(|entity| {
-                let world = unsafe { &mut *self.world.get() };
-                let entity_components = world.get_entity_components(entity);
-
-                entity_components.is_superset(&components)
-                    && entity_components.is_disjoint(&without_components)
+                components
+                    .iter()
+                    .all(|type_id| world.has_component_by_id(entity, type_id))
+                    && !without_components
+                        .iter()
+                        .any(|type_id| world.has_component_by_id(entity, type_id))
             })
             .collect()
     }
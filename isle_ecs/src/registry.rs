@@ -0,0 +1,456 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    fmt::Display,
+};
+
+use hashbrown::HashMap;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    component::{Component, StorageType},
+    scene::SceneSerde,
+    world::snapshot::SnapshotClone,
+};
+
+type FieldGetter = Box<dyn Fn(&dyn Any) -> Option<&dyn Any>>;
+type FieldGetterMut = Box<dyn Fn(&mut dyn Any) -> Option<&mut dyn Any>>;
+type FieldSetter = Box<dyn Fn(&mut dyn Any, Box<dyn Any>) -> Result<(), ReflectError>>;
+
+#[derive(Debug)]
+pub enum ReflectError {
+    UnknownType(TypeId),
+    UnknownField {
+        type_name: &'static str,
+        field: String,
+    },
+    NotReflected {
+        type_name: &'static str,
+    },
+    TypeMismatch {
+        expected: &'static str,
+    },
+}
+
+impl std::error::Error for ReflectError {}
+
+impl Display for ReflectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownType(type_id) => write!(f, "Type {type_id:?} is not registered"),
+            Self::UnknownField { type_name, field } => {
+                write!(f, "Type {type_name} has no field '{field}'")
+            }
+            Self::NotReflected { type_name } => {
+                write!(f, "Type {type_name} is not registered, cannot access its fields")
+            }
+            Self::TypeMismatch { expected } => write!(f, "Expected value of type {expected}"),
+        }
+    }
+}
+
+pub struct FieldInfo {
+    name: &'static str,
+    type_name: &'static str,
+    type_id: TypeId,
+    get: FieldGetter,
+    get_mut: FieldGetterMut,
+    set: FieldSetter,
+}
+
+impl FieldInfo {
+    pub fn new<C: 'static, F: 'static>(
+        name: &'static str,
+        get: fn(&C) -> &F,
+        get_mut: fn(&mut C) -> &mut F,
+    ) -> Self {
+        Self {
+            name,
+            type_name: type_name::<F>(),
+            type_id: TypeId::of::<F>(),
+            get: Box::new(move |component| Some(get(component.downcast_ref()?))),
+            get_mut: Box::new(move |component| Some(get_mut(component.downcast_mut()?))),
+            set: Box::new(move |component, value| {
+                let component = component.downcast_mut::<C>().ok_or(ReflectError::TypeMismatch {
+                    expected: type_name::<C>(),
+                })?;
+                let value = value.downcast::<F>().map_err(|_| ReflectError::TypeMismatch {
+                    expected: type_name::<F>(),
+                })?;
+                *get_mut(component) = *value;
+                Ok(())
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    pub fn get<'a>(&self, component: &'a dyn Any) -> Option<&'a dyn Any> {
+        (self.get)(component)
+    }
+
+    pub fn get_mut<'a>(&self, component: &'a mut dyn Any) -> Option<&'a mut dyn Any> {
+        (self.get_mut)(component)
+    }
+
+    pub fn set(&self, component: &mut dyn Any, value: Box<dyn Any>) -> Result<(), ReflectError> {
+        (self.set)(component, value)
+    }
+}
+
+pub struct ComponentInfo {
+    name: &'static str,
+    type_id: TypeId,
+    size: usize,
+    storage: StorageType,
+    fields: Vec<FieldInfo>,
+    serde: Option<SceneSerde>,
+    snapshot: Option<SnapshotClone>,
+}
+
+impl ComponentInfo {
+    pub fn new<T: 'static>(fields: Vec<FieldInfo>) -> Self {
+        Self {
+            name: type_name::<T>(),
+            type_id: TypeId::of::<T>(),
+            size: std::mem::size_of::<T>(),
+            storage: StorageType::Dense,
+            fields,
+            serde: None,
+            snapshot: None,
+        }
+    }
+
+    pub fn with_storage(mut self, storage: StorageType) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    pub fn with_serde(mut self, serde: SceneSerde) -> Self {
+        self.serde = Some(serde);
+        self
+    }
+
+    pub fn with_snapshot(mut self, snapshot: SnapshotClone) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn storage(&self) -> StorageType {
+        self.storage
+    }
+
+    pub fn fields(&self) -> &[FieldInfo] {
+        &self.fields
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldInfo> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    pub fn serde(&self) -> Option<&SceneSerde> {
+        self.serde.as_ref()
+    }
+
+    pub fn snapshot(&self) -> Option<&SnapshotClone> {
+        self.snapshot.as_ref()
+    }
+
+    fn field_or_err(&self, name: &str) -> Result<&FieldInfo, ReflectError> {
+        self.field(name).ok_or_else(|| ReflectError::UnknownField {
+            type_name: self.name,
+            field: name.to_string(),
+        })
+    }
+}
+
+pub struct ResourceInfo {
+    name: &'static str,
+    type_id: TypeId,
+    serde: Option<SceneSerde>,
+    snapshot: Option<SnapshotClone>,
+}
+
+impl ResourceInfo {
+    fn new<T: 'static>() -> Self {
+        Self {
+            name: type_name::<T>(),
+            type_id: TypeId::of::<T>(),
+            serde: None,
+            snapshot: None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    pub fn serde(&self) -> Option<&SceneSerde> {
+        self.serde.as_ref()
+    }
+
+    pub fn snapshot(&self) -> Option<&SnapshotClone> {
+        self.snapshot.as_ref()
+    }
+}
+
+#[derive(Default)]
+pub struct ComponentRegistry {
+    components: HashMap<TypeId, ComponentInfo>,
+    resources: HashMap<TypeId, ResourceInfo>,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<T: Component>(&mut self) {
+        self.components
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| T::component_info().with_storage(T::STORAGE));
+    }
+
+    pub fn register_info(&mut self, info: ComponentInfo) {
+        self.components.insert(info.type_id, info);
+    }
+
+    pub fn get(&self, type_id: &TypeId) -> Option<&ComponentInfo> {
+        self.components.get(type_id)
+    }
+
+    pub fn contains(&self, type_id: &TypeId) -> bool {
+        self.components.contains_key(type_id)
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&ComponentInfo> {
+        self.components.values().find(|info| info.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ComponentInfo> {
+        self.components.values()
+    }
+
+    pub fn register_resource<T: Serialize + DeserializeOwned + 'static>(&mut self) {
+        self.resources
+            .entry(TypeId::of::<T>())
+            .or_insert_with(ResourceInfo::new::<T>)
+            .serde = Some(SceneSerde::new::<T>());
+    }
+
+    /// Includes the resource in world snapshots
+    pub fn register_resource_snapshot<T: Clone + 'static>(&mut self) {
+        self.resources
+            .entry(TypeId::of::<T>())
+            .or_insert_with(ResourceInfo::new::<T>)
+            .snapshot = Some(SnapshotClone::new::<T>());
+    }
+
+    pub fn get_resource(&self, type_id: &TypeId) -> Option<&ResourceInfo> {
+        self.resources.get(type_id)
+    }
+
+    pub fn get_resource_by_name(&self, name: &str) -> Option<&ResourceInfo> {
+        self.resources.values().find(|info| info.name == name)
+    }
+
+    pub fn iter_resources(&self) -> impl Iterator<Item = &ResourceInfo> {
+        self.resources.values()
+    }
+
+    fn info_or_err(&self, type_id: &TypeId, type_name: &'static str) -> Result<&ComponentInfo, ReflectError> {
+        self.get(type_id)
+            .ok_or(ReflectError::NotReflected { type_name })
+    }
+
+    /// Resolves a dot separated field path, e.g. `position.0`, descending through registered types
+    pub fn get_field<'a>(
+        &self,
+        type_id: &TypeId,
+        component: &'a dyn Any,
+        path: &str,
+    ) -> Result<&'a dyn Any, ReflectError> {
+        let mut info = self.get(type_id).ok_or(ReflectError::UnknownType(*type_id))?;
+        let mut value = component;
+        let mut segments = path.split('.').peekable();
+
+        while let Some(segment) = segments.next() {
+            let field = info.field_or_err(segment)?;
+            value = field.get(value).ok_or(ReflectError::TypeMismatch {
+                expected: info.name,
+            })?;
+
+            if segments.peek().is_some() {
+                info = self.info_or_err(&field.type_id, field.type_name)?;
+            }
+        }
+
+        Ok(value)
+    }
+
+    pub fn get_field_mut<'a>(
+        &self,
+        type_id: &TypeId,
+        component: &'a mut dyn Any,
+        path: &str,
+    ) -> Result<&'a mut dyn Any, ReflectError> {
+        let mut info = self.get(type_id).ok_or(ReflectError::UnknownType(*type_id))?;
+        let mut value = component;
+        let mut segments = path.split('.').peekable();
+
+        while let Some(segment) = segments.next() {
+            let field = info.field_or_err(segment)?;
+            value = field.get_mut(value).ok_or(ReflectError::TypeMismatch {
+                expected: info.name,
+            })?;
+
+            if segments.peek().is_some() {
+                info = self.info_or_err(&field.type_id, field.type_name)?;
+            }
+        }
+
+        Ok(value)
+    }
+
+    pub fn set_field(
+        &self,
+        type_id: &TypeId,
+        component: &mut dyn Any,
+        path: &str,
+        value: Box<dyn Any>,
+    ) -> Result<(), ReflectError> {
+        let (parent, field) = match path.rsplit_once('.') {
+            Some((parent, field)) => (Some(parent), field),
+            None => (None, path),
+        };
+
+        let (parent_type, parent) = match parent {
+            Some(parent_path) => {
+                let parent_type = self.field_type(type_id, parent_path)?;
+                (parent_type, self.get_field_mut(type_id, component, parent_path)?)
+            }
+            None => (*type_id, component),
+        };
+
+        let info = self.get(&parent_type).ok_or(ReflectError::UnknownType(parent_type))?;
+        info.field_or_err(field)?.set(parent, value)
+    }
+
+    fn field_type(&self, type_id: &TypeId, path: &str) -> Result<TypeId, ReflectError> {
+        let mut info = self.get(type_id).ok_or(ReflectError::UnknownType(*type_id))?;
+        let mut segments = path.split('.').peekable();
+
+        while let Some(segment) = segments.next() {
+            let field = info.field_or_err(segment)?;
+            if segments.peek().is_none() {
+                return Ok(field.type_id);
+            }
+            info = self.info_or_err(&field.type_id, field.type_name)?;
+        }
+
+        Ok(*type_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::Component;
+
+    #[derive(Component, Debug, PartialEq)]
+    struct Position(f32, f32);
+
+    #[derive(Component, Debug)]
+    struct Player {
+        name: String,
+        position: Position,
+    }
+
+    fn player() -> Player {
+        Player {
+            name: "player".to_string(),
+            position: Position(1.0, 2.0),
+        }
+    }
+
+    #[test]
+    fn component_info() {
+        let info = Player::component_info();
+
+        assert_eq!(info.size(), std::mem::size_of::<Player>());
+        assert_eq!(
+            info.fields().iter().map(FieldInfo::name).collect::<Vec<_>>(),
+            vec!["name", "position"]
+        );
+        assert_eq!(info.field("position").unwrap().type_id(), TypeId::of::<Position>());
+    }
+
+    #[test]
+    fn get_set_field_by_path() {
+        let mut registry = ComponentRegistry::new();
+        registry.register::<Player>();
+        registry.register::<Position>();
+
+        let mut player = player();
+        let id = TypeId::of::<Player>();
+
+        let name = registry.get_field(&id, &player, "name").unwrap();
+        assert_eq!(name.downcast_ref::<String>().unwrap(), "player");
+
+        registry
+            .set_field(&id, &mut player, "position.1", Box::new(5.0f32))
+            .unwrap();
+        assert_eq!(player.position, Position(1.0, 5.0));
+
+        let x = registry.get_field_mut(&id, &mut player, "position.0").unwrap();
+        *x.downcast_mut::<f32>().unwrap() = 3.0;
+        assert_eq!(player.position, Position(3.0, 5.0));
+    }
+
+    #[test]
+    fn field_errors() {
+        let mut registry = ComponentRegistry::new();
+        registry.register::<Player>();
+
+        let mut player = player();
+        let id = TypeId::of::<Player>();
+
+        assert!(matches!(
+            registry.get_field(&id, &player, "health"),
+            Err(ReflectError::UnknownField { .. })
+        ));
+        assert!(matches!(
+            registry.get_field(&id, &player, "position.0"),
+            Err(ReflectError::NotReflected { .. })
+        ));
+        assert!(matches!(
+            registry.set_field(&id, &mut player, "name", Box::new(4u32)),
+            Err(ReflectError::TypeMismatch { .. })
+        ));
+    }
+}
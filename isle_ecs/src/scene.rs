@@ -0,0 +1,373 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    path::Path,
+};
+
+use hashbrown::HashMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{component::Component, entity::Entity, world::World};
+
+#[derive(Clone, Copy)]
+pub struct SceneSerde {
+    serialize: fn(&dyn Any) -> serde_json::Result<Value>,
+    deserialize: fn(Value) -> serde_json::Result<Box<dyn Any>>,
+}
+
+impl SceneSerde {
+    pub fn new<T: Serialize + DeserializeOwned + 'static>() -> Self {
+        Self {
+            serialize: serialize_value::<T>,
+            deserialize: deserialize_value::<T>,
+        }
+    }
+
+    pub fn serialize(&self, value: &dyn Any) -> serde_json::Result<Value> {
+        (self.serialize)(value)
+    }
+
+    pub fn deserialize(&self, value: Value) -> serde_json::Result<Box<dyn Any>> {
+        (self.deserialize)(value)
+    }
+}
+
+fn serialize_value<T: Serialize + 'static>(value: &dyn Any) -> serde_json::Result<Value> {
+    serde_json::to_value(value.downcast_ref::<T>().unwrap())
+}
+
+fn deserialize_value<T: DeserializeOwned + 'static>(value: Value) -> serde_json::Result<Box<dyn Any>> {
+    Ok(Box::new(serde_json::from_value::<T>(value)?))
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    UnknownComponent(String),
+    UnknownResource(String),
+    NotSerializable(&'static str),
+    MissingResource(&'static str),
+    MissingEntity(Entity),
+    /// A prefab override targets entity `index` of a prefab with `entities` entities
+    InvalidOverride { index: usize, entities: usize },
+    Format(serde_json::Error),
+    Io(std::io::Error),
+}
+
+impl std::error::Error for SceneError {}
+
+impl Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownComponent(name) => write!(
+                f,
+                "Unknown component type '{name}' in scene\nHint: register it with ComponentRegistry::register and add #[component(serialize)]"
+            ),
+            Self::UnknownResource(name) => write!(
+                f,
+                "Unknown resource type '{name}' in scene\nHint: register it with ComponentRegistry::register_resource"
+            ),
+            Self::NotSerializable(name) => write!(
+                f,
+                "Type {name} is not serializable\nHint: add #[component(serialize)] to its Component derive"
+            ),
+            Self::MissingResource(name) => write!(f, "Resource {name} not found in world"),
+            Self::MissingEntity(entity) => write!(f, "Entity {entity:?} not found in world"),
+            Self::InvalidOverride { index, entities } => write!(
+                f,
+                "Prefab override targets entity {index} but the prefab has {entities} entities\nHint: use the index returned by Prefab::add_entity"
+            ),
+            Self::Format(err) => write!(f, "Invalid scene data: {err}"),
+            Self::Io(err) => write!(f, "Could not access scene file: {err}"),
+        }
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Format(err)
+    }
+}
+
+impl From<std::io::Error> for SceneError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SceneEntity {
+    pub id: u32,
+    #[serde(default)]
+    pub components: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Scene {
+    #[serde(default)]
+    pub resources: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub entities: Vec<SceneEntity>,
+}
+
+impl Scene {
+    pub fn to_json(&self) -> Result<String, SceneError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, SceneError> {
+        Ok(serde_json::from_str(data)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SceneError> {
+        Ok(std::fs::write(path, self.to_json()?)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Spawns the scene into `world` using freshly allocated entities
+    ///
+    /// Returns the mapping from scene entity ids to the spawned entities
+    pub fn write_to_world(&self, world: &mut World) -> Result<HashMap<u32, Entity>, SceneError> {
+        let registry = world.registry();
+
+        let resources = self
+            .resources
+            .iter()
+            .map(|(name, value)| {
+                let info = registry
+                    .get_resource_by_name(name)
+                    .ok_or_else(|| SceneError::UnknownResource(name.clone()))?;
+                let serde = info.serde().ok_or(SceneError::NotSerializable(info.name()))?;
+                Ok((info.type_id(), serde.deserialize(value.clone())?))
+            })
+            .collect::<Result<Vec<_>, SceneError>>()?;
+
+        let entities = self
+            .entities
+            .iter()
+            .map(|entity| {
+                let components = entity
+                    .components
+                    .iter()
+                    .map(|(name, value)| {
+                        let info = registry
+                            .get_by_name(name)
+                            .ok_or_else(|| SceneError::UnknownComponent(name.clone()))?;
+                        let serde = info.serde().ok_or(SceneError::NotSerializable(info.name()))?;
+                        Ok((info.type_id(), serde.deserialize(value.clone())?))
+                    })
+                    .collect::<Result<Vec<_>, SceneError>>()?;
+                Ok((entity.id, components))
+            })
+            .collect::<Result<Vec<_>, SceneError>>()?;
+
+        resources.into_iter().for_each(|(type_id, resource)| {
+            world.store_resource_by_id(type_id, resource);
+        });
+
+        let mut entity_map = HashMap::new();
+        entities.into_iter().for_each(|(id, components)| {
+            let entity = *entity_map.entry(id).or_insert_with(|| world.make_entity());
+            components.into_iter().for_each(|(type_id, component)| {
+                world.store_component_by_id(entity, type_id, component);
+            });
+        });
+
+        Ok(entity_map)
+    }
+}
+
+pub struct SceneBuilder<'w> {
+    world: &'w World,
+    entities: Vec<Entity>,
+    components: Option<HashSet<TypeId>>,
+    resources: Vec<(TypeId, &'static str)>,
+}
+
+impl<'w> SceneBuilder<'w> {
+    pub fn new(world: &'w World) -> Self {
+        Self {
+            world,
+            entities: Vec::new(),
+            components: None,
+            resources: Vec::new(),
+        }
+    }
+
+    pub fn with_entity(mut self, entity: Entity) -> Self {
+        self.entities.push(entity);
+        self
+    }
+
+    pub fn with_entities(mut self, entities: impl IntoIterator<Item = Entity>) -> Self {
+        self.entities.extend(entities);
+        self
+    }
+
+    pub fn with_all_entities(mut self) -> Self {
+        self.entities.extend(self.world.entities().copied());
+        self
+    }
+
+    /// Restricts the scene to the given component types, by default every serializable component is saved
+    pub fn with_component<T: Component>(mut self) -> Self {
+        self.components
+            .get_or_insert_with(HashSet::new)
+            .insert(TypeId::of::<T>());
+        self
+    }
+
+    pub fn with_resource<T: 'static>(mut self) -> Self {
+        self.resources.push((TypeId::of::<T>(), type_name::<T>()));
+        self
+    }
+
+    pub fn build(self) -> Result<Scene, SceneError> {
+        let registry = self.world.registry();
+
+        let resources = self
+            .resources
+            .iter()
+            .map(|(type_id, name)| {
+                let info = registry
+                    .get_resource(type_id)
+                    .filter(|info| info.serde().is_some())
+                    .ok_or(SceneError::NotSerializable(name))?;
+                let resource = self
+                    .world
+                    .get_resource_by_id(type_id)
+                    .ok_or(SceneError::MissingResource(info.name()))?;
+                Ok((info.name().to_string(), info.serde().unwrap().serialize(resource)?))
+            })
+            .collect::<Result<BTreeMap<_, _>, SceneError>>()?;
+
+        let entities = self
+            .entities
+            .iter()
+            .enumerate()
+            .map(|(id, entity)| {
+                let components = self
+                    .world
+                    .try_get_entity_components(entity)
+                    .ok_or(SceneError::MissingEntity(*entity))?
+                    .iter()
+                    .filter(|type_id| match &self.components {
+                        Some(components) => components.contains(type_id),
+                        None => registry.get(type_id).is_some_and(|info| info.serde().is_some()),
+                    })
+                    .map(|type_id| {
+                        let info = registry.get(type_id).unwrap();
+                        let serde = info.serde().ok_or(SceneError::NotSerializable(info.name()))?;
+                        let component = self.world.get_component_by_id(entity, type_id).unwrap();
+                        Ok((info.name().to_string(), serde.serialize(component)?))
+                    })
+                    .collect::<Result<BTreeMap<_, _>, SceneError>>()?;
+
+                Ok(SceneEntity {
+                    id: id as u32,
+                    components,
+                })
+            })
+            .collect::<Result<Vec<_>, SceneError>>()?;
+
+        Ok(Scene {
+            resources,
+            entities,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::Component;
+
+    #[derive(Component, Serialize, Deserialize, Debug, PartialEq)]
+    #[component(serialize)]
+    struct Health(u32);
+
+    #[derive(Component, Serialize, Deserialize, Debug, PartialEq)]
+    #[component(serialize)]
+    struct Name {
+        name: String,
+    }
+
+    #[derive(Component)]
+    struct Selected;
+
+    #[derive(Component, Serialize, Deserialize, Debug, PartialEq)]
+    #[component(serialize, storage = "sparse")]
+    struct Marker(u8);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Score(u64);
+
+    fn make_world() -> World {
+        let mut world = World::new();
+        world.registry_mut().register::<Health>();
+        world.registry_mut().register::<Name>();
+        world.registry_mut().register::<Marker>();
+        world.registry_mut().register_resource::<Score>();
+        world
+    }
+
+    #[test]
+    fn scene_round_trip() {
+        let mut world = make_world();
+        world.store_resource(Score(12));
+
+        let player = world.make_entity();
+        world.store_component(player, Health(100));
+        world.store_component(player, Name { name: "player".to_string() });
+        world.store_component(player, Selected);
+
+        let scene = SceneBuilder::new(&world)
+            .with_entity(player)
+            .with_resource::<Score>()
+            .build()
+            .unwrap();
+
+        assert_eq!(scene.entities[0].components.len(), 2);
+
+        let scene = Scene::from_json(&scene.to_json().unwrap()).unwrap();
+
+        let mut loaded = make_world();
+        let entities = scene.write_to_world(&mut loaded).unwrap();
+        let entity = entities[&0];
+
+        assert_eq!(loaded.get_component::<Health>(&entity), Some(&Health(100)));
+        assert_eq!(loaded.get_component::<Name>(&entity).unwrap().name, "player");
+        assert_eq!(loaded.get_resource::<Score>(), Some(&Score(12)));
+    }
+
+    #[test]
+    fn scene_sparse_only_entity() {
+        let mut world = make_world();
+        let marked = world.make_entity();
+        world.store_component(marked, Marker(3));
+
+        let scene = SceneBuilder::new(&world).with_all_entities().build().unwrap();
+        assert_eq!(scene.entities.len(), 1);
+
+        let mut loaded = make_world();
+        let entities = scene.write_to_world(&mut loaded).unwrap();
+        assert_eq!(loaded.get_component::<Marker>(&entities[&0]), Some(&Marker(3)));
+        assert_eq!(loaded.entities().count(), 1);
+    }
+
+    #[test]
+    fn scene_unknown_component() {
+        let scene = Scene::from_json(r#"{ "entities": [{ "id": 0, "components": { "game::Missing": null } }] }"#)
+            .unwrap();
+
+        let mut world = make_world();
+        let err = scene.write_to_world(&mut world).unwrap_err();
+
+        assert!(matches!(err, SceneError::UnknownComponent(name) if name == "game::Missing"));
+        assert_eq!(world.entities().count(), 0);
+    }
+}
@@ -1,25 +1,40 @@
 use std::{
     any::{Any, TypeId},
     collections::HashSet,
-    sync::mpsc::{Receiver, Sender},
+    sync::{
+        mpsc::{Receiver, Sender},
+        Arc,
+    },
 };
 
 type EntityEvents = EventWriter<EntityEvent>;
 
 pub mod event;
+pub mod snapshot;
+pub mod sparse_set;
 
 use event::EntityEvent;
+use sparse_set::SparseSet;
 use hashbrown::HashMap;
 use isle_event::EventWriter;
 
-use crate::{component::Component, entity::Entity};
+use crate::{
+    component::{Component, StorageType},
+    entity::{DefaultEntityFactory, Entity, EntityFactory},
+    prefab::{PrefabOverrides, Prefabs, SceneHandle},
+    registry::ComponentRegistry,
+    scene::SceneError,
+};
 
 pub type Command = Box<dyn FnOnce(&mut World)>;
 
 pub struct World {
     components: HashMap<TypeId, HashMap<Entity, Box<dyn Any>>>,
+    sparse: HashMap<TypeId, SparseSet>,
     resources: HashMap<TypeId, Box<dyn Any>>,
     entities: HashMap<Entity, HashSet<TypeId>>,
+    registry: ComponentRegistry,
+    entity_factory: Arc<DefaultEntityFactory>,
     command_sender: Sender<Command>,
     command_receiver: Receiver<Command>,
 }
@@ -29,13 +44,17 @@ impl World {
         let (command_sender, command_receiver) = std::sync::mpsc::channel();
         let mut world = Self {
             components: HashMap::new(),
+            sparse: HashMap::new(),
             resources: HashMap::new(),
             entities: HashMap::new(),
+            registry: ComponentRegistry::new(),
+            entity_factory: Arc::new(DefaultEntityFactory::new()),
             command_sender,
             command_receiver,
         };
 
         world.store_resource(EntityEvents::new());
+        world.store_resource(Prefabs::default());
 
         world
     }
@@ -44,10 +63,55 @@ impl World {
         &self.command_sender
     }
 
+    pub fn registry(&self) -> &ComponentRegistry {
+        &self.registry
+    }
+
+    pub fn registry_mut(&mut self) -> &mut ComponentRegistry {
+        &mut self.registry
+    }
+
+    pub fn make_entity(&self) -> Entity {
+        self.entity_factory.make_entity()
+    }
+
+    pub fn entity_factory(&self) -> &Arc<DefaultEntityFactory> {
+        &self.entity_factory
+    }
+
+    /// Instantiates a prefab from the `Prefabs` resource and returns its root entity
+    pub fn spawn_scene(&mut self, handle: SceneHandle, overrides: PrefabOverrides) -> Result<Entity, SceneError> {
+        let root = self.make_entity();
+        self.spawn_scene_at(root, handle, overrides)?;
+        Ok(root)
+    }
+
+    pub(crate) fn spawn_scene_at(
+        &mut self,
+        root: Entity,
+        handle: SceneHandle,
+        overrides: PrefabOverrides,
+    ) -> Result<Vec<Entity>, SceneError> {
+        let prefab = self
+            .get_resource::<Prefabs>()
+            .and_then(|prefabs| prefabs.get_shared(handle))
+            .unwrap_or_else(|| panic!("Invalid scene handle {handle:?}"));
+
+        prefab.spawn(self, handle, root, overrides)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.keys()
+    }
+
     pub fn store_resource<T: 'static>(&mut self, resource: T) {
         self.resources.insert(TypeId::of::<T>(), Box::new(resource));
     }
 
+    pub fn store_resource_by_id(&mut self, type_id: TypeId, resource: Box<dyn Any>) {
+        self.resources.insert(type_id, resource);
+    }
+
     pub fn get_resource<T: 'static>(&self) -> Option<&T> {
         self.resources.get(&TypeId::of::<T>())?.downcast_ref::<T>()
     }
@@ -73,31 +137,142 @@ impl World {
     }
 
     pub fn store_component<T: Component>(&mut self, entity: Entity, component: T) {
+        if !self.registry.contains(&TypeId::of::<T>()) {
+            self.registry.register::<T>();
+        }
+
+        self.store_component_by_id(entity, TypeId::of::<T>(), Box::new(component));
+    }
+
+    /// Stores a type erased component, `type_id` must match the boxed value's type
+    ///
+    /// Sparse types are only tracked by their `SparseSet`, an older generation of `entity`
+    /// still holding the slot loses the component first
+    pub fn store_component_by_id(&mut self, entity: Entity, type_id: TypeId, component: Box<dyn Any>) {
         let mut events = self.get_resource::<EntityEvents>().cloned().unwrap();
-        self.components
-            .entry(TypeId::of::<T>())
-            .or_insert_with(HashMap::new)
-            .insert(entity, Box::new(component));
+        let sparse = self.storage_type(&type_id) == StorageType::Sparse;
+        if sparse {
+            let stale = self
+                .sparse
+                .get(&type_id)
+                .and_then(|set| set.occupant(entity.1))
+                .filter(|occupant| *occupant != entity);
+            if let Some(stale) = stale {
+                self.remove_component_by_id(&stale, &type_id);
+            }
+
+            self.sparse
+                .entry(type_id)
+                .or_insert_with(SparseSet::new)
+                .insert(entity, component);
+        } else {
+            self.components
+                .entry(type_id)
+                .or_insert_with(HashMap::new)
+                .insert(entity, component);
+        }
 
-        self.entities
-            .entry(entity)
-            .or_insert_with(|| {
-                events.send(EntityEvent::Created(entity));
-                HashSet::new()
-            })
-            .insert(TypeId::of::<T>());
+        let types = self.entities.entry(entity).or_insert_with(|| {
+            events.send(EntityEvent::Created(entity));
+            HashSet::new()
+        });
+        if !sparse {
+            types.insert(type_id);
+        }
+
+        events.send(EntityEvent::ComponentAdded(entity, type_id));
+    }
+
+    pub fn remove_component<T: Component>(&mut self, entity: &Entity) -> Option<T> {
+        self.remove_component_by_id(entity, &TypeId::of::<T>())?
+            .downcast()
+            .ok()
+            .map(|component| *component)
+    }
+
+    /// Entities left without components are dropped from `entities`
+    pub fn remove_component_by_id(&mut self, entity: &Entity, type_id: &TypeId) -> Option<Box<dyn Any>> {
+        let component = match self.sparse.get_mut(type_id) {
+            Some(sparse) => sparse.remove(entity)?,
+            None => self.components.get_mut(type_id)?.remove(entity)?,
+        };
+
+        let mut events = self.get_resource::<EntityEvents>().cloned().unwrap();
+        events.send(EntityEvent::ComponentRemoved(*entity, *type_id));
+
+        if let Some(types) = self.entities.get_mut(entity) {
+            types.remove(type_id);
+        }
+        if self.destroy_if_empty(entity) {
+            events.send(EntityEvent::Destroyed(*entity));
+        }
+
+        Some(component)
+    }
+
+    /// Drops `entity` from `entities` once neither dense nor sparse storage holds it
+    fn destroy_if_empty(&mut self, entity: &Entity) -> bool {
+        let empty = self.entities.get(entity).is_some_and(HashSet::is_empty)
+            && !self.sparse.values().any(|set| set.contains(entity));
+        if empty {
+            self.entities.remove(entity);
+        }
 
-        events.send(EntityEvent::ComponentAdded(entity, TypeId::of::<T>()));
+        empty
+    }
+
+    pub fn has_component_by_id(&self, entity: &Entity, type_id: &TypeId) -> bool {
+        match self.sparse.get(type_id) {
+            Some(sparse) => sparse.contains(entity),
+            None => self
+                .components
+                .get(type_id)
+                .is_some_and(|components| components.contains_key(entity)),
+        }
+    }
+
+    fn storage_type(&self, type_id: &TypeId) -> StorageType {
+        self.registry
+            .get(type_id)
+            .map_or(StorageType::Dense, |info| info.storage())
     }
 
     pub fn get_component<T: Component>(&self, entity: &Entity) -> Option<&T> {
-        self.components
-            .get(&TypeId::of::<T>())?
-            .get(entity)?
+        self.get_component_by_id(entity, &TypeId::of::<T>())?
             .downcast_ref::<T>()
     }
 
+    pub fn get_component_by_id(&self, entity: &Entity, type_id: &TypeId) -> Option<&dyn Any> {
+        if let Some(sparse) = self.sparse.get(type_id) {
+            return sparse.get(entity);
+        }
+
+        self.components
+            .get(type_id)?
+            .get(entity)
+            .map(Box::as_ref)
+    }
+
+    pub fn get_component_by_id_mut(
+        &mut self,
+        entity: &Entity,
+        type_id: &TypeId,
+    ) -> Option<&mut dyn Any> {
+        if let Some(sparse) = self.sparse.get_mut(type_id) {
+            return sparse.get_mut(entity);
+        }
+
+        self.components
+            .get_mut(type_id)?
+            .get_mut(entity)
+            .map(Box::as_mut)
+    }
+
     pub fn get_entities_with_component(&mut self, type_id: &TypeId) -> Vec<Entity> {
+        if let Some(sparse) = self.sparse.get(type_id) {
+            return sparse.entities().to_vec();
+        }
+
         self.components
             .entry(*type_id)
             .or_insert_with(HashMap::new)
@@ -107,10 +282,26 @@ impl World {
     }
 
     pub fn get_entity_components(&self, entity: &Entity) -> HashSet<TypeId> {
-        self.entities.get(entity).unwrap().clone()
+        self.try_get_entity_components(entity).unwrap()
+    }
+
+    pub fn try_get_entity_components(&self, entity: &Entity) -> Option<HashSet<TypeId>> {
+        let mut types = self.entities.get(entity)?.clone();
+        types.extend(
+            self.sparse
+                .iter()
+                .filter(|(_, set)| set.contains(entity))
+                .map(|(type_id, _)| *type_id),
+        );
+
+        Some(types)
     }
 
     pub fn get_components_by_id(&self, type_id: &TypeId) -> Option<Vec<&dyn Any>> {
+        if let Some(sparse) = self.sparse.get(type_id) {
+            return Some(sparse.iter().map(|(_, component)| component.as_ref()).collect());
+        }
+
         Some(self.components.get(type_id)?.values().map(Box::as_ref).collect())
     }
 
@@ -120,6 +311,10 @@ impl World {
         &mut self,
         entity: &Entity,
     ) -> Option<&mut T> {
+        if T::STORAGE == StorageType::Sparse {
+            return self.sparse.get_mut(&TypeId::of::<T>())?.get_mut(entity)?.downcast_mut::<T>();
+        }
+
         self.components
             .get_mut(&TypeId::of::<T>())?
             .get_many_unchecked_mut([entity])?[0]
@@ -127,6 +322,10 @@ impl World {
     }
 
     pub fn get_components_by_id_mut(&mut self, type_id: &TypeId) -> Option<Vec<&mut dyn Any>> {
+        if let Some(sparse) = self.sparse.get_mut(type_id) {
+            return Some(sparse.values_mut().map(Box::as_mut).collect());
+        }
+
         Some(
             self.components
                 .get_mut(type_id)?
@@ -146,6 +345,10 @@ impl World {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        ecs::SystemParam,
+        query::{Query, With, Without},
+    };
 
     impl Component for u32 {}
     impl Component for u8 {}
@@ -219,7 +422,7 @@ mod tests {
 
         world.store_component(Entity(0, 0), val);
 
-        let val = world.get_component(&Entity(0, 0)).unwrap();
+        let val = world.get_component::<u32>(&Entity(0, 0)).unwrap();
 
         assert_eq!(47u32, *val);
     }
@@ -233,8 +436,8 @@ mod tests {
         world.store_component(Entity(0, 0), val1);
         world.store_component(Entity(0, 1), val2);
 
-        let val1 = world.get_component(&Entity(0, 0)).unwrap();
-        let val2 = world.get_component(&Entity(0, 1)).unwrap();
+        let val1 = world.get_component::<u32>(&Entity(0, 0)).unwrap();
+        let val2 = world.get_component::<u8>(&Entity(0, 1)).unwrap();
 
         assert_eq!(47u32, *val1);
         assert_eq!(64u8, *val2);
@@ -247,10 +450,10 @@ mod tests {
 
         world.store_component(Entity(0, 0), val);
 
-        let val = unsafe { world.get_component_mut(&Entity(0, 0)) }.unwrap();
+        let val = unsafe { world.get_component_mut::<u32>(&Entity(0, 0)) }.unwrap();
         *val = 42u32;
 
-        let val = world.get_component(&Entity(0, 0)).unwrap();
+        let val = world.get_component::<u32>(&Entity(0, 0)).unwrap();
 
         assert_eq!(42u32, *val);
     }
@@ -264,38 +467,101 @@ mod tests {
         world.store_component(Entity(0, 0), val1);
         world.store_component(Entity(0, 1), val2);
 
-        let val1 = unsafe { world.get_component_mut(&Entity(0, 0)) }.unwrap();
+        let val1 = unsafe { world.get_component_mut::<u32>(&Entity(0, 0)) }.unwrap();
         *val1 = 42u32;
 
-        let val2 = unsafe { world.get_component_mut(&Entity(0, 1)) }.unwrap();
+        let val2 = unsafe { world.get_component_mut::<u8>(&Entity(0, 1)) }.unwrap();
         *val2 = 54u8;
 
-        let val1 = world.get_component(&Entity(0, 0)).unwrap();
-        let val2 = world.get_component(&Entity(0, 1)).unwrap();
+        let val1 = world.get_component::<u32>(&Entity(0, 0)).unwrap();
+        let val2 = world.get_component::<u8>(&Entity(0, 1)).unwrap();
 
         assert_eq!(42u32, *val1);
         assert_eq!(54u8, *val2);
     }
-}
 
-/// For isle engine internal use
-pub trait AssetManagerExt {
-    fn get_res_and_components(&mut self, resource_id: &TypeId, component_id: &TypeId) -> Option<(&mut dyn Any, Vec<&mut dyn Any>)>;
-}
+    #[test]
+    fn component_reflection() {
+        let mut world = World::new();
 
-impl AssetManagerExt for World {
-    fn get_res_and_components(&mut self, resource_id: &TypeId, component_id: &TypeId) -> Option<(&mut dyn Any, Vec<&mut dyn Any>)> {
-        let World{
-            ref mut resources,
-            ref mut components,
-            ..
-        } = *self;
+        world.store_component(Entity(0, 0), 47u32);
+        world.store_component(Entity(0, 0), 64u8);
 
-        Some(
-            (
-                resources.get_mut(resource_id)?.as_mut(),
-                components.get_mut(component_id)?.values_mut().map(|v| v.as_mut()).collect()
-            )
-        )
+        let mut names: Vec<_> = world
+            .get_entity_components(&Entity(0, 0))
+            .iter()
+            .map(|type_id| world.registry().get(type_id).unwrap().name())
+            .collect();
+        names.sort();
+
+        assert_eq!(names, vec!["u32", "u8"]);
+
+        let val = world
+            .get_component_by_id_mut(&Entity(0, 0), &TypeId::of::<u32>())
+            .unwrap();
+        *val.downcast_mut::<u32>().unwrap() = 42;
+
+        assert_eq!(42u32, *world.get_component::<u32>(&Entity(0, 0)).unwrap());
+    }
+
+    #[derive(isle_ecs_macros::Component, Debug, PartialEq)]
+    #[component(storage = "sparse")]
+    struct Selected;
+
+    #[test]
+    fn sparse_storage() {
+        let mut world = World::new();
+
+        world.store_component(Entity(0, 0), 47u32);
+        world.store_component(Entity(0, 1), 64u32);
+        world.store_component(Entity(0, 0), Selected);
+
+        assert!(world.has_component_by_id(&Entity(0, 0), &TypeId::of::<Selected>()));
+        assert!(!world.has_component_by_id(&Entity(0, 1), &TypeId::of::<Selected>()));
+        assert_eq!(world.get_entity_components(&Entity(0, 0)).len(), 2);
+
+        let cell = std::cell::UnsafeCell::new(world);
+        let mut state = ();
+        let query = <Query<Entity, With<Selected>> as SystemParam>::from_world(&cell, &mut state, "");
+        assert_eq!(query.iter().collect::<Vec<_>>(), vec![Entity(0, 0)]);
+
+        let query = <Query<&u32, Without<Selected>> as SystemParam>::from_world(&cell, &mut state, "");
+        assert_eq!(query.iter().collect::<Vec<_>>(), vec![&64u32]);
+
+        let mut world = cell.into_inner();
+        assert_eq!(world.remove_component::<Selected>(&Entity(0, 0)), Some(Selected));
+        assert!(world.get_component::<Selected>(&Entity(0, 0)).is_none());
+        assert_eq!(world.get_entity_components(&Entity(0, 0)).len(), 1);
+    }
+
+    #[test]
+    fn sparse_only_entities() {
+        let mut world = World::new();
+
+        world.store_component(Entity(0, 0), Selected);
+        world.store_component(Entity(0, 1), 47u32);
+
+        let mut entities: Vec<Entity> = world.entities().copied().collect();
+        entities.sort_by_key(|entity| entity.1);
+        assert_eq!(entities, vec![Entity(0, 0), Entity(0, 1)]);
+        assert_eq!(world.get_entity_components(&Entity(0, 0)).len(), 1);
+
+        world.remove_component::<Selected>(&Entity(0, 0));
+        world.remove_component::<u32>(&Entity(0, 1));
+        assert_eq!(world.entities().count(), 0);
+        assert!(world.try_get_entity_components(&Entity(0, 1)).is_none());
+    }
+
+    #[test]
+    fn sparse_stores_evict_stale_generations() {
+        let mut world = World::new();
+
+        world.store_component(Entity(0, 3), Selected);
+        world.store_component(Entity(1, 3), Selected);
+
+        assert!(world.get_component::<Selected>(&Entity(0, 3)).is_none());
+        assert!(world.try_get_entity_components(&Entity(0, 3)).is_none());
+        assert_eq!(world.entities().copied().collect::<Vec<_>>(), vec![Entity(1, 3)]);
+        assert_eq!(world.get_component::<Selected>(&Entity(1, 3)), Some(&Selected));
     }
 }
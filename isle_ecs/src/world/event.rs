@@ -6,5 +6,6 @@ use crate::entity::Entity;
 pub enum EntityEvent {
     Created(Entity),
     ComponentAdded(Entity, TypeId),
+    ComponentRemoved(Entity, TypeId),
     Destroyed(Entity),
 }
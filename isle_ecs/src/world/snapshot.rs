@@ -0,0 +1,331 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashSet,
+};
+
+use hashbrown::HashMap;
+
+use crate::{
+    component::{Component, StorageType},
+    entity::Entity,
+};
+
+use super::{event::EntityEvent, sparse_set::SparseSet, EntityEvents, World};
+
+#[derive(Clone, Copy)]
+pub struct SnapshotClone {
+    clone: fn(&dyn Any) -> Box<dyn Any>,
+}
+
+impl SnapshotClone {
+    pub fn new<T: Clone + 'static>() -> Self {
+        Self {
+            clone: clone_value::<T>,
+        }
+    }
+
+    pub fn clone(&self, value: &dyn Any) -> Box<dyn Any> {
+        (self.clone)(value)
+    }
+}
+
+fn clone_value<T: Clone + 'static>(value: &dyn Any) -> Box<dyn Any> {
+    Box::new(value.downcast_ref::<T>().unwrap().clone())
+}
+
+struct SnapshotComponents {
+    clone: SnapshotClone,
+    components: HashMap<Entity, Box<dyn Any>>,
+}
+
+impl Clone for SnapshotComponents {
+    fn clone(&self) -> Self {
+        Self {
+            clone: self.clone,
+            components: self
+                .components
+                .iter()
+                .map(|(entity, component)| (*entity, self.clone.clone(component.as_ref())))
+                .collect(),
+        }
+    }
+}
+
+struct SnapshotResource {
+    clone: SnapshotClone,
+    resource: Box<dyn Any>,
+}
+
+impl Clone for SnapshotResource {
+    fn clone(&self) -> Self {
+        Self {
+            clone: self.clone,
+            resource: self.clone.clone(self.resource.as_ref()),
+        }
+    }
+}
+
+/// Owned copy of every component and resource registered with a `SnapshotClone`
+#[derive(Clone)]
+pub struct WorldSnapshot {
+    components: HashMap<TypeId, SnapshotComponents>,
+    resources: HashMap<TypeId, SnapshotResource>,
+    entities: HashSet<Entity>,
+    entity_state: (u32, u32),
+}
+
+impl WorldSnapshot {
+    pub fn get_component<T: Component>(&self, entity: &Entity) -> Option<&T> {
+        self.components
+            .get(&TypeId::of::<T>())?
+            .components
+            .get(entity)?
+            .downcast_ref::<T>()
+    }
+
+    pub fn get_resource<T: 'static>(&self) -> Option<&T> {
+        self.resources
+            .get(&TypeId::of::<T>())?
+            .resource
+            .downcast_ref::<T>()
+    }
+}
+
+impl World {
+    /// Captures components marked `#[component(snapshot)]` and resources registered
+    /// with `ComponentRegistry::register_resource_snapshot`
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let components = self
+            .registry
+            .iter()
+            .filter_map(|info| Some((info.type_id(), *info.snapshot()?)))
+            .map(|(type_id, clone)| {
+                let copy = |(entity, component): (&Entity, &Box<dyn Any>)| {
+                    (*entity, clone.clone(component.as_ref()))
+                };
+                let components = match self.sparse.get(&type_id) {
+                    Some(sparse) => sparse.iter().map(copy).collect(),
+                    None => self.components.get(&type_id).into_iter().flatten().map(copy).collect(),
+                };
+                (type_id, SnapshotComponents { clone, components })
+            })
+            .collect();
+
+        let resources = self
+            .registry
+            .iter_resources()
+            .filter_map(|info| Some((info.type_id(), *info.snapshot()?)))
+            .filter_map(|(type_id, clone)| {
+                let resource = self.resources.get(&type_id)?;
+                Some((
+                    type_id,
+                    SnapshotResource {
+                        clone,
+                        resource: clone.clone(resource.as_ref()),
+                    },
+                ))
+            })
+            .collect();
+
+        WorldSnapshot {
+            components,
+            resources,
+            entities: self.entities.keys().copied().collect(),
+            entity_state: self.entity_factory.state(),
+        }
+    }
+
+    /// Rewinds every snapshotted component and resource to the state in `snapshot`
+    ///
+    /// Entities spawned since are despawned with all of their components so their ids can be
+    /// handed out again. Snapshotted components added since are removed, entities left without
+    /// components are destroyed
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) {
+        let spawned: Vec<Entity> = self
+            .entities
+            .keys()
+            .filter(|entity| !snapshot.entities.contains(*entity))
+            .copied()
+            .collect();
+        spawned.iter().for_each(|entity| {
+            self.get_entity_components(entity).iter().for_each(|type_id| {
+                self.remove_component_by_id(entity, type_id);
+            });
+        });
+
+        let mut events = self.get_resource::<EntityEvents>().cloned().unwrap();
+        let mut emptied = HashSet::new();
+
+        let types: HashSet<TypeId> = self
+            .registry
+            .iter()
+            .filter(|info| info.snapshot().is_some())
+            .map(|info| info.type_id())
+            .chain(snapshot.components.keys().copied())
+            .collect();
+
+        for type_id in types {
+            let new = snapshot
+                .components
+                .get(&type_id)
+                .map(|components| components.clone().components)
+                .unwrap_or_default();
+
+            let sparse = self.storage_type(&type_id) == StorageType::Sparse;
+            let old: HashSet<Entity> = if sparse {
+                self.sparse
+                    .remove(&type_id)
+                    .map(|set| set.entities().iter().copied().collect())
+            } else {
+                self.components.remove(&type_id).map(|components| components.into_keys().collect())
+            }
+            .unwrap_or_default();
+
+            old.iter()
+                .filter(|entity| !new.contains_key(*entity))
+                .for_each(|entity| {
+                    if let Some(types) = self.entities.get_mut(entity) {
+                        types.remove(&type_id);
+                        emptied.insert(*entity);
+                    }
+                });
+
+            new.keys()
+                .filter(|entity| !old.contains(*entity))
+                .for_each(|entity| {
+                    let types = self.entities.entry(*entity).or_insert_with(|| {
+                        events.send(EntityEvent::Created(*entity));
+                        HashSet::new()
+                    });
+                    if !sparse {
+                        types.insert(type_id);
+                    }
+                    events.send(EntityEvent::ComponentAdded(*entity, type_id));
+                });
+
+            if sparse {
+                let mut set = SparseSet::new();
+                new.into_iter().for_each(|(entity, component)| {
+                    set.insert(entity, component);
+                });
+                self.sparse.insert(type_id, set);
+            } else {
+                self.components.insert(type_id, new);
+            }
+        }
+
+        emptied.into_iter().for_each(|entity| {
+            if self.destroy_if_empty(&entity) {
+                events.send(EntityEvent::Destroyed(entity));
+            }
+        });
+
+        let resources: Vec<TypeId> = self
+            .registry
+            .iter_resources()
+            .filter(|info| info.snapshot().is_some())
+            .map(|info| info.type_id())
+            .collect();
+
+        resources.iter().for_each(|type_id| match snapshot.resources.get(type_id) {
+            Some(resource) => {
+                self.resources.insert(*type_id, resource.clone().resource);
+            }
+            None => {
+                self.resources.remove(type_id);
+            }
+        });
+
+        self.entity_factory.set_state(snapshot.entity_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::Component;
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(snapshot)]
+    struct Position(f32, f32);
+
+    #[derive(Component, Debug, PartialEq)]
+    struct Name(&'static str);
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Score(u32);
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    #[component(snapshot, storage = "sparse")]
+    struct Stunned(u8);
+
+    #[test]
+    fn snapshot_restore() {
+        let mut world = World::new();
+        world.registry_mut().register_resource_snapshot::<Score>();
+        world.store_resource(Score(1));
+
+        let player = world.make_entity();
+        world.store_component(player, Position(0.0, 0.0));
+        world.store_component(player, Name("player"));
+
+        let snapshot = world.snapshot();
+
+        unsafe { world.get_component_mut::<Position>(&player) }.unwrap().0 = 5.0;
+        world.store_resource(Score(2));
+        world.store_component(player, Name("renamed"));
+
+        let spawned = world.make_entity();
+        world.store_component(spawned, Position(1.0, 1.0));
+
+        world.restore(&snapshot);
+
+        assert_eq!(world.get_component::<Position>(&player), Some(&Position(0.0, 0.0)));
+        assert_eq!(world.get_component::<Name>(&player), Some(&Name("renamed")));
+        assert_eq!(world.get_resource::<Score>(), Some(&Score(1)));
+        assert!(world.try_get_entity_components(&spawned).is_none());
+        assert_eq!(world.make_entity(), spawned);
+        assert_eq!(snapshot.get_component::<Position>(&player), Some(&Position(0.0, 0.0)));
+    }
+
+    #[test]
+    fn snapshot_restore_sparse_only() {
+        let mut world = World::new();
+        let stunned = world.make_entity();
+        world.store_component(stunned, Stunned(2));
+
+        let snapshot = world.snapshot();
+        world.remove_component::<Stunned>(&stunned);
+        assert_eq!(world.entities().count(), 0);
+
+        let spawned = world.make_entity();
+        world.store_component(spawned, Stunned(1));
+
+        world.restore(&snapshot);
+        assert_eq!(world.entities().copied().collect::<Vec<_>>(), vec![stunned]);
+        assert_eq!(world.get_component::<Stunned>(&stunned), Some(&Stunned(2)));
+        assert!(world.get_component::<Stunned>(&spawned).is_none());
+    }
+
+    #[test]
+    fn restore_despawns_entities_spawned_since() {
+        let mut world = World::new();
+        let player = world.make_entity();
+        world.store_component(player, Name("player"));
+
+        let snapshot = world.snapshot();
+        let spawned = world.make_entity();
+        world.store_component(spawned, Name("spawned"));
+        world.store_component(spawned, Stunned(1));
+
+        world.restore(&snapshot);
+        assert_eq!(world.entities().copied().collect::<Vec<_>>(), vec![player]);
+        assert_eq!(world.get_component::<Name>(&player), Some(&Name("player")));
+
+        let reused = world.make_entity();
+        assert_eq!(reused, spawned);
+        assert!(world.try_get_entity_components(&reused).is_none());
+        assert!(world.get_component::<Name>(&reused).is_none());
+        assert!(world.get_component::<Stunned>(&reused).is_none());
+    }
+}
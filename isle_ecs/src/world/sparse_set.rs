@@ -0,0 +1,98 @@
+use std::any::Any;
+
+use crate::entity::Entity;
+
+/// Component storage indexed directly by entity id, values are packed for iteration
+#[derive(Default)]
+pub struct SparseSet {
+    sparse: Vec<Option<usize>>,
+    entities: Vec<Entity>,
+    values: Vec<Box<dyn Any>>,
+}
+
+impl SparseSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    fn index(&self, entity: &Entity) -> Option<usize> {
+        let index = (*self.sparse.get(entity.1 as usize)?)?;
+        (self.entities[index] == *entity).then_some(index)
+    }
+
+    pub fn contains(&self, entity: &Entity) -> bool {
+        self.index(entity).is_some()
+    }
+
+    /// The entity of any generation holding the slot for `index`
+    pub fn occupant(&self, index: u32) -> Option<Entity> {
+        let index = (*self.sparse.get(index as usize)?)?;
+        Some(self.entities[index])
+    }
+
+    /// Returns the replaced value when `entity` already had one
+    ///
+    /// Panics if another generation of `entity` holds its slot, remove that one first
+    pub fn insert(&mut self, entity: Entity, value: Box<dyn Any>) -> Option<Box<dyn Any>> {
+        let slot = entity.1 as usize;
+        if slot >= self.sparse.len() {
+            self.sparse.resize(slot + 1, None);
+        }
+
+        match self.sparse[slot] {
+            Some(index) => {
+                let occupant = self.entities[index];
+                assert!(occupant == entity, "{occupant:?} still holds the slot of {entity:?}");
+                Some(std::mem::replace(&mut self.values[index], value))
+            }
+            None => {
+                self.sparse[slot] = Some(self.entities.len());
+                self.entities.push(entity);
+                self.values.push(value);
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, entity: &Entity) -> Option<Box<dyn Any>> {
+        let index = self.index(entity)?;
+        self.sparse[entity.1 as usize] = None;
+
+        self.entities.swap_remove(index);
+        let value = self.values.swap_remove(index);
+
+        if let Some(moved) = self.entities.get(index) {
+            self.sparse[moved.1 as usize] = Some(index);
+        }
+
+        Some(value)
+    }
+
+    pub fn get(&self, entity: &Entity) -> Option<&dyn Any> {
+        self.index(entity).map(|index| self.values[index].as_ref())
+    }
+
+    pub fn get_mut(&mut self, entity: &Entity) -> Option<&mut dyn Any> {
+        self.index(entity).map(|index| self.values[index].as_mut())
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Entity, &Box<dyn Any>)> {
+        self.entities.iter().zip(&self.values)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Any>> {
+        self.values.iter_mut()
+    }
+}
@@ -1,15 +1,19 @@
 use std::{
-    f32::consts::PI, io::Write, path::PathBuf, str::FromStr, time::{Instant, UNIX_EPOCH}
+    f32::consts::PI, time::UNIX_EPOCH
 };
 
 use geode::{
-    camera::CameraCreationSettings, geometry::Geometry, material, plugin::components::{Camera, Material, Mesh, SpotLight}, renderer::{self, Renderer}, texture::Texture
+    camera::CameraCreationSettings, geometry::Geometry, plugin::{assets::GpuTextures, components::{Camera, Material, Mesh, SpotLight}}, renderer::Renderer, texture::Texture
 };
 use isle::{isle_engine::{flow::stages, params::Tick}, prelude::*};
-use isle_ecs::command::WorldCommand;
+use isle_ecs::{
+    command::WorldCommand,
+    prefab::{Prefab, PrefabOverrides, Prefabs},
+};
 use isle_engine::{
+    asset::{AssetServer, Assets, Handle},
     input::{
-        define_axis_binding, define_binding, Axis, AxisMapping, Button, InputMap, Key, Mapping,
+        define_axis_binding, define_binding, Axis, Button, InputMap, Key,
     },
     params::{Event, EventTrigger, Input, InputAxis},
 };
@@ -34,6 +38,7 @@ fn main() {
 
     flow.add_resource(false);
     flow.add_resource(Vec3::ZERO);
+    flow.add_resource(None::<Handle<Texture>>);
 
     flow.push_system(setup);
     flow.push_system(update_light);
@@ -53,7 +58,16 @@ fn random_orientation() -> Quaternion {
     Quaternion(x, y, z, w).norm()
 }
 
-fn setup(renderer: Option<ResMut<Renderer>>, mut flow: WorldCommand, mut run: ResMut<bool>) {
+fn setup(
+    renderer: Option<ResMut<Renderer>>,
+    asset_server: Res<AssetServer>,
+    mut geometries: ResMut<Assets<Geometry>>,
+    gpu_textures: Res<GpuTextures>,
+    mut tree: ResMut<Option<Handle<Texture>>>,
+    mut prefabs: ResMut<Prefabs>,
+    mut flow: WorldCommand,
+    mut run: ResMut<bool>,
+) {
     if *run {
         return;
     }
@@ -63,18 +77,16 @@ fn setup(renderer: Option<ResMut<Renderer>>, mut flow: WorldCommand, mut run: Re
         None => return,
     };
 
-    let camera = Entity(0, 0);
+    let tree = tree.get_or_insert_with(|| asset_server.load("happy_tree.png"));
+    let Some(texture) = gpu_textures.get(tree) else {
+        return;
+    };
+
+    let camera = flow.make_entity();
     flow.add_component(camera, Camera::new(&CameraCreationSettings::default()));
 
     let cube_size = Vec3(100.0, 100.0, 100.0);
-    let cube = Geometry::cube(cube_size);
-    // cube.load_to_gpu(renderer.device());
-    let cube = renderer.add_geometry(cube);
-
-    let mut texture = Texture::new(&PathBuf::from_str("assets/happy_tree.png").unwrap());
-    texture.load_to_mem().unwrap();
-    texture.load_to_gpu(renderer.device(), renderer.queue());
-    let texture = renderer.add_texture(texture);
+    let cube = geometries.add(Geometry::cube(cube_size));
 
     let material = geode::material::Material::default_shader(&renderer);
     let material = renderer.add_material(material);
@@ -98,7 +110,11 @@ fn setup(renderer: Option<ResMut<Renderer>>, mut flow: WorldCommand, mut run: Re
     let start_x = -len_x / 2.0;
     let start_y = -len_y / 2.0;
 
-    let mut n = 0;
+    let cube = prefabs.add(
+        Prefab::new()
+            .with_component(Prefab::ROOT, Mesh::new(cube))
+            .with_component(Prefab::ROOT, Material::new(material, material_instance)),
+    );
 
     for x in 0..num_cubes_x {
         for y in 0..num_cubes_y {
@@ -108,16 +124,15 @@ fn setup(renderer: Option<ResMut<Renderer>>, mut flow: WorldCommand, mut run: Re
                 start_y + (y as f32 * y_span),
             );
 
-            let entity = Entity(1, n);
-            flow.add_component(entity, Mesh::new(cube));
-            flow.add_component(entity, Material::new(material, material_instance));
-            flow.add_component(entity, Transform::new(pos, random_orientation().into(), Vec3::IDENTITY));
-            n += 1;
+            flow.spawn_scene_with(
+                cube,
+                PrefabOverrides::new().with(Transform::new(pos, random_orientation().into(), Vec3::IDENTITY)),
+            );
         }
     }
 
     let position = Vec3(0.0, 500., -500.0);
-    let light = Entity(0, 1);
+    let light = flow.make_entity();
     flow.add_component(light, SpotLight::new(
         Vec3::IDENTITY,
         300.0,
@@ -1,8 +1,11 @@
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
+    bracketed,
     parse::{Parse, ParseStream},
-    BinOp, Expr, ExprBinary, Ident, Token,
+    parse_quote,
+    punctuated::Punctuated,
+    BinOp, Expr, ExprBinary, ExprPath, Ident, LitInt, Token,
 };
 
 #[proc_macro]
@@ -12,11 +15,17 @@ pub fn define_binding(input: TokenStream) -> TokenStream {
     let name = &binding_input.struct_name;
     let keys = &binding_input.keys;
     let buttons = &binding_input.buttons;
+    let mouse_buttons = &binding_input.mouse_buttons;
+    let chords = &binding_input.chords;
+    let sequence_steps = binding_input.sequences.iter().map(|sequence| &sequence.steps);
+    let sequence_windows = binding_input.sequences.iter().map(|sequence| sequence.window_ms);
 
     quote! {
         pub struct #name;
 
         impl isle_engine::input::Mapping for #name {
+            const NAME: &'static str = stringify!(#name);
+
             fn keys<'a>() -> &'a [isle_engine::input::Key] {
                 &[#(isle_engine::input::#keys),*]
             }
@@ -24,6 +33,22 @@ pub fn define_binding(input: TokenStream) -> TokenStream {
             fn buttons<'a>() -> &'a [isle_engine::input::Button] {
                 &[#(isle_engine::input::#buttons),*]
             }
+
+            fn mouse_buttons<'a>() -> &'a [isle_engine::input::MouseButton] {
+                &[#(isle_engine::input::#mouse_buttons),*]
+            }
+
+            fn chords<'a>() -> &'a [&'a [isle_engine::input::InputKind]] {
+                &[#(&[#(#chords),*]),*]
+            }
+
+            fn sequences<'a>() -> &'a [isle_engine::input::Sequence] {
+                const SEQUENCES: &[isle_engine::input::Sequence] = &[#(isle_engine::input::Sequence {
+                    steps: &[#(#sequence_steps),*],
+                    window: std::time::Duration::from_millis(#sequence_windows),
+                }),*];
+                SEQUENCES
+            }
         }
     }
     .into()
@@ -33,30 +58,85 @@ struct KeyButtonBinding {
     struct_name: Ident,
     keys: Vec<Expr>,
     buttons: Vec<Expr>,
+    mouse_buttons: Vec<Expr>,
+    chords: Vec<Vec<Expr>>,
+    sequences: Vec<SequenceBinding>,
 }
 
+/// Alternatives separated by `|`, each a single input, a chord such as `Modifier::Control + Key::S`
+/// or a sequence such as `[Key::Down, Key::Right, Button::West] within 300ms`
 impl Parse for KeyButtonBinding {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let struct_name: Ident = input.parse()?;
         input.parse::<Token![,]>()?;
 
-        let mut items = Vec::new();
-
-        let expr = input.parse::<Expr>()?;
-
-        collect_list(&expr, &mut items)?;
-
         let mut keys = Vec::new();
         let mut buttons = Vec::new();
+        let mut mouse_buttons = Vec::new();
+        let mut chords = Vec::new();
+        let mut sequences = Vec::new();
+
+        loop {
+            if input.peek(syn::token::Bracket) {
+                sequences.push(input.parse()?);
+            } else {
+                let mut chord = vec![Expr::Path(input.parse::<ExprPath>()?)];
+                while input.peek(Token![+]) {
+                    input.parse::<Token![+]>()?;
+                    chord.push(Expr::Path(input.parse::<ExprPath>()?));
+                }
+
+                if chord.len() == 1 && binding_prefix(&chord[0])? != "Modifier" {
+                    parse_single_binding(&chord[0], &mut keys, &mut buttons, &mut mouse_buttons)?;
+                } else {
+                    chords.push(chord.iter().map(input_kind).collect::<syn::Result<_>>()?);
+                }
+            }
 
-        for item in items {
-            parse_single_binding(&item, &mut keys, &mut buttons)?;
+            if !input.peek(Token![|]) {
+                break;
+            }
+            input.parse::<Token![|]>()?;
         }
 
         Ok(Self {
             struct_name,
             keys,
             buttons,
+            mouse_buttons,
+            chords,
+            sequences,
+        })
+    }
+}
+
+struct SequenceBinding {
+    steps: Vec<Expr>,
+    window_ms: u64,
+}
+
+impl Parse for SequenceBinding {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        bracketed!(content in input);
+        let steps = Punctuated::<ExprPath, Token![,]>::parse_terminated(&content)?
+            .into_iter()
+            .map(|step| input_kind(&Expr::Path(step)))
+            .collect::<syn::Result<_>>()?;
+
+        let within: Ident = input.parse()?;
+        if within != "within" {
+            return Err(syn::Error::new_spanned(within, "Expected `within` and a time such as 300ms"));
+        }
+
+        let window: LitInt = input.parse()?;
+        if !matches!(window.suffix(), "" | "ms") {
+            return Err(syn::Error::new_spanned(window, "Sequence windows are in milliseconds"));
+        }
+
+        Ok(Self {
+            steps,
+            window_ms: window.base10_parse()?,
         })
     }
 }
@@ -69,17 +149,26 @@ pub fn define_axis_binding(input: TokenStream) -> TokenStream {
     let axes = &binding_input.axes;
     let positive_fallback = &binding_input.positive_fallback;
     let negative_fallback = &binding_input.negative_fallback;
+    let settings = binding_input.settings.iter();
 
     quote! {
         pub struct #name;
 
         impl isle_engine::input::AxisMapping for #name {
+            const NAME: &'static str = stringify!(#name);
+
             type PositiveMapping = #positive_fallback;
             type NegativeMapping = #negative_fallback;
 
             fn axes<'a>() -> &'a [isle_engine::input::Axis] {
                 &[#(isle_engine::input::#axes),*]
             }
+
+            #(
+                fn settings() -> isle_engine::input::AxisSettings {
+                    #settings
+                }
+            )*
         }
     }
     .into()
@@ -90,6 +179,7 @@ struct AxisBinding {
     positive_fallback: Ident,
     negative_fallback: Ident,
     axes: Vec<Expr>,
+    settings: Option<Expr>,
 }
 
 impl Parse for AxisBinding {
@@ -107,11 +197,19 @@ impl Parse for AxisBinding {
 
         let negative_fallback: Ident = input.parse()?;
 
+        let settings = if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
         Ok(Self {
             struct_name,
             positive_fallback,
             negative_fallback,
             axes,
+            settings,
         })
     }
 }
@@ -138,10 +236,41 @@ fn collect_list(expr: &Expr, mut list: &mut Vec<Expr>) -> syn::Result<()> {
     Ok(())
 }
 
+fn binding_prefix(expr: &Expr) -> syn::Result<String> {
+    match expr {
+        Expr::Path(path) if path.path.segments.len() > 1 => {
+            Ok(path.path.segments.first().unwrap().ident.to_string())
+        }
+        _ => Err(syn::Error::new_spanned(
+            expr,
+            "Expected Key::, Button::, MouseButton:: or Modifier::",
+        )),
+    }
+}
+
+/// `isle_engine::input::InputKind` wrapping the input `expr` names
+fn input_kind(expr: &Expr) -> syn::Result<Expr> {
+    let kind: Expr = match binding_prefix(expr)?.as_str() {
+        "Key" => parse_quote!(isle_engine::input::InputKind::Key(isle_engine::input::#expr)),
+        "Button" => parse_quote!(isle_engine::input::InputKind::Button(isle_engine::input::#expr)),
+        "MouseButton" => parse_quote!(isle_engine::input::InputKind::Mouse(isle_engine::input::#expr)),
+        "Modifier" => parse_quote!(isle_engine::input::InputKind::Modifier(isle_engine::input::#expr)),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                expr,
+                "Expected Key::, Button::, MouseButton:: or Modifier::",
+            ))
+        }
+    };
+
+    Ok(kind)
+}
+
 fn parse_single_binding(
     expr: &Expr,
     keys: &mut Vec<Expr>,
     buttons: &mut Vec<Expr>,
+    mouse_buttons: &mut Vec<Expr>,
 ) -> syn::Result<()> {
     let path = if let Expr::Path(path) = expr {
         &path.path
@@ -152,12 +281,18 @@ fn parse_single_binding(
     let ty = path
         .segments
         .first()
-        .unwrap_or_else(|| panic!("Expected Key:: or Button::"));
+        .unwrap_or_else(|| panic!("Expected Key::, Button:: or MouseButton::"));
 
     match ty.ident.to_string().as_str() {
         "Key" => keys.push(expr.clone()),
         "Button" => buttons.push(expr.clone()),
-        _ => return Err(syn::Error::new_spanned(expr, "Expected Key:: or Button::")),
+        "MouseButton" => mouse_buttons.push(expr.clone()),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                expr,
+                "Expected Key::, Button:: or MouseButton::",
+            ))
+        }
     }
 
     Ok(())
@@ -0,0 +1,295 @@
+use std::{
+    any::TypeId,
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Weak,
+    },
+};
+
+use rustc_hash::FxHashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AssetId(u64);
+
+/// Hands out asset ids and collects the ids whose last strong handle dropped
+pub(crate) struct HandleAllocator {
+    next: AtomicU64,
+    drops: Sender<(TypeId, AssetId)>,
+}
+
+impl HandleAllocator {
+    pub(crate) fn new() -> (Arc<Self>, Receiver<(TypeId, AssetId)>) {
+        let (drops, receiver) = mpsc::channel();
+        let allocator = Self {
+            next: AtomicU64::new(0),
+            drops,
+        };
+        (Arc::new(allocator), receiver)
+    }
+
+    pub(crate) fn strong<T: 'static>(&self) -> Handle<T> {
+        let id = AssetId(self.next.fetch_add(1, Ordering::Relaxed));
+        Handle {
+            id,
+            reference: HandleRef::Strong(Arc::new(StrongRef {
+                type_id: TypeId::of::<T>(),
+                id,
+                drops: self.drops.clone(),
+            })),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+pub(crate) struct StrongRef {
+    type_id: TypeId,
+    id: AssetId,
+    drops: Sender<(TypeId, AssetId)>,
+}
+
+impl Drop for StrongRef {
+    fn drop(&mut self) {
+        let _ = self.drops.send((self.type_id, self.id));
+    }
+}
+
+#[derive(Clone)]
+enum HandleRef {
+    Strong(Arc<StrongRef>),
+    Weak(Weak<StrongRef>),
+}
+
+/// Reference to an asset in `Assets<T>`, the asset is unloaded once every strong handle dropped
+pub struct Handle<T: 'static> {
+    id: AssetId,
+    reference: HandleRef,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> Handle<T> {
+    pub fn id(&self) -> AssetId {
+        self.id
+    }
+
+    pub fn is_strong(&self) -> bool {
+        matches!(self.reference, HandleRef::Strong(_))
+    }
+
+    /// Handle that does not keep the asset loaded
+    pub fn downgrade(&self) -> Self {
+        let reference = match &self.reference {
+            HandleRef::Strong(strong) => HandleRef::Weak(Arc::downgrade(strong)),
+            weak => weak.clone(),
+        };
+
+        Self {
+            id: self.id,
+            reference,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Strong handle to the same asset, `None` once it was unloaded
+    pub fn upgrade(&self) -> Option<Self> {
+        let strong = match &self.reference {
+            HandleRef::Strong(strong) => strong.clone(),
+            HandleRef::Weak(weak) => weak.upgrade()?,
+        };
+
+        Some(Self {
+            id: self.id,
+            reference: HandleRef::Strong(strong),
+            _phantom: PhantomData,
+        })
+    }
+
+    /// False once every strong handle dropped
+    pub fn is_alive(&self) -> bool {
+        match &self.reference {
+            HandleRef::Strong(_) => true,
+            HandleRef::Weak(weak) => weak.strong_count() > 0,
+        }
+    }
+
+    pub(crate) fn weak_ref(&self) -> Weak<StrongRef> {
+        match &self.reference {
+            HandleRef::Strong(strong) => Arc::downgrade(strong),
+            HandleRef::Weak(weak) => weak.clone(),
+        }
+    }
+
+    pub(crate) fn from_weak_ref(reference: &Weak<StrongRef>) -> Option<Self> {
+        let strong = reference.upgrade()?;
+        Some(Self {
+            id: strong.id,
+            reference: HandleRef::Strong(strong),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<T: 'static> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            reference: self.reference.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: 'static> Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handle")
+            .field("id", &self.id)
+            .field("strong", &self.is_strong())
+            .finish()
+    }
+}
+
+impl<T: 'static> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T: 'static> Eq for Handle<T> {}
+
+impl<T: 'static> Hash for Handle<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+/// Typed asset storage, filled by `AssetServer` and by `add`
+pub struct Assets<T: 'static> {
+    assets: FxHashMap<AssetId, T>,
+    allocator: Arc<HandleAllocator>,
+}
+
+impl<T: 'static> Assets<T> {
+    pub(crate) fn new(allocator: Arc<HandleAllocator>) -> Self {
+        Self {
+            assets: FxHashMap::default(),
+            allocator,
+        }
+    }
+
+    /// Stores an asset created at runtime
+    pub fn add(&mut self, asset: T) -> Handle<T> {
+        let handle = self.allocator.strong::<T>();
+        self.assets.insert(handle.id, asset);
+        handle
+    }
+
+    pub fn get(&self, handle: &Handle<T>) -> Option<&T> {
+        self.assets.get(&handle.id)
+    }
+
+    pub fn get_mut(&mut self, handle: &Handle<T>) -> Option<&mut T> {
+        self.assets.get_mut(&handle.id)
+    }
+
+    pub fn contains(&self, handle: &Handle<T>) -> bool {
+        self.assets.contains_key(&handle.id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.assets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (AssetId, &T)> {
+        self.assets.iter().map(|(id, asset)| (*id, asset))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (AssetId, &mut T)> {
+        self.assets.iter_mut().map(|(id, asset)| (*id, asset))
+    }
+
+    pub(crate) fn insert(&mut self, id: AssetId, asset: T) -> Option<T> {
+        self.assets.insert(id, asset)
+    }
+
+    pub(crate) fn remove(&mut self, id: AssetId) -> Option<T> {
+        self.assets.remove(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Text;
+
+    #[test]
+    fn strong_handles_share_one_reference() {
+        let (allocator, drops) = HandleAllocator::new();
+        let handle = allocator.strong::<Text>();
+        let other = allocator.strong::<Text>();
+        assert_ne!(handle, other);
+
+        let id = handle.id();
+        let clone = handle.clone();
+        assert!(clone.is_strong());
+        assert_eq!(clone, handle);
+
+        drop(handle);
+        assert!(drops.try_recv().is_err());
+        drop(clone);
+        assert_eq!(drops.try_recv().unwrap(), (TypeId::of::<Text>(), id));
+        assert!(drops.try_recv().is_err());
+    }
+
+    #[test]
+    fn downgrade_and_upgrade() {
+        let (allocator, drops) = HandleAllocator::new();
+        let handle = allocator.strong::<Text>();
+
+        let weak = handle.downgrade();
+        assert!(!weak.is_strong());
+        assert!(weak.is_alive());
+        assert_eq!(weak, handle);
+        assert!(!weak.downgrade().is_strong());
+
+        let upgraded = weak.upgrade().unwrap();
+        assert!(upgraded.is_strong());
+        drop(handle);
+        assert!(weak.is_alive());
+        assert!(drops.try_recv().is_err());
+
+        drop(upgraded);
+        assert!(!weak.is_alive());
+        assert!(weak.upgrade().is_none());
+        assert_eq!(drops.try_recv().unwrap(), (TypeId::of::<Text>(), weak.id()));
+    }
+
+    #[test]
+    fn unload_after_last_strong_drop() {
+        let (allocator, drops) = HandleAllocator::new();
+        let mut assets = Assets::new(allocator);
+        let handle = assets.add(Text);
+        let weak = handle.downgrade();
+        assert!(assets.contains(&weak));
+
+        drop(weak);
+        assert!(drops.try_recv().is_err());
+
+        let id = handle.id();
+        drop(handle);
+        let dropped: Vec<_> = drops.try_iter().collect();
+        assert_eq!(dropped, [(TypeId::of::<Text>(), id)]);
+
+        dropped.into_iter().for_each(|(_, id)| {
+            assets.remove(id);
+        });
+        assert!(assets.is_empty());
+    }
+}
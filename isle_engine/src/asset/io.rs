@@ -0,0 +1,458 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::SystemTime,
+};
+
+use rustc_hash::FxHashMap;
+
+/// Separates the source name from the path in asset URIs such as `pack://textures/tree.png`
+pub const SOURCE_SEPARATOR: &str = "://";
+
+const PACK_MAGIC: &[u8; 8] = b"ISLEPACK";
+const PACK_VERSION: u32 = 1;
+
+/// Where asset bytes come from, paths are relative to the source and use `/` separators
+pub trait AssetReader: Send + Sync + 'static {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Last modification time used for hot reloading, `None` for sources that never change
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        let _ = path;
+        None
+    }
+}
+
+/// Asset path with an optional source, parsed from `source://path` or a plain path for the default source
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AssetPath {
+    source: Option<String>,
+    path: PathBuf,
+}
+
+impl AssetPath {
+    pub fn parse(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let uri = path.to_string_lossy();
+        match uri.split_once(SOURCE_SEPARATOR) {
+            Some((source, path)) => Self {
+                source: Some(source.to_string()),
+                path: PathBuf::from(path),
+            },
+            None => Self {
+                source: None,
+                path: path.to_path_buf(),
+            },
+        }
+    }
+
+    /// `None` for the default source
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Display for AssetPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{source}{SOURCE_SEPARATOR}{}", self.path.display()),
+            None => write!(f, "{}", self.path.display()),
+        }
+    }
+}
+
+/// Key used by the in-memory sources and pack archives, `a\b/./c` and `a/b/c` are the same file
+pub(crate) fn normalize(path: &Path) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::ParentDir => {
+                parts.pop();
+            }
+            _ => {}
+        }
+    }
+    parts.join("/")
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} is not in this source", path.display()))
+}
+
+/// Reads files below a directory on disk
+pub struct DirectoryReader {
+    root: PathBuf,
+}
+
+impl DirectoryReader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl AssetReader for DirectoryReader {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(self.root.join(path))
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        fs::metadata(self.root.join(path)).and_then(|metadata| metadata.modified()).ok()
+    }
+}
+
+/// Files compiled into the binary, usually with `include_bytes!`
+#[derive(Default)]
+pub struct EmbeddedReader {
+    files: FxHashMap<String, &'static [u8]>,
+}
+
+impl EmbeddedReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_file(mut self, path: impl AsRef<Path>, bytes: &'static [u8]) -> Self {
+        self.files.insert(normalize(path.as_ref()), bytes);
+        self
+    }
+}
+
+impl AssetReader for EmbeddedReader {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files
+            .get(&normalize(path))
+            .map(|bytes| bytes.to_vec())
+            .ok_or_else(|| not_found(path))
+    }
+}
+
+type MemoryFiles = FxHashMap<String, (Vec<u8>, SystemTime)>;
+
+/// Files kept in memory, clones share the same files so tests can change them after handing one to the server
+#[derive(Default, Clone)]
+pub struct MemoryReader {
+    files: Arc<RwLock<MemoryFiles>>,
+}
+
+impl MemoryReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_file(self, path: impl AsRef<Path>, bytes: impl Into<Vec<u8>>) -> Self {
+        self.insert(path, bytes);
+        self
+    }
+
+    /// Adds or replaces a file, replacing one counts as a modification for hot reloading
+    pub fn insert(&self, path: impl AsRef<Path>, bytes: impl Into<Vec<u8>>) {
+        let mut files = self.files.write().unwrap();
+        files.insert(normalize(path.as_ref()), (bytes.into(), SystemTime::now()));
+    }
+
+    pub fn remove(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        let mut files = self.files.write().unwrap();
+        files.remove(&normalize(path.as_ref())).map(|(bytes, _)| bytes)
+    }
+}
+
+impl AssetReader for MemoryReader {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let files = self.files.read().unwrap();
+        files
+            .get(&normalize(path))
+            .map(|(bytes, _)| bytes.clone())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        let files = self.files.read().unwrap();
+        files.get(&normalize(path)).map(|(_, modified)| *modified)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PackEntry {
+    offset: u64,
+    len: u64,
+}
+
+/// Reads files from a single-file archive written by `PackBuilder`
+///
+/// The index is read once on open, file contents are read on demand
+pub struct PackReader {
+    file: Mutex<File>,
+    index: FxHashMap<String, PackEntry>,
+}
+
+impl PackReader {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+        let index = read_index(&mut file, size)?;
+        Ok(Self {
+            file: Mutex::new(file),
+            index,
+        })
+    }
+
+    pub fn contains(&self, path: impl AsRef<Path>) -> bool {
+        self.index.contains_key(&normalize(path.as_ref()))
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.index.keys().map(String::as_str)
+    }
+}
+
+impl AssetReader for PackReader {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let entry = *self.index.get(&normalize(path)).ok_or_else(|| not_found(path))?;
+
+        let mut file = self.file.lock().unwrap();
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut bytes = vec![0; entry.len as usize];
+        file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+fn invalid_pack(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid asset pack: {message}"))
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Layout: magic, version, entry count, then per entry the path length, the path, the offset
+/// from the start of the archive and the length, followed by the file contents
+///
+/// Every entry has to lie within the `size` bytes of the archive, so a corrupt index fails here
+/// instead of allocating whatever length it claims on read
+fn read_index(reader: &mut impl Read, size: u64) -> io::Result<FxHashMap<String, PackEntry>> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != PACK_MAGIC {
+        return Err(invalid_pack("missing magic bytes"));
+    }
+    let version = read_u32(reader)?;
+    if version != PACK_VERSION {
+        return Err(invalid_pack(&format!("unsupported version {version}")));
+    }
+
+    let count = read_u32(reader)?;
+    let mut index = FxHashMap::default();
+    for _ in 0..count {
+        let len = read_u32(reader)?;
+        if u64::from(len) > size {
+            return Err(invalid_pack("path length exceeds the archive size"));
+        }
+        let mut path = vec![0; len as usize];
+        reader.read_exact(&mut path)?;
+        let path = String::from_utf8(path).map_err(|_| invalid_pack("path is not UTF-8"))?;
+        let entry = PackEntry {
+            offset: read_u64(reader)?,
+            len: read_u64(reader)?,
+        };
+        if entry.offset.checked_add(entry.len).is_none_or(|end| end > size) {
+            return Err(invalid_pack(&format!("{path} lies outside the archive")));
+        }
+        index.insert(path, entry);
+    }
+
+    Ok(index)
+}
+
+/// Collects files and writes them into an archive `PackReader` can open
+#[derive(Default)]
+pub struct PackBuilder {
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl PackBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_file(mut self, path: impl AsRef<Path>, bytes: impl Into<Vec<u8>>) -> Self {
+        self.files.insert(normalize(path.as_ref()), bytes.into());
+        self
+    }
+
+    /// Adds every file below `dir`, paths in the archive are relative to `dir`
+    pub fn with_dir(mut self, dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            for entry in fs::read_dir(&current)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else {
+                    let bytes = fs::read(&path)?;
+                    let relative = path.strip_prefix(dir).unwrap();
+                    self.files.insert(normalize(relative), bytes);
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        let index_len: usize = self.files.keys().map(|path| 4 + path.len() + 16).sum();
+        let mut offset = (PACK_MAGIC.len() + 8 + index_len) as u64;
+
+        writer.write_all(PACK_MAGIC)?;
+        writer.write_all(&PACK_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.files.len() as u32).to_le_bytes())?;
+        for (path, bytes) in &self.files {
+            writer.write_all(&(path.len() as u32).to_le_bytes())?;
+            writer.write_all(path.as_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+            offset += bytes.len() as u64;
+        }
+        for bytes in self.files.values() {
+            writer.write_all(bytes)?;
+        }
+        writer.flush()
+    }
+
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(File::create(path)?);
+        self.write(&mut writer)
+    }
+}
+
+/// Named asset sources, plain paths go to the default source
+pub(crate) struct AssetSources {
+    default: Arc<dyn AssetReader>,
+    named: FxHashMap<String, Arc<dyn AssetReader>>,
+}
+
+impl AssetSources {
+    pub(crate) fn new(default: impl AssetReader) -> Self {
+        Self {
+            default: Arc::new(default),
+            named: FxHashMap::default(),
+        }
+    }
+
+    pub(crate) fn set_default(&mut self, reader: impl AssetReader) {
+        self.default = Arc::new(reader);
+    }
+
+    pub(crate) fn insert(&mut self, name: impl Into<String>, reader: impl AssetReader) {
+        self.named.insert(name.into(), Arc::new(reader));
+    }
+
+    pub(crate) fn get(&self, source: Option<&str>) -> Option<Arc<dyn AssetReader>> {
+        match source {
+            Some(name) => self.named.get(name).cloned(),
+            None => Some(self.default.clone()),
+        }
+    }
+
+    pub(crate) fn names(&self) -> Vec<String> {
+        self.named.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn parse_asset_paths() {
+        let path = AssetPath::parse("pack://textures/tree.png");
+        assert_eq!(path.source(), Some("pack"));
+        assert_eq!(path.path(), Path::new("textures/tree.png"));
+        assert_eq!(path.to_string(), "pack://textures/tree.png");
+
+        let path = AssetPath::parse("textures/tree.png");
+        assert_eq!(path.source(), None);
+        assert_eq!(path.path(), Path::new("textures/tree.png"));
+        assert_eq!(path.to_string(), "textures/tree.png");
+
+        let path = AssetPath::parse("embedded://");
+        assert_eq!(path.source(), Some("embedded"));
+        assert_eq!(path.path(), Path::new(""));
+    }
+
+    #[test]
+    fn pack_round_trip() {
+        let builder = PackBuilder::new()
+            .with_file("textures/tree.png", b"tree".to_vec())
+            .with_file("models/../models/cube.obj", b"v 0 0 0".to_vec())
+            .with_file("empty", Vec::new());
+        assert_eq!(builder.len(), 3);
+
+        let path = std::env::temp_dir().join(format!("isle_pack_{}.pack", std::process::id()));
+        builder.write_to_file(&path).unwrap();
+        let reader = PackReader::open(&path);
+        fs::remove_file(&path).unwrap();
+        let reader = reader.unwrap();
+
+        let mut paths: Vec<_> = reader.paths().collect();
+        paths.sort();
+        assert_eq!(paths, ["empty", "models/cube.obj", "textures/tree.png"]);
+        assert!(reader.contains("textures/./tree.png"));
+        assert_eq!(reader.read(Path::new("textures/tree.png")).unwrap(), b"tree");
+        assert_eq!(reader.read(Path::new("models/cube.obj")).unwrap(), b"v 0 0 0");
+        assert!(reader.read(Path::new("empty")).unwrap().is_empty());
+        assert_eq!(
+            reader.read(Path::new("missing")).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn pack_index_out_of_bounds() {
+        let mut bytes = Vec::new();
+        PackBuilder::new().with_file("a", b"abc".to_vec()).write(&mut bytes).unwrap();
+        let size = bytes.len() as u64;
+        assert_eq!(read_index(&mut Cursor::new(&bytes), size).unwrap().len(), 1);
+
+        // Length of the only entry, right after magic, version, count, path length and path
+        let len_at = PACK_MAGIC.len() + 8 + 4 + 1 + 8;
+        bytes[len_at..len_at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        let err = read_index(&mut Cursor::new(&bytes), size).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        bytes[len_at..len_at + 8].copy_from_slice(&4u64.to_le_bytes());
+        let err = read_index(&mut Cursor::new(&bytes), size).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    any::type_name,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{server::AssetError, Result};
+
+/// Converts source files into an engine-ready form ahead of loading, e.g. `.obj` text into packed
+/// vertex and index buffers
+///
+/// The output is read by the loader registered for `OUTPUT_EXTENSION`, results are cached by
+/// `ProcessedCache` so a source is only processed again when its contents or `VERSION` change
+pub trait AssetProcessor: Send + Sync + 'static {
+    /// Source extensions without the leading dot
+    const EXTENSIONS: &'static [&'static str];
+    const OUTPUT_EXTENSION: &'static str;
+    /// Bump whenever the output format changes, cached artifacts of other versions are rebuilt
+    const VERSION: u32;
+
+    fn process(&self, bytes: &[u8], path: &Path) -> Result<Vec<u8>>;
+}
+
+pub(crate) trait AssetProcessorAny: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn extensions(&self) -> &'static [&'static str];
+    fn output_extension(&self) -> &'static str;
+    fn version(&self) -> u32;
+    fn process(&self, bytes: &[u8], path: &Path) -> Result<Vec<u8>>;
+}
+
+impl<P: AssetProcessor> AssetProcessorAny for P {
+    fn name(&self) -> &'static str {
+        type_name::<P>()
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        P::EXTENSIONS
+    }
+    fn output_extension(&self) -> &'static str {
+        P::OUTPUT_EXTENSION
+    }
+    fn version(&self) -> u32 {
+        P::VERSION
+    }
+    fn process(&self, bytes: &[u8], path: &Path) -> Result<Vec<u8>> {
+        AssetProcessor::process(self, bytes, path)
+    }
+}
+
+/// 64 bit FNV-1a, stable across builds unlike `std`'s hashers so cache keys survive compiler updates
+fn content_hash(chunks: &[&[u8]]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    chunks.iter().flat_map(|chunk| chunk.iter()).fold(OFFSET, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Written next to every artifact, an artifact is only used when its meta matches the processor
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct ProcessedMeta {
+    processor: String,
+    version: u32,
+    source: String,
+    hash: String,
+}
+
+/// Output of `ProcessedCache::load_or_process`, usable even when it could not be cached
+pub(crate) struct Processed {
+    pub(crate) bytes: Vec<u8>,
+    /// Writing the artifact failed, the next load processes the source again
+    pub(crate) cache_error: Option<AssetError>,
+}
+
+/// Directory of processed artifacts named by the hash of their source contents
+pub struct ProcessedCache {
+    dir: PathBuf,
+}
+
+impl ProcessedCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Removes every cached artifact
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Returns the cached artifact for `bytes` when it is up to date, otherwise processes them and
+    /// stores the result, failing to write the cache only costs the next load the same work
+    pub(crate) fn load_or_process(
+        &self,
+        processor: &dyn AssetProcessorAny,
+        bytes: &[u8],
+        path: &Path,
+    ) -> std::result::Result<Processed, AssetError> {
+        let hash = format!("{:016x}", content_hash(&[processor.name().as_bytes(), bytes]));
+        let meta = ProcessedMeta {
+            processor: processor.name().to_string(),
+            version: processor.version(),
+            source: path.display().to_string(),
+            hash: hash.clone(),
+        };
+        let artifact = self.dir.join(format!("{hash}.{}", processor.output_extension()));
+        let meta_path = self.dir.join(format!("{hash}.meta"));
+
+        if let Some(bytes) = self.read_cached(&artifact, &meta_path, &meta) {
+            return Ok(Processed {
+                bytes,
+                cache_error: None,
+            });
+        }
+
+        let bytes = processor
+            .process(bytes, path)
+            .map_err(|err| AssetError::Process(path.to_path_buf(), err))?;
+        let cache_error = self
+            .write_cached(&artifact, &meta_path, &meta, &bytes)
+            .err()
+            .map(|err| AssetError::Cache(path.to_path_buf(), err));
+
+        Ok(Processed { bytes, cache_error })
+    }
+
+    fn read_cached(&self, artifact: &Path, meta_path: &Path, expected: &ProcessedMeta) -> Option<Vec<u8>> {
+        let meta: ProcessedMeta = serde_json::from_slice(&fs::read(meta_path).ok()?).ok()?;
+        let up_to_date = meta.processor == expected.processor
+            && meta.version == expected.version
+            && meta.hash == expected.hash;
+
+        up_to_date.then(|| fs::read(artifact).ok()).flatten()
+    }
+
+    /// Writes through temporary files so other workers never read half an artifact
+    fn write_cached(&self, artifact: &Path, meta_path: &Path, meta: &ProcessedMeta, processed: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let temp = artifact.with_extension("tmp");
+        fs::write(&temp, processed)?;
+        fs::rename(&temp, artifact)?;
+
+        let temp = meta_path.with_extension("meta.tmp");
+        fs::write(&temp, serde_json::to_vec_pretty(meta)?)?;
+        fs::rename(&temp, meta_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Reverses its input and counts how often it ran
+    #[derive(Default)]
+    struct Reverse {
+        runs: AtomicUsize,
+    }
+
+    impl AssetProcessor for Reverse {
+        const EXTENSIONS: &'static [&'static str] = &["fwd"];
+        const OUTPUT_EXTENSION: &'static str = "rev";
+        const VERSION: u32 = 2;
+
+        fn process(&self, bytes: &[u8], _: &Path) -> Result<Vec<u8>> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            Ok(bytes.iter().rev().copied().collect())
+        }
+    }
+
+    fn cache(name: &str) -> ProcessedCache {
+        let cache = ProcessedCache::new(std::env::temp_dir().join(format!("isle_{name}_{}", std::process::id())));
+        cache.clear().unwrap();
+        cache
+    }
+
+    fn process(cache: &ProcessedCache, processor: &Reverse, bytes: &[u8]) -> Vec<u8> {
+        let processed = cache.load_or_process(processor, bytes, Path::new("a.fwd")).unwrap();
+        assert!(processed.cache_error.is_none());
+        processed.bytes
+    }
+
+    #[test]
+    fn cache_hits_skip_the_processor() {
+        let cache = cache("cache_hit");
+        let processor = Reverse::default();
+
+        assert_eq!(process(&cache, &processor, b"abc"), b"cba");
+        assert_eq!(process(&cache, &processor, b"abc"), b"cba");
+        assert_eq!(processor.runs.load(Ordering::SeqCst), 1);
+
+        // Changed contents hash to another artifact
+        assert_eq!(process(&cache, &processor, b"abcd"), b"dcba");
+        assert_eq!(processor.runs.load(Ordering::SeqCst), 2);
+        assert_eq!(process(&cache, &processor, b"abc"), b"cba");
+        assert_eq!(processor.runs.load(Ordering::SeqCst), 2);
+
+        cache.clear().unwrap();
+        assert_eq!(process(&cache, &processor, b"abc"), b"cba");
+        assert_eq!(processor.runs.load(Ordering::SeqCst), 3);
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn stale_meta_reprocesses() {
+        let cache = cache("cache_stale");
+        let processor = Reverse::default();
+        process(&cache, &processor, b"abc");
+
+        let meta_path = fs::read_dir(cache.dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.extension().is_some_and(|extension| extension == "meta"))
+            .unwrap();
+        let rewrite = |edit: fn(&mut ProcessedMeta)| {
+            let mut meta: ProcessedMeta = serde_json::from_slice(&fs::read(&meta_path).unwrap()).unwrap();
+            edit(&mut meta);
+            fs::write(&meta_path, serde_json::to_vec(&meta).unwrap()).unwrap();
+        };
+
+        rewrite(|meta| meta.version = 1);
+        assert_eq!(process(&cache, &processor, b"abc"), b"cba");
+        assert_eq!(processor.runs.load(Ordering::SeqCst), 2);
+
+        // Reprocessing rewrote the meta for the current version
+        process(&cache, &processor, b"abc");
+        assert_eq!(processor.runs.load(Ordering::SeqCst), 2);
+
+        rewrite(|meta| meta.hash = "0000000000000000".into());
+        process(&cache, &processor, b"abc");
+        assert_eq!(processor.runs.load(Ordering::SeqCst), 3);
+        cache.clear().unwrap();
+    }
+}
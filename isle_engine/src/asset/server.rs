@@ -0,0 +1,1577 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, OnceLock, RwLock, Weak,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use isle_ecs::world::World;
+use rustc_hash::FxHashMap;
+
+use crate::event::Events;
+
+use super::{
+    handle::{AssetId, Assets, Handle, HandleAllocator, StrongRef},
+    io::{normalize, AssetPath, AssetReader, AssetSources, DirectoryReader, SOURCE_SEPARATOR},
+    processor::{AssetProcessor, AssetProcessorAny, Processed, ProcessedCache},
+    Result, StageInfo,
+};
+
+/// Upper bound for loader threads, the pool is smaller on machines with fewer cores
+pub const MAX_LOAD_WORKERS: usize = 4;
+
+/// Turns the bytes of a file into an asset, one stage at a time on a worker thread
+///
+/// Stage 0 is `load`, every further stage in `STAGES` is a call to `process`. Assets `load` requests
+/// through its `LoadContext` become dependencies, see `AssetServer::recursive_load_state`
+///
+/// The server picks the loader matching a path's extension or an explicit MIME type, a loader
+/// without extensions is used for its asset type when no other loader matches
+pub trait AssetLoader: Send + Sync + 'static {
+    type Asset: Send + Sync + 'static;
+    /// Without the leading dot, compound extensions such as `tar.gz` are allowed
+    const EXTENSIONS: &'static [&'static str] = &[];
+    const MIME_TYPES: &'static [&'static str] = &[];
+    const STAGES: &'static [StageInfo] = &[StageInfo {
+        name: "Load",
+        load_impact: 1.0,
+    }];
+
+    fn load(&self, bytes: &[u8], context: &mut LoadContext) -> Result<Self::Asset>;
+
+    fn process(&self, asset: &mut Self::Asset, stage: usize) -> Result<()> {
+        let _ = (asset, stage);
+        Ok(())
+    }
+}
+
+trait AssetLoaderAny: Send + Sync {
+    fn extensions(&self) -> &'static [&'static str];
+    fn mime_types(&self) -> &'static [&'static str];
+    fn stages(&self) -> &'static [StageInfo];
+    fn load(&self, bytes: &[u8], context: &mut LoadContext) -> Result<Box<dyn Any + Send>>;
+    fn process(&self, asset: &mut (dyn Any + Send), stage: usize) -> Result<()>;
+}
+
+impl<L: AssetLoader> AssetLoaderAny for L {
+    fn extensions(&self) -> &'static [&'static str] {
+        L::EXTENSIONS
+    }
+    fn mime_types(&self) -> &'static [&'static str] {
+        L::MIME_TYPES
+    }
+    fn stages(&self) -> &'static [StageInfo] {
+        L::STAGES
+    }
+    fn load(&self, bytes: &[u8], context: &mut LoadContext) -> Result<Box<dyn Any + Send>> {
+        Ok(Box::new(AssetLoader::load(self, bytes, context)?))
+    }
+    fn process(&self, asset: &mut (dyn Any + Send), stage: usize) -> Result<()> {
+        let asset = asset.downcast_mut().unwrap();
+        AssetLoader::process(self, asset, stage)
+    }
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    NoLoader {
+        asset: &'static str,
+        requested: String,
+        supported: Vec<String>,
+    },
+    UnknownSource {
+        path: PathBuf,
+        known: Vec<String>,
+    },
+    Io(PathBuf, io::Error),
+    Process(PathBuf, Box<dyn std::error::Error + Send + Sync>),
+    /// Writing the processed artifact to the `ProcessedCache` failed, the asset itself still loads
+    Cache(PathBuf, io::Error),
+    Load(PathBuf, Box<dyn std::error::Error + Send + Sync>),
+    Stage {
+        path: PathBuf,
+        stage: &'static str,
+        err: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+impl std::error::Error for AssetError {}
+
+impl Display for AssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoLoader {
+                asset,
+                requested,
+                supported,
+            } if supported.is_empty() => write!(
+                f,
+                "No loader registered for asset type {asset} to load {requested}\nHint: register one with FlowBuilder::with_asset_loader"
+            ),
+            Self::NoLoader {
+                asset,
+                requested,
+                supported,
+            } => write!(
+                f,
+                "No loader for asset type {asset} accepts {requested}\nHint: supported types are {}",
+                supported.join(", ")
+            ),
+            Self::UnknownSource { path, known } if known.is_empty() => write!(
+                f,
+                "Asset {} names an unknown source\nHint: add sources with FlowBuilder::with_asset_source",
+                path.display()
+            ),
+            Self::UnknownSource { path, known } => write!(
+                f,
+                "Asset {} names an unknown source\nHint: known sources are {}",
+                path.display(),
+                known.join(", ")
+            ),
+            Self::Io(path, err) => write!(
+                f,
+                "Failed to read asset {}: {err}\nHint: paths are relative to their source, plain paths to the asset root",
+                path.display()
+            ),
+            Self::Process(path, err) => write!(f, "Failed to process asset {}: {err}", path.display()),
+            Self::Cache(path, err) => write!(
+                f,
+                "Failed to cache processed asset {}: {err}\nHint: check that the asset cache directory is writable",
+                path.display()
+            ),
+            Self::Load(path, err) => write!(f, "Failed to load asset {}: {err}", path.display()),
+            Self::Stage { path, stage, err } => write!(
+                f,
+                "Failed to load asset {} in stage {stage}: {err}",
+                path.display()
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LoadState {
+    Queued,
+    /// Running the stage at this index of the loader's `STAGES`
+    Stage(usize),
+    Loaded,
+    Failed(Arc<AssetError>),
+}
+
+/// State of an asset and the dependencies it loaded through its `LoadContext`
+#[derive(Debug, Clone)]
+pub enum RecursiveLoadState {
+    Loading,
+    Loaded,
+    /// The first failure found in the asset or any of its dependencies
+    Failed(Arc<AssetError>),
+}
+
+struct LoadProgress {
+    state: LoadState,
+    stages: &'static [StageInfo],
+}
+
+impl LoadProgress {
+    /// Share of the total `load_impact` of the stages completed so far
+    fn fraction(&self) -> f32 {
+        let completed = match self.state {
+            LoadState::Queued => 0,
+            LoadState::Stage(stage) => stage,
+            LoadState::Loaded | LoadState::Failed(_) => return 1.0,
+        };
+
+        let total: f32 = self.stages.iter().map(|stage| stage.load_impact).sum();
+        if total <= 0.0 {
+            return completed as f32 / self.stages.len().max(1) as f32;
+        }
+
+        let done: f32 = self.stages[..completed].iter().map(|stage| stage.load_impact).sum();
+        done / total
+    }
+}
+
+/// Sent through `Events<AssetEvent<T>>` when the server changes `Assets<T>` or fails to, handles are weak
+pub enum AssetEvent<T: 'static> {
+    Loaded(Handle<T>),
+    /// The source file changed and the asset behind the handle was replaced
+    Modified(Handle<T>),
+    Unloaded(AssetId),
+    /// Loading the asset failed, or reloading it failed and the previous data stays in `Assets<T>`.
+    /// `AssetServer::load_state` tells which assets are usable
+    Failed(Handle<T>, Arc<AssetError>),
+    /// The asset loaded but its processed artifact could not be cached, the next load processes
+    /// the source again
+    CacheWriteFailed(Handle<T>, Arc<AssetError>),
+}
+
+impl<T: 'static> Clone for AssetEvent<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Loaded(handle) => Self::Loaded(handle.clone()),
+            Self::Modified(handle) => Self::Modified(handle.clone()),
+            Self::Unloaded(id) => Self::Unloaded(*id),
+            Self::Failed(handle, err) => Self::Failed(handle.clone(), err.clone()),
+            Self::CacheWriteFailed(handle, err) => Self::CacheWriteFailed(handle.clone(), err.clone()),
+        }
+    }
+}
+
+impl<T: 'static> std::fmt::Debug for AssetEvent<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Loaded(handle) => f.debug_tuple("Loaded").field(handle).finish(),
+            Self::Modified(handle) => f.debug_tuple("Modified").field(handle).finish(),
+            Self::Unloaded(id) => f.debug_tuple("Unloaded").field(id).finish(),
+            Self::Failed(handle, err) => f.debug_tuple("Failed").field(handle).field(err).finish(),
+            Self::CacheWriteFailed(handle, err) => {
+                f.debug_tuple("CacheWriteFailed").field(handle).field(err).finish()
+            }
+        }
+    }
+}
+
+fn send_asset_event<T: 'static>(world: &mut World, event: AssetEvent<T>) {
+    if let Some(events) = unsafe { world.get_resource_mut::<Events<AssetEvent<T>>>() } {
+        events.send(event);
+    }
+}
+
+/// Moves loaded assets into `Assets<T>`, unloads dropped ones and reports failed loads and cache writes
+#[derive(Clone, Copy)]
+struct AssetStorage {
+    insert: fn(&mut World, &Weak<StrongRef>, Box<dyn Any + Send>),
+    remove: fn(&mut World, AssetId),
+    fail: fn(&mut World, &Weak<StrongRef>, Arc<AssetError>, FailureKind),
+}
+
+impl AssetStorage {
+    fn new<T: Send + Sync + 'static>() -> Self {
+        Self {
+            insert: insert_asset::<T>,
+            remove: remove_asset::<T>,
+            fail: fail_asset::<T>,
+        }
+    }
+}
+
+fn insert_asset<T: Send + Sync + 'static>(world: &mut World, handle: &Weak<StrongRef>, asset: Box<dyn Any + Send>) {
+    let Some(handle) = Handle::<T>::from_weak_ref(handle) else {
+        return;
+    };
+    let Some(assets) = (unsafe { world.get_resource_mut::<Assets<T>>() }) else {
+        return;
+    };
+
+    let asset = *asset.downcast::<T>().unwrap();
+    let event = match assets.insert(handle.id(), asset) {
+        Some(_) => AssetEvent::Modified(handle.downgrade()),
+        None => AssetEvent::Loaded(handle.downgrade()),
+    };
+    send_asset_event(world, event);
+}
+
+fn remove_asset<T: Send + Sync + 'static>(world: &mut World, id: AssetId) {
+    if let Some(assets) = unsafe { world.get_resource_mut::<Assets<T>>() } {
+        if assets.remove(id).is_some() {
+            send_asset_event::<T>(world, AssetEvent::Unloaded(id));
+        }
+    }
+}
+
+fn fail_asset<T: Send + Sync + 'static>(
+    world: &mut World,
+    handle: &Weak<StrongRef>,
+    err: Arc<AssetError>,
+    kind: FailureKind,
+) {
+    if let Some(handle) = Handle::<T>::from_weak_ref(handle) {
+        let event = match kind {
+            FailureKind::Load => AssetEvent::Failed(handle.downgrade(), err),
+            FailureKind::CacheWrite => AssetEvent::CacheWriteFailed(handle.downgrade(), err),
+        };
+        send_asset_event(world, event);
+    }
+}
+
+/// Passed to `AssetLoader::load`, assets loaded through it are dependencies of the asset being loaded
+///
+/// Dependencies stay loaded while a strong handle to them is alive, assets usually keep the handles
+/// `load` returns
+pub struct LoadContext<'a> {
+    shared: &'a ServerShared,
+    path: &'a Path,
+    dependencies: Vec<AssetId>,
+}
+
+impl LoadContext<'_> {
+    /// Path of the asset being loaded
+    pub fn path(&self) -> &Path {
+        self.path
+    }
+
+    /// Resolves `path` against the directory of the asset being loaded, paths with a `source://`
+    /// prefix are used as they are
+    pub fn resolve(&self, path: impl AsRef<Path>) -> PathBuf {
+        let path = path.as_ref();
+        if AssetPath::parse(path).source().is_some() {
+            return path.to_path_buf();
+        }
+
+        let parent = AssetPath::parse(self.path);
+        let joined = normalize(&parent.path().parent().unwrap_or(Path::new("")).join(path));
+        match parent.source() {
+            Some(source) => PathBuf::from(format!("{source}{SOURCE_SEPARATOR}{joined}")),
+            None => PathBuf::from(joined),
+        }
+    }
+
+    /// Queues `path` as a dependency, see `resolve` for how the path is interpreted
+    pub fn load<T: Send + Sync + 'static>(&mut self, path: impl AsRef<Path>) -> Handle<T> {
+        let handle = self.shared.load_with(self.resolve(path), None);
+        self.dependencies.push(handle.id());
+        handle
+    }
+
+    pub fn load_with_mime<T: Send + Sync + 'static>(&mut self, path: impl AsRef<Path>, mime: &str) -> Handle<T> {
+        let handle = self.shared.load_with(self.resolve(path), Some(mime));
+        self.dependencies.push(handle.id());
+        handle
+    }
+
+    /// Reads another file without making it an asset, it is not tracked for hot reloading
+    pub fn read(&self, path: impl AsRef<Path>) -> std::result::Result<Vec<u8>, AssetError> {
+        self.shared.read(&self.resolve(path))
+    }
+}
+
+struct LoadedAsset {
+    type_id: TypeId,
+    id: AssetId,
+    handle: Weak<StrongRef>,
+    asset: Box<dyn Any + Send>,
+    modified: Option<SystemTime>,
+    dependencies: Vec<AssetId>,
+    reload: Option<Reload>,
+}
+
+#[derive(Clone, Copy)]
+enum FailureKind {
+    Load,
+    CacheWrite,
+}
+
+struct FailedLoad {
+    type_id: TypeId,
+    handle: Weak<StrongRef>,
+    err: Arc<AssetError>,
+    kind: FailureKind,
+}
+
+/// State shared between the server and its worker threads
+#[derive(Default)]
+struct Loading {
+    progress: Mutex<FxHashMap<AssetId, LoadProgress>>,
+    loaded: Mutex<Vec<LoadedAsset>>,
+    failed: Mutex<Vec<FailedLoad>>,
+}
+
+impl Loading {
+    fn set_state(&self, id: AssetId, state: LoadState) {
+        if let Some(progress) = self.progress.lock().unwrap().get_mut(&id) {
+            progress.state = state;
+        }
+    }
+
+    /// Reported as `AssetEvent::Failed` on the next spin
+    fn fail(&self, type_id: TypeId, handle: Weak<StrongRef>, err: Arc<AssetError>) {
+        self.push_failure(type_id, handle, err, FailureKind::Load);
+    }
+
+    /// Reported as `AssetEvent::CacheWriteFailed` on the next spin
+    fn cache_write_failed(&self, type_id: TypeId, handle: Weak<StrongRef>, err: Arc<AssetError>) {
+        self.push_failure(type_id, handle, err, FailureKind::CacheWrite);
+    }
+
+    fn push_failure(&self, type_id: TypeId, handle: Weak<StrongRef>, err: Arc<AssetError>, kind: FailureKind) {
+        self.failed.lock().unwrap().push(FailedLoad {
+            type_id,
+            handle,
+            err,
+            kind,
+        });
+    }
+}
+
+/// Why a loaded asset is loaded again
+#[derive(Clone)]
+struct Reload {
+    /// Assets already reloaded in this chain of dependents, keeps dependency cycles from reloading forever
+    chain: Vec<AssetId>,
+}
+
+struct LoadJob {
+    type_id: TypeId,
+    handle: Weak<StrongRef>,
+    id: AssetId,
+    path: PathBuf,
+    reader: Arc<dyn AssetReader>,
+    source_path: PathBuf,
+    loader: Arc<dyn AssetLoaderAny>,
+    processor: Option<Arc<dyn AssetProcessorAny>>,
+    /// Reloads keep the asset `Loaded` and the old data when they fail
+    reload: Option<Reload>,
+}
+
+impl LoadJob {
+    fn run(self, shared: &ServerShared) {
+        let modified = self.reader.modified(&self.source_path);
+        match self.load(shared) {
+            Ok((asset, dependencies)) => shared.loading.loaded.lock().unwrap().push(LoadedAsset {
+                type_id: self.type_id,
+                id: self.id,
+                handle: self.handle,
+                asset,
+                modified,
+                dependencies,
+                reload: self.reload,
+            }),
+            Err(err) => {
+                let err = Arc::new(err);
+                self.set_state(&shared.loading, LoadState::Failed(err.clone()));
+                shared.loading.fail(self.type_id, self.handle, err);
+            }
+        }
+    }
+
+    fn set_state(&self, loading: &Loading, state: LoadState) {
+        if self.reload.is_none() {
+            loading.set_state(self.id, state);
+        }
+    }
+
+    fn load(
+        &self,
+        shared: &ServerShared,
+    ) -> std::result::Result<(Box<dyn Any + Send>, Vec<AssetId>), AssetError> {
+        self.set_state(&shared.loading, LoadState::Stage(0));
+        let mut bytes = self
+            .reader
+            .read(&self.source_path)
+            .map_err(|err| AssetError::Io(self.path.clone(), err))?;
+        if let Some(processor) = &self.processor {
+            let processed = shared.process(processor.as_ref(), &bytes, &self.path)?;
+            if let Some(err) = processed.cache_error {
+                shared.loading.cache_write_failed(self.type_id, self.handle.clone(), Arc::new(err));
+            }
+            bytes = processed.bytes;
+        }
+
+        let mut context = LoadContext {
+            shared,
+            path: &self.path,
+            dependencies: Vec::new(),
+        };
+        let mut asset = self
+            .loader
+            .load(&bytes, &mut context)
+            .map_err(|err| AssetError::Load(self.path.clone(), err))?;
+        let dependencies = context.dependencies;
+
+        for (stage, info) in self.loader.stages().iter().enumerate().skip(1) {
+            if self.handle.strong_count() == 0 {
+                break;
+            }
+
+            self.set_state(&shared.loading, LoadState::Stage(stage));
+            self.loader
+                .process(asset.as_mut(), stage)
+                .map_err(|err| AssetError::Stage {
+                    path: self.path.clone(),
+                    stage: info.name,
+                    err,
+                })?;
+        }
+
+        Ok((asset, dependencies))
+    }
+}
+
+/// Where a loaded asset came from, kept to reload it when the file changes
+struct LoadOrigin {
+    type_id: TypeId,
+    asset: &'static str,
+    path: PathBuf,
+    reader: Arc<dyn AssetReader>,
+    source_path: PathBuf,
+    mime: Option<String>,
+    handle: Weak<StrongRef>,
+    modified: Option<SystemTime>,
+}
+
+struct Watch {
+    interval: Duration,
+    last: Instant,
+}
+
+type Selected = (Arc<dyn AssetLoaderAny>, Option<Arc<dyn AssetProcessorAny>>);
+
+#[derive(Default)]
+struct ServerState {
+    loaders: FxHashMap<TypeId, Vec<Arc<dyn AssetLoaderAny>>>,
+    storages: FxHashMap<TypeId, AssetStorage>,
+    paths: FxHashMap<(TypeId, PathBuf), Weak<StrongRef>>,
+    origins: FxHashMap<AssetId, LoadOrigin>,
+    dependencies: FxHashMap<AssetId, Vec<AssetId>>,
+    processors: Vec<Arc<dyn AssetProcessorAny>>,
+    watch: Option<Watch>,
+}
+
+impl ServerState {
+    fn select_loader(
+        &self,
+        type_id: TypeId,
+        asset: &'static str,
+        path: &Path,
+        mime: Option<&str>,
+    ) -> std::result::Result<Arc<dyn AssetLoaderAny>, AssetError> {
+        let loaders = self.loaders.get(&type_id).map_or(&[][..], Vec::as_slice);
+
+        let selected = match mime {
+            Some(mime) => loaders
+                .iter()
+                .find(|loader| {
+                    loader
+                        .mime_types()
+                        .iter()
+                        .any(|supported| supported.eq_ignore_ascii_case(mime))
+                }),
+            None => {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                loaders
+                    .iter()
+                    .find(|loader| {
+                        loader
+                            .extensions()
+                            .iter()
+                            .any(|extension| name.ends_with(&format!(".{}", extension.to_lowercase())))
+                    })
+                    .or_else(|| loaders.iter().find(|loader| loader.extensions().is_empty()))
+            }
+        };
+
+        selected.cloned().ok_or_else(|| AssetError::NoLoader {
+            asset,
+            requested: match mime {
+                Some(mime) => mime.to_string(),
+                None => path.display().to_string(),
+            },
+            supported: loaders
+                .iter()
+                .flat_map(|loader| {
+                    let extensions = loader.extensions().iter().map(|extension| format!(".{extension}"));
+                    extensions.chain(loader.mime_types().iter().map(|mime| mime.to_string()))
+                })
+                .collect(),
+        })
+    }
+
+    /// Processor for `path` when it is loaded by extension
+    fn select_processor(&self, path: &Path, mime: Option<&str>) -> Option<Arc<dyn AssetProcessorAny>> {
+        if mime.is_some() {
+            return None;
+        }
+
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        self.processors
+            .iter()
+            .find(|processor| {
+                processor
+                    .extensions()
+                    .iter()
+                    .any(|extension| name.ends_with(&format!(".{}", extension.to_lowercase())))
+            })
+            .cloned()
+    }
+
+    /// Loader for `path` and the processor its bytes go through first, processed bytes are read by
+    /// the loader of the processor's output extension
+    fn select(
+        &self,
+        type_id: TypeId,
+        asset: &'static str,
+        path: &Path,
+        mime: Option<&str>,
+    ) -> std::result::Result<Selected, AssetError> {
+        let processor = self.select_processor(path, mime);
+        let loader = match &processor {
+            Some(processor) => {
+                let output = PathBuf::from(format!("{}.{}", path.display(), processor.output_extension()));
+                self.select_loader(type_id, asset, &output, None)?
+            }
+            None => self.select_loader(type_id, asset, path, mime)?,
+        };
+
+        Ok((loader, processor))
+    }
+
+    fn reload_job(&self, id: AssetId, reload: Reload) -> Option<LoadJob> {
+        let origin = self.origins.get(&id)?;
+        let (loader, processor) = self
+            .select(origin.type_id, origin.asset, &origin.path, origin.mime.as_deref())
+            .ok()?;
+
+        Some(LoadJob {
+            type_id: origin.type_id,
+            handle: origin.handle.clone(),
+            id,
+            path: origin.path.clone(),
+            reader: origin.reader.clone(),
+            source_path: origin.source_path.clone(),
+            loader,
+            processor,
+            reload: Some(reload),
+        })
+    }
+
+    /// Assets that loaded `id` through their `LoadContext`
+    fn dependents(&self, id: AssetId) -> Vec<AssetId> {
+        self.dependencies
+            .iter()
+            .filter(|(_, dependencies)| dependencies.contains(&id))
+            .map(|(dependent, _)| *dependent)
+            .collect()
+    }
+}
+
+/// Everything the worker threads need, loaders queue their dependencies through it
+struct ServerShared {
+    sources: RwLock<AssetSources>,
+    allocator: Arc<HandleAllocator>,
+    state: Mutex<ServerState>,
+    loading: Loading,
+    cache: RwLock<Option<ProcessedCache>>,
+    workers: OnceLock<Sender<LoadJob>>,
+}
+
+impl ServerShared {
+    fn workers(self: &Arc<Self>) -> &Sender<LoadJob> {
+        self.workers.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel::<LoadJob>();
+            let receiver = Arc::new(Mutex::new(receiver));
+            let count = thread::available_parallelism().map_or(1, |count| count.get().min(MAX_LOAD_WORKERS));
+
+            (0..count).for_each(|_| {
+                let receiver = receiver.clone();
+                let shared = Arc::downgrade(self);
+                thread::spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+                    match (job, shared.upgrade()) {
+                        (Ok(job), Some(shared)) => job.run(&shared),
+                        _ => break,
+                    }
+                });
+            });
+
+            sender
+        })
+    }
+
+    fn queue(&self, job: LoadJob) {
+        // Jobs are only created after `AssetServer` started the pool
+        self.workers.get().unwrap().send(job).unwrap();
+    }
+
+    fn resolve(&self, path: &Path) -> std::result::Result<(Arc<dyn AssetReader>, PathBuf), AssetError> {
+        let asset_path = AssetPath::parse(path);
+        let sources = self.sources.read().unwrap();
+        match sources.get(asset_path.source()) {
+            Some(reader) => Ok((reader, asset_path.path().to_path_buf())),
+            None => Err(AssetError::UnknownSource {
+                path: path.to_path_buf(),
+                known: sources.names(),
+            }),
+        }
+    }
+
+    fn read(&self, path: &Path) -> std::result::Result<Vec<u8>, AssetError> {
+        let (reader, source_path) = self.resolve(path)?;
+        reader
+            .read(&source_path)
+            .map_err(|err| AssetError::Io(path.to_path_buf(), err))
+    }
+
+    fn process(
+        &self,
+        processor: &dyn AssetProcessorAny,
+        bytes: &[u8],
+        path: &Path,
+    ) -> std::result::Result<Processed, AssetError> {
+        match &*self.cache.read().unwrap() {
+            Some(cache) => cache.load_or_process(processor, bytes, path),
+            None => processor
+                .process(bytes, path)
+                .map(|bytes| Processed {
+                    bytes,
+                    cache_error: None,
+                })
+                .map_err(|err| AssetError::Process(path.to_path_buf(), err)),
+        }
+    }
+
+    fn load_with<T: Send + Sync + 'static>(&self, path: impl AsRef<Path>, mime: Option<&str>) -> Handle<T> {
+        let path = path.as_ref().to_path_buf();
+        let type_id = TypeId::of::<T>();
+        let mut state = self.state.lock().unwrap();
+
+        let key = (type_id, path.clone());
+        if let Some(handle) = state.paths.get(&key).and_then(Handle::from_weak_ref) {
+            if !matches!(self.load_state(handle.id()), Some(LoadState::Failed(_))) {
+                return handle;
+            }
+        }
+
+        let handle = self.allocator.strong::<T>();
+        state.paths.insert(key, handle.weak_ref());
+
+        let selected = state
+            .select(type_id, type_name::<T>(), &path, mime)
+            .and_then(|selected| Ok((selected, self.resolve(&path)?)));
+        let ((loader, processor), (reader, source_path)) = match selected {
+            Ok(selected) => selected,
+            Err(err) => {
+                let err = Arc::new(err);
+                self.loading.progress.lock().unwrap().insert(
+                    handle.id(),
+                    LoadProgress {
+                        state: LoadState::Failed(err.clone()),
+                        stages: &[],
+                    },
+                );
+                self.loading.fail(type_id, handle.weak_ref(), err);
+                return handle;
+            }
+        };
+        state.origins.insert(
+            handle.id(),
+            LoadOrigin {
+                type_id,
+                asset: type_name::<T>(),
+                path: path.clone(),
+                reader: reader.clone(),
+                source_path: source_path.clone(),
+                mime: mime.map(str::to_string),
+                handle: handle.weak_ref(),
+                modified: None,
+            },
+        );
+        drop(state);
+
+        self.loading.progress.lock().unwrap().insert(
+            handle.id(),
+            LoadProgress {
+                state: LoadState::Queued,
+                stages: loader.stages(),
+            },
+        );
+
+        self.queue(LoadJob {
+            type_id,
+            handle: handle.weak_ref(),
+            id: handle.id(),
+            path,
+            reader,
+            source_path,
+            loader,
+            processor,
+            reload: None,
+        });
+
+        handle
+    }
+
+    fn load_state(&self, id: AssetId) -> Option<LoadState> {
+        let progress = self.loading.progress.lock().unwrap();
+        progress.get(&id).map(|progress| progress.state.clone())
+    }
+
+    fn recursive_load_state(&self, id: AssetId) -> RecursiveLoadState {
+        let state = self.state.lock().unwrap();
+        let progress = self.loading.progress.lock().unwrap();
+
+        let mut pending = false;
+        let mut visited = vec![id];
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            match progress.get(&id).map(|progress| &progress.state) {
+                Some(LoadState::Failed(err)) => return RecursiveLoadState::Failed(err.clone()),
+                Some(LoadState::Queued | LoadState::Stage(_)) => pending = true,
+                Some(LoadState::Loaded) | None => {}
+            }
+
+            let dependencies = state.dependencies.get(&id).map_or(&[][..], Vec::as_slice);
+            for dependency in dependencies {
+                if !visited.contains(dependency) {
+                    visited.push(*dependency);
+                    stack.push(*dependency);
+                }
+            }
+        }
+
+        match pending {
+            true => RecursiveLoadState::Loading,
+            false => RecursiveLoadState::Loaded,
+        }
+    }
+
+    /// Queues a reload for every loaded asset whose file is newer than the loaded data
+    fn poll_changes(&self) {
+        let mut state = self.state.lock().unwrap();
+        match &mut state.watch {
+            Some(watch) if watch.last.elapsed() >= watch.interval => watch.last = Instant::now(),
+            _ => return,
+        }
+
+        let mut changed = Vec::new();
+        state.origins.iter_mut().for_each(|(id, origin)| {
+            let Some(loaded) = origin.modified else {
+                return;
+            };
+            let modified = origin.reader.modified(&origin.source_path);
+            if let Some(modified) = modified.filter(|modified| *modified > loaded) {
+                origin.modified = Some(modified);
+                changed.push(*id);
+            }
+        });
+
+        let jobs: Vec<_> = changed
+            .into_iter()
+            .filter_map(|id| state.reload_job(id, Reload { chain: vec![id] }))
+            .collect();
+        drop(state);
+
+        jobs.into_iter().for_each(|job| self.queue(job));
+    }
+}
+
+/// Loads assets on worker threads, loaded assets show up in `Assets<T>` on the spin after their
+/// last stage finished
+///
+/// Plain paths are read from the root directory, `name://path` reads from the source added as `name`
+pub struct AssetServer {
+    shared: Arc<ServerShared>,
+    drops: Mutex<Receiver<(TypeId, AssetId)>>,
+}
+
+impl AssetServer {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let (allocator, drops) = HandleAllocator::new();
+        let shared = ServerShared {
+            sources: RwLock::new(AssetSources::new(DirectoryReader::new(root))),
+            allocator,
+            state: Mutex::default(),
+            loading: Loading::default(),
+            cache: RwLock::default(),
+            workers: OnceLock::new(),
+        };
+
+        Self {
+            shared: Arc::new(shared),
+            drops: Mutex::new(drops),
+        }
+    }
+
+    pub(crate) fn set_root(&mut self, root: PathBuf) {
+        let mut sources = self.shared.sources.write().unwrap();
+        sources.set_default(DirectoryReader::new(root));
+    }
+
+    /// Makes `reader` available as `name://` in asset paths, replacing an earlier source of that name
+    pub fn add_source(&mut self, name: impl Into<String>, reader: impl AssetReader) {
+        let mut sources = self.shared.sources.write().unwrap();
+        sources.insert(name, reader);
+    }
+
+    /// Reads the bytes behind an asset path on the calling thread, for data that is not an asset
+    pub fn read(&self, path: impl AsRef<Path>) -> std::result::Result<Vec<u8>, AssetError> {
+        self.shared.read(path.as_ref())
+    }
+
+    /// Without a cache processors run on every load
+    pub fn set_cache(&mut self, cache: ProcessedCache) {
+        *self.shared.cache.write().unwrap() = Some(cache);
+    }
+
+    /// Runs `processor` on files with its extensions before their bytes reach a loader
+    pub fn register_processor<P: AssetProcessor>(&mut self, processor: P) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.processors.push(Arc::new(processor));
+    }
+
+    /// Processes `path` on the calling thread and fills the cache, meant for warming the cache
+    /// before shipping. Does nothing for paths without a processor
+    pub fn process(&self, path: impl AsRef<Path>) -> std::result::Result<(), AssetError> {
+        let path = path.as_ref();
+        let processor = self.shared.state.lock().unwrap().select_processor(path, None);
+        if let Some(processor) = processor {
+            let bytes = self.shared.read(path)?;
+            let processed = self.shared.process(processor.as_ref(), &bytes, path)?;
+            if let Some(err) = processed.cache_error {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the storage for `T`, dropped handles of its assets are unloaded by the server
+    pub fn add_assets<T: Send + Sync + 'static>(&mut self) -> Assets<T> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.storages.insert(TypeId::of::<T>(), AssetStorage::new::<T>());
+
+        Assets::new(self.shared.allocator.clone())
+    }
+
+    /// Adds `loader` to the loaders of its asset type and returns a new empty `Assets<T>`
+    pub fn register_loader<L: AssetLoader>(&mut self, loader: L) -> Assets<L::Asset> {
+        let mut state = self.shared.state.lock().unwrap();
+        state
+            .loaders
+            .entry(TypeId::of::<L::Asset>())
+            .or_default()
+            .push(Arc::new(loader));
+        drop(state);
+
+        self.add_assets()
+    }
+
+    /// Queues `path` for loading with the loader matching its extension and returns its handle right away
+    ///
+    /// Loading a path twice returns the same asset while any strong handle to it is alive, unless
+    /// the first load failed
+    pub fn load<T: Send + Sync + 'static>(&self, path: impl AsRef<Path>) -> Handle<T> {
+        self.shared.workers();
+        self.shared.load_with(path, None)
+    }
+
+    /// Like `load` but picks the loader by MIME type instead of extension
+    pub fn load_with_mime<T: Send + Sync + 'static>(&self, path: impl AsRef<Path>, mime: &str) -> Handle<T> {
+        self.shared.workers();
+        self.shared.load_with(path, Some(mime))
+    }
+
+    /// Polls the files of loaded assets every `interval` and reloads the ones that changed, assets
+    /// that loaded a changed asset as a dependency are reloaded after it
+    pub fn watch_for_changes(&mut self, interval: Duration) {
+        self.shared.state.lock().unwrap().watch = Some(Watch {
+            interval,
+            last: Instant::now(),
+        });
+    }
+
+    /// `None` for handles the server did not load, such as ones from `Assets::add`
+    pub fn load_state<T: 'static>(&self, handle: &Handle<T>) -> Option<LoadState> {
+        self.shared.load_state(handle.id())
+    }
+
+    pub fn is_loaded<T: 'static>(&self, handle: &Handle<T>) -> bool {
+        matches!(self.load_state(handle), Some(LoadState::Loaded))
+    }
+
+    /// State of the asset together with everything it loaded through its `LoadContext`, transitively
+    pub fn recursive_load_state<T: 'static>(&self, handle: &Handle<T>) -> RecursiveLoadState {
+        self.shared.recursive_load_state(handle.id())
+    }
+
+    pub fn is_loaded_with_dependencies<T: 'static>(&self, handle: &Handle<T>) -> bool {
+        matches!(self.recursive_load_state(handle), RecursiveLoadState::Loaded)
+    }
+
+    /// Progress of every asset the server holds, weighted by `load_impact` and between 0 and 1
+    ///
+    /// Failed assets count as finished, check `load_state` to tell them apart
+    pub fn progress(&self) -> f32 {
+        let progress = self.shared.loading.progress.lock().unwrap();
+        if progress.is_empty() {
+            return 1.0;
+        }
+
+        progress.values().map(LoadProgress::fraction).sum::<f32>() / progress.len() as f32
+    }
+
+    /// Path `handle` was loaded from
+    pub fn path<T: 'static>(&self, handle: &Handle<T>) -> Option<PathBuf> {
+        let state = self.shared.state.lock().unwrap();
+        state
+            .paths
+            .iter()
+            .find(|((type_id, _), reference)| {
+                *type_id == TypeId::of::<T>() && Weak::ptr_eq(reference, &handle.weak_ref())
+            })
+            .map(|((_, path), _)| path.clone())
+    }
+}
+
+/// Applies finished loads, reports failed ones and unloads assets whose last strong handle dropped
+pub(crate) fn update_assets(world: &mut World) {
+    let Some(server) = world.get_resource::<AssetServer>() else {
+        return;
+    };
+
+    let shared = server.shared.clone();
+    shared.poll_changes();
+    let mut loaded = std::mem::take(&mut *shared.loading.loaded.lock().unwrap());
+    let failed = std::mem::take(&mut *shared.loading.failed.lock().unwrap());
+    let dropped: Vec<_> = server.drops.lock().unwrap().try_iter().collect();
+
+    let mut state = shared.state.lock().unwrap();
+    if !dropped.is_empty() {
+        state.paths.retain(|_, reference| reference.strong_count() > 0);
+        dropped.iter().for_each(|(_, id)| {
+            state.origins.remove(id);
+            state.dependencies.remove(id);
+        });
+    }
+
+    let mut reloads = Vec::new();
+    loaded.iter_mut().filter(|loaded| loaded.handle.strong_count() > 0).for_each(|loaded| {
+        if let Some(origin) = state.origins.get_mut(&loaded.id) {
+            origin.modified = origin.modified.max(loaded.modified);
+        }
+        let dependencies = std::mem::take(&mut loaded.dependencies);
+        state.dependencies.insert(loaded.id, dependencies);
+
+        if let Some(reload) = &loaded.reload {
+            state
+                .dependents(loaded.id)
+                .into_iter()
+                .filter(|dependent| !reload.chain.contains(dependent))
+                .for_each(|dependent| {
+                    let mut chain = reload.chain.clone();
+                    chain.push(dependent);
+                    reloads.extend(state.reload_job(dependent, Reload { chain }));
+                });
+        }
+    });
+    let storages = state.storages.clone();
+    drop(state);
+
+    reloads.into_iter().for_each(|job| shared.queue(job));
+
+    {
+        let mut progress = shared.loading.progress.lock().unwrap();
+        loaded.iter().for_each(|loaded| {
+            if let Some(progress) = progress.get_mut(&loaded.id) {
+                progress.state = LoadState::Loaded;
+            }
+        });
+        dropped.iter().for_each(|(_, id)| {
+            progress.remove(id);
+        });
+    }
+
+    loaded
+        .into_iter()
+        .filter(|loaded| loaded.handle.strong_count() > 0)
+        .for_each(|loaded| {
+            if let Some(storage) = storages.get(&loaded.type_id) {
+                (storage.insert)(world, &loaded.handle, loaded.asset);
+            }
+        });
+
+    failed.into_iter().for_each(|failed| {
+        if let Some(storage) = storages.get(&failed.type_id) {
+            (storage.fail)(world, &failed.handle, failed.err, failed.kind);
+        }
+    });
+
+    dropped.into_iter().for_each(|(type_id, id)| {
+        if let Some(storage) = storages.get(&type_id) {
+            (storage.remove)(world, id);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::SyncSender;
+
+    use super::*;
+    use crate::asset::MemoryReader;
+
+    /// Parses its bytes as UTF-8, the second stage waits for the test to release it
+    struct TextLoader {
+        bake: Option<Mutex<Receiver<()>>>,
+    }
+
+    impl TextLoader {
+        fn new() -> Self {
+            Self { bake: None }
+        }
+
+        fn gated() -> (Self, SyncSender<()>) {
+            let (sender, receiver) = mpsc::sync_channel(0);
+            let loader = Self {
+                bake: Some(Mutex::new(receiver)),
+            };
+            (loader, sender)
+        }
+    }
+
+    impl AssetLoader for TextLoader {
+        type Asset = String;
+        const EXTENSIONS: &'static [&'static str] = &["txt"];
+        const STAGES: &'static [StageInfo] = &[
+            StageInfo {
+                name: "Parse",
+                load_impact: 1.0,
+            },
+            StageInfo {
+                name: "Bake",
+                load_impact: 3.0,
+            },
+        ];
+
+        fn load(&self, bytes: &[u8], _: &mut LoadContext) -> Result<String> {
+            Ok(String::from_utf8(bytes.to_vec())?)
+        }
+
+        fn process(&self, _: &mut String, _: usize) -> Result<()> {
+            if let Some(bake) = &self.bake {
+                bake.lock().unwrap().recv()?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Turns `.lower` files into upper case `.txt` artifacts
+    struct UpperProcessor;
+
+    impl AssetProcessor for UpperProcessor {
+        const EXTENSIONS: &'static [&'static str] = &["lower"];
+        const OUTPUT_EXTENSION: &'static str = "txt";
+        const VERSION: u32 = 1;
+
+        fn process(&self, bytes: &[u8], _: &Path) -> Result<Vec<u8>> {
+            Ok(bytes.to_ascii_uppercase())
+        }
+    }
+
+    /// A second `String` loader, picked by its extensions or MIME type
+    struct ShoutLoader;
+
+    impl AssetLoader for ShoutLoader {
+        type Asset = String;
+        const EXTENSIONS: &'static [&'static str] = &["shout", "txt.gz"];
+        const MIME_TYPES: &'static [&'static str] = &["text/x-shout"];
+
+        fn load(&self, bytes: &[u8], _: &mut LoadContext) -> Result<String> {
+            Ok(String::from_utf8(bytes.to_ascii_uppercase())?)
+        }
+    }
+
+    /// Lists of paths, one per line, `.list` entries load nested lists and the rest text
+    struct Bundle {
+        lists: Vec<Handle<Bundle>>,
+        texts: Vec<Handle<String>>,
+    }
+
+    struct ListLoader;
+
+    impl AssetLoader for ListLoader {
+        type Asset = Bundle;
+        const EXTENSIONS: &'static [&'static str] = &["list"];
+
+        fn load(&self, bytes: &[u8], context: &mut LoadContext) -> Result<Bundle> {
+            let mut bundle = Bundle {
+                lists: Vec::new(),
+                texts: Vec::new(),
+            };
+            for line in std::str::from_utf8(bytes)?.lines() {
+                match line.ends_with(".list") {
+                    true => bundle.lists.push(context.load(line)),
+                    false => bundle.texts.push(context.load(line)),
+                }
+            }
+            Ok(bundle)
+        }
+    }
+
+    /// Adds `ListLoader` next to the text loader of `world_with`
+    fn with_lists(mut world: World) -> World {
+        let assets = unsafe { world.get_resource_mut::<AssetServer>() }
+            .unwrap()
+            .register_loader(ListLoader);
+        world.store_resource(assets);
+        world.store_resource(Events::<AssetEvent<Bundle>>::new());
+        world
+    }
+
+    fn bundle<'a>(world: &'a World, handle: &Handle<Bundle>) -> Option<&'a Bundle> {
+        world.get_resource::<Assets<Bundle>>().unwrap().get(handle)
+    }
+
+    fn recursively_loaded<T: 'static>(world: &World, handle: &Handle<T>) -> bool {
+        matches!(server(world).recursive_load_state(handle), RecursiveLoadState::Loaded)
+    }
+
+    fn world_with(loader: TextLoader, files: &MemoryReader) -> World {
+        let mut server = AssetServer::new("assets");
+        server.add_source("mem", files.clone());
+        let assets = server.register_loader(loader);
+
+        let mut world = World::new();
+        world.store_resource(server);
+        world.store_resource(assets);
+        world.store_resource(Events::<AssetEvent<String>>::new());
+        world
+    }
+
+    fn server(world: &World) -> &AssetServer {
+        world.get_resource::<AssetServer>().unwrap()
+    }
+
+    fn events(world: &World) -> &[AssetEvent<String>] {
+        world.get_resource::<Events<AssetEvent<String>>>().unwrap().pending()
+    }
+
+    fn text<'a>(world: &'a World, handle: &Handle<String>) -> Option<&'a str> {
+        world.get_resource::<Assets<String>>().unwrap().get(handle).map(String::as_str)
+    }
+
+    /// Spins the asset server until `done` holds
+    fn wait_for(world: &mut World, mut done: impl FnMut(&World) -> bool) {
+        let start = Instant::now();
+        while !done(world) {
+            assert!(start.elapsed() < Duration::from_secs(5), "timed out waiting for the asset server");
+            thread::sleep(Duration::from_millis(1));
+            update_assets(world);
+        }
+    }
+
+    #[test]
+    fn progress_weighs_stages_by_load_impact() {
+        let progress = |state, stages| LoadProgress { state, stages }.fraction();
+        let stages = TextLoader::STAGES;
+        assert_eq!(progress(LoadState::Queued, stages), 0.0);
+        assert_eq!(progress(LoadState::Stage(0), stages), 0.0);
+        assert_eq!(progress(LoadState::Stage(1), stages), 0.25);
+        assert_eq!(progress(LoadState::Loaded, stages), 1.0);
+
+        let err = Arc::new(AssetError::Io(PathBuf::new(), io::ErrorKind::NotFound.into()));
+        assert_eq!(progress(LoadState::Failed(err), stages), 1.0);
+
+        let unweighted = &[
+            StageInfo {
+                name: "Memory",
+                load_impact: 0.0,
+            },
+            StageInfo {
+                name: "GPU",
+                load_impact: 0.0,
+            },
+        ];
+        assert_eq!(progress(LoadState::Stage(1), unweighted), 0.5);
+    }
+
+    #[test]
+    fn server_progress_while_loading() {
+        let files = MemoryReader::new().with_file("a.txt", "a");
+        let (loader, bake) = TextLoader::gated();
+        let mut world = world_with(loader, &files);
+        assert_eq!(server(&world).progress(), 1.0);
+
+        let handle = server(&world).load::<String>("mem://a.txt");
+        wait_for(&mut world, |world| {
+            matches!(server(world).load_state(&handle), Some(LoadState::Stage(1)))
+        });
+        assert_eq!(server(&world).progress(), 0.25);
+        assert!(text(&world, &handle).is_none());
+
+        bake.send(()).unwrap();
+        wait_for(&mut world, |world| server(world).is_loaded(&handle));
+        assert_eq!(server(&world).progress(), 1.0);
+        assert_eq!(text(&world, &handle), Some("a"));
+    }
+
+    #[test]
+    fn failed_loads_count_as_finished() {
+        let files = MemoryReader::new().with_file("bad.txt", vec![0xff]);
+        let mut world = world_with(TextLoader::new(), &files);
+
+        let handle = server(&world).load::<String>("mem://bad.txt");
+        let missing = server(&world).load::<String>("mem://missing.txt");
+        wait_for(&mut world, |world| {
+            [&handle, &missing]
+                .iter()
+                .all(|handle| matches!(server(world).load_state(handle), Some(LoadState::Failed(_))))
+        });
+        assert_eq!(server(&world).progress(), 1.0);
+        assert!(text(&world, &handle).is_none());
+
+        wait_for(&mut world, |world| events(world).len() == 2);
+        let failed: Vec<_> = events(&world)
+            .iter()
+            .map(|event| match event {
+                AssetEvent::Failed(failed, _) => failed.id(),
+                event => panic!("expected a failure, got {event:?}"),
+            })
+            .collect();
+        assert!(failed.contains(&handle.id()) && failed.contains(&missing.id()));
+    }
+
+    #[test]
+    fn unload_after_last_strong_drop() {
+        let files = MemoryReader::new().with_file("a.txt", "a");
+        let mut world = world_with(TextLoader::new(), &files);
+
+        let handle = server(&world).load::<String>("mem://a.txt");
+        let id = handle.id();
+        wait_for(&mut world, |world| text(world, &handle).is_some());
+        assert!(matches!(events(&world), [AssetEvent::Loaded(loaded)] if loaded.id() == id));
+
+        drop(handle);
+        update_assets(&mut world);
+        assert!(world.get_resource::<Assets<String>>().unwrap().is_empty());
+        assert!(matches!(events(&world), [_, AssetEvent::Unloaded(unloaded)] if *unloaded == id));
+    }
+
+    #[test]
+    fn hot_reload_replaces_changed_assets() {
+        let files = MemoryReader::new().with_file("a.txt", "one");
+        let mut world = world_with(TextLoader::new(), &files);
+        unsafe { world.get_resource_mut::<AssetServer>() }
+            .unwrap()
+            .watch_for_changes(Duration::ZERO);
+
+        let handle = server(&world).load::<String>("mem://a.txt");
+        wait_for(&mut world, |world| text(world, &handle).is_some());
+        assert_eq!(text(&world, &handle), Some("one"));
+
+        thread::sleep(Duration::from_millis(2));
+        files.insert("a.txt", "two");
+        wait_for(&mut world, |world| text(world, &handle) == Some("two"));
+        assert!(matches!(events(&world), [_, AssetEvent::Modified(modified)] if *modified == handle));
+        assert!(server(&world).is_loaded(&handle));
+
+        // Unchanged files are not loaded again
+        (0..10).for_each(|_| update_assets(&mut world));
+        assert_eq!(events(&world).len(), 2);
+    }
+
+    #[test]
+    fn failed_reload_keeps_previous_asset() {
+        let files = MemoryReader::new().with_file("a.txt", "one");
+        let mut world = world_with(TextLoader::new(), &files);
+        unsafe { world.get_resource_mut::<AssetServer>() }
+            .unwrap()
+            .watch_for_changes(Duration::ZERO);
+
+        let handle = server(&world).load::<String>("mem://a.txt");
+        wait_for(&mut world, |world| text(world, &handle).is_some());
+
+        thread::sleep(Duration::from_millis(2));
+        files.insert("a.txt", vec![0xff]);
+        wait_for(&mut world, |world| events(world).len() == 2);
+        match &events(&world)[1] {
+            AssetEvent::Failed(failed, err) => {
+                assert_eq!(*failed, handle);
+                assert!(matches!(**err, AssetError::Load(..)));
+            }
+            event => panic!("expected a failed reload, got {event:?}"),
+        }
+        assert_eq!(text(&world, &handle), Some("one"));
+        assert!(server(&world).is_loaded(&handle));
+
+        thread::sleep(Duration::from_millis(2));
+        files.insert("a.txt", "three");
+        wait_for(&mut world, |world| text(world, &handle) == Some("three"));
+    }
+
+    #[test]
+    fn cache_write_failures_are_reported() {
+        let blocker = std::env::temp_dir().join(format!("isle_cache_blocker_{}", std::process::id()));
+        std::fs::write(&blocker, "not a directory").unwrap();
+
+        let files = MemoryReader::new().with_file("a.lower", "a");
+        let mut world = world_with(TextLoader::new(), &files);
+        let server_mut = unsafe { world.get_resource_mut::<AssetServer>() }.unwrap();
+        server_mut.register_processor(UpperProcessor);
+        server_mut.set_cache(ProcessedCache::new(blocker.join("cache")));
+
+        let warmed = server(&world).process("mem://a.lower");
+        let handle = server(&world).load::<String>("mem://a.lower");
+        wait_for(&mut world, |world| text(world, &handle).is_some());
+        std::fs::remove_file(&blocker).unwrap();
+
+        assert!(matches!(warmed, Err(AssetError::Cache(..))));
+        assert_eq!(text(&world, &handle), Some("A"));
+        assert!(server(&world).is_loaded(&handle));
+        assert!(events(&world).iter().any(|event| {
+            matches!(event, AssetEvent::CacheWriteFailed(failed, err) if *failed == handle && matches!(**err, AssetError::Cache(..)))
+        }));
+        assert!(!events(&world).iter().any(|event| matches!(event, AssetEvent::Failed(..))));
+    }
+
+    #[test]
+    fn dependencies_load_transitively() {
+        let files = MemoryReader::new()
+            .with_file("lists/a.list", "b.list\n../c.txt")
+            .with_file("lists/b.list", "mem://d.txt")
+            .with_file("c.txt", "c")
+            .with_file("d.txt", "d");
+        let mut world = with_lists(world_with(TextLoader::new(), &files));
+
+        let a = server(&world).load::<Bundle>("mem://lists/a.list");
+        wait_for(&mut world, |world| recursively_loaded(world, &a));
+
+        let b = bundle(&world, &a).unwrap().lists[0].clone();
+        let c = bundle(&world, &a).unwrap().texts[0].clone();
+        let d = bundle(&world, &b).unwrap().texts[0].clone();
+        assert_eq!(text(&world, &c), Some("c"));
+        assert_eq!(text(&world, &d), Some("d"));
+
+        // Dependencies are the same assets as loading their resolved paths directly
+        assert_eq!(server(&world).load::<String>("mem://c.txt"), c);
+        assert_eq!(server(&world).load::<Bundle>("mem://lists/b.list"), b);
+    }
+
+    #[test]
+    fn recursive_state_waits_for_dependencies() {
+        let files = MemoryReader::new()
+            .with_file("a.list", "b.txt")
+            .with_file("b.txt", "b");
+        let (loader, bake) = TextLoader::gated();
+        let mut world = with_lists(world_with(loader, &files));
+
+        let a = server(&world).load::<Bundle>("mem://a.list");
+        wait_for(&mut world, |world| server(world).is_loaded(&a));
+        let b = bundle(&world, &a).unwrap().texts[0].clone();
+        wait_for(&mut world, |world| {
+            matches!(server(world).load_state(&b), Some(LoadState::Stage(1)))
+        });
+        assert!(matches!(server(&world).recursive_load_state(&a), RecursiveLoadState::Loading));
+
+        bake.send(()).unwrap();
+        wait_for(&mut world, |world| recursively_loaded(world, &a));
+        assert_eq!(text(&world, &b), Some("b"));
+    }
+
+    #[test]
+    fn failed_dependencies_fail_their_dependents() {
+        let files = MemoryReader::new()
+            .with_file("a.list", "b.list")
+            .with_file("b.list", "ok.txt\nmissing.txt")
+            .with_file("ok.txt", "ok");
+        let mut world = with_lists(world_with(TextLoader::new(), &files));
+
+        let a = server(&world).load::<Bundle>("mem://a.list");
+        wait_for(&mut world, |world| {
+            matches!(server(world).recursive_load_state(&a), RecursiveLoadState::Failed(_))
+        });
+        match server(&world).recursive_load_state(&a) {
+            RecursiveLoadState::Failed(err) => assert!(matches!(*err, AssetError::Io(..))),
+            _ => unreachable!(),
+        }
+        assert!(server(&world).is_loaded(&a));
+        assert!(!recursively_loaded(&world, &a));
+    }
+
+    #[test]
+    fn reloaded_dependencies_reload_their_dependents() {
+        let files = MemoryReader::new()
+            .with_file("a.list", "b.list")
+            .with_file("b.list", "c.txt")
+            .with_file("c.txt", "one");
+        let mut world = with_lists(world_with(TextLoader::new(), &files));
+        unsafe { world.get_resource_mut::<AssetServer>() }
+            .unwrap()
+            .watch_for_changes(Duration::ZERO);
+
+        let a = server(&world).load::<Bundle>("mem://a.list");
+        wait_for(&mut world, |world| recursively_loaded(world, &a));
+        let b = bundle(&world, &a).unwrap().lists[0].clone();
+
+        thread::sleep(Duration::from_millis(2));
+        files.insert("c.txt", "two");
+        let modified = |world: &World, handle: &Handle<Bundle>| {
+            world
+                .get_resource::<Events<AssetEvent<Bundle>>>()
+                .unwrap()
+                .pending()
+                .iter()
+                .any(|event| matches!(event, AssetEvent::Modified(modified) if modified == handle))
+        };
+        wait_for(&mut world, |world| modified(world, &a) && modified(world, &b));
+
+        let c = bundle(&world, &bundle(&world, &a).unwrap().lists[0]).unwrap().texts[0].clone();
+        assert_eq!(text(&world, &c), Some("two"));
+        assert!(recursively_loaded(&world, &a));
+    }
+
+    #[test]
+    fn loaders_are_selected_by_extension_or_mime_type() {
+        let files = MemoryReader::new()
+            .with_file("a.txt", "a")
+            .with_file("b.SHOUT", "b")
+            .with_file("c.txt", "c")
+            .with_file("d.txt.gz", "d");
+        let mut world = world_with(TextLoader::new(), &files);
+        // Loaders of the same asset type share the `Assets<String>` already in the world
+        unsafe { world.get_resource_mut::<AssetServer>() }
+            .unwrap()
+            .register_loader(ShoutLoader);
+
+        let a = server(&world).load::<String>("mem://a.txt");
+        let b = server(&world).load::<String>("mem://b.SHOUT");
+        let c = server(&world).load_with_mime::<String>("mem://c.txt", "Text/X-Shout");
+        let d = server(&world).load::<String>("mem://d.txt.gz");
+        wait_for(&mut world, |world| [&a, &b, &c, &d].iter().all(|handle| text(world, handle).is_some()));
+
+        assert_eq!(text(&world, &a), Some("a"));
+        assert_eq!(text(&world, &b), Some("B"));
+        assert_eq!(text(&world, &c), Some("C"));
+        assert_eq!(text(&world, &d), Some("D"));
+    }
+
+    #[test]
+    fn no_loader_lists_supported_types() {
+        let files = MemoryReader::new().with_file("a.bin", "a");
+        let mut server = AssetServer::new("assets");
+        server.add_source("mem", files);
+        server.register_loader(TextLoader::new());
+        server.register_loader(ShoutLoader);
+
+        let state = server.shared.state.lock().unwrap();
+        let no_loader = |path: &str, mime| {
+            let err = state
+                .select_loader(TypeId::of::<String>(), "String", Path::new(path), mime)
+                .err()
+                .unwrap();
+            assert!(matches!(err, AssetError::NoLoader { .. }));
+            err.to_string()
+        };
+        assert_eq!(
+            no_loader("mem://a.bin", None),
+            "No loader for asset type String accepts mem://a.bin\nHint: supported types are .txt, .shout, .txt.gz, text/x-shout"
+        );
+        assert!(no_loader("mem://a.txt", Some("text/plain")).starts_with("No loader for asset type String accepts text/plain\n"));
+
+        let err = state
+            .select_loader(TypeId::of::<u32>(), "u32", Path::new("mem://a.bin"), None)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "No loader registered for asset type u32 to load mem://a.bin\nHint: register one with FlowBuilder::with_asset_loader"
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use std::{
+    fmt::{Debug, Display},
+    io::{self, Read, Write},
+    marker::PhantomData,
+    net::{Shutdown, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+use isle_ecs::world::World;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{event::Events, executor::Executor, plugin::EngineHook, schedule::Scheduler};
+
+/// Frames larger than this are rejected instead of allocated
+pub const MAX_FRAME_LEN: u32 = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum BridgeError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    FrameTooLarge(u32),
+    Disconnected,
+}
+
+impl std::error::Error for BridgeError {}
+
+impl Display for BridgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Event bridge I/O failed: {err}"),
+            Self::Format(err) => write!(
+                f,
+                "Event could not be encoded: {err}\nHint: both ends of a bridge must use the same event type"
+            ),
+            Self::FrameTooLarge(len) => write!(
+                f,
+                "Event frame of {len} bytes exceeds the limit of {MAX_FRAME_LEN}\nHint: the remote end is probably not speaking the bridge protocol"
+            ),
+            Self::Disconnected => write!(f, "Event bridge endpoint disconnected"),
+        }
+    }
+}
+
+impl From<io::Error> for BridgeError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for BridgeError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Format(err)
+    }
+}
+
+/// Writes `event` as a little endian `u32` length followed by its JSON encoding
+pub fn write_frame<T: Serialize>(writer: &mut impl Write, event: &T) -> Result<(), BridgeError> {
+    let payload = serde_json::to_vec(event)?;
+    let len = u32::try_from(payload.len()).unwrap_or(u32::MAX);
+    if len > MAX_FRAME_LEN {
+        return Err(BridgeError::FrameTooLarge(len));
+    }
+
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&payload)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads one frame written by `write_frame`, `Disconnected` on a clean end of stream
+pub fn read_frame<T: DeserializeOwned>(reader: &mut impl Read) -> Result<T, BridgeError> {
+    let mut len = [0; 4];
+    match reader.read_exact(&mut len) {
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Err(BridgeError::Disconnected),
+        result => result?,
+    }
+
+    let len = u32::from_le_bytes(len);
+    if len > MAX_FRAME_LEN {
+        return Err(BridgeError::FrameTooLarge(len));
+    }
+
+    let mut payload = vec![0; len as usize];
+    reader.read_exact(&mut payload)?;
+    Ok(serde_json::from_slice(&payload)?)
+}
+
+enum Outgoing<T> {
+    Channel(Sender<T>),
+    Stream(Box<dyn Write + Send>),
+}
+
+/// Forwards events of type `T` between the world and an external endpoint
+///
+/// Stored as a resource and pumped by `BridgeHook<T>`, see `FlowBuilder::with_event_bridge`
+pub struct EventBridge<T> {
+    incoming: Receiver<T>,
+    outgoing: Outgoing<T>,
+    connected: bool,
+    shutdown: Option<Box<dyn Fn() + Send>>,
+}
+
+impl<T: Serialize + DeserializeOwned + Send + 'static> EventBridge<T> {
+    pub fn from_channel(sender: Sender<T>, receiver: Receiver<T>) -> Self {
+        Self {
+            incoming: receiver,
+            outgoing: Outgoing::Channel(sender),
+            connected: true,
+            shutdown: None,
+        }
+    }
+
+    /// Creates two bridges connected to each other, typically one for the world and one for another thread
+    pub fn pair() -> (Self, Self) {
+        let (to_remote, from_local) = mpsc::channel();
+        let (to_local, from_remote) = mpsc::channel();
+        (
+            Self::from_channel(to_remote, from_remote),
+            Self::from_channel(to_local, from_local),
+        )
+    }
+
+    /// Bridges over a byte stream, incoming frames are read on a background thread
+    pub fn from_stream<S: Read + Write + Send + 'static>(reader: S, writer: S) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = reader;
+            while let Ok(event) = read_frame(&mut reader) {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            incoming: receiver,
+            outgoing: Outgoing::Stream(Box::new(writer)),
+            connected: true,
+            shutdown: None,
+        }
+    }
+
+    pub fn from_tcp(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        let handle = stream.try_clone()?;
+        let mut bridge = Self::from_stream(stream.try_clone()?, stream);
+        bridge.shutdown = Some(Box::new(move || {
+            let _ = handle.shutdown(Shutdown::Both);
+        }));
+        Ok(bridge)
+    }
+
+    pub fn connect_tcp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::from_tcp(TcpStream::connect(addr)?)
+    }
+
+    #[cfg(unix)]
+    pub fn from_unix(stream: std::os::unix::net::UnixStream) -> io::Result<Self> {
+        let handle = stream.try_clone()?;
+        let mut bridge = Self::from_stream(stream.try_clone()?, stream);
+        bridge.shutdown = Some(Box::new(move || {
+            let _ = handle.shutdown(Shutdown::Both);
+        }));
+        Ok(bridge)
+    }
+
+    #[cfg(unix)]
+    pub fn connect_unix(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        Self::from_unix(std::os::unix::net::UnixStream::connect(path)?)
+    }
+
+    /// False once either direction failed, the bridge stops forwarding after that
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    pub fn send(&mut self, event: T) -> Result<(), BridgeError> {
+        if !self.connected {
+            return Err(BridgeError::Disconnected);
+        }
+
+        let result = match &mut self.outgoing {
+            Outgoing::Channel(sender) => sender.send(event).map_err(|_| BridgeError::Disconnected),
+            Outgoing::Stream(writer) => write_frame(writer, &event),
+        };
+
+        if matches!(result, Err(BridgeError::Disconnected | BridgeError::Io(_))) {
+            self.connected = false;
+        }
+        result
+    }
+
+    /// Returns the next received event without blocking
+    pub fn try_recv(&mut self) -> Option<T> {
+        match self.incoming.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.connected = false;
+                None
+            }
+        }
+    }
+}
+
+impl<T> Drop for EventBridge<T> {
+    /// Closes socket endpoints so the remote end and the reader thread see the disconnect
+    fn drop(&mut self) {
+        if let Some(shutdown) = &self.shutdown {
+            shutdown();
+        }
+    }
+}
+
+/// Sends last frame's `Events<T>` through the `EventBridge<T>` resource and makes received
+/// events readable this frame, received events are never sent back
+pub struct BridgeHook<T>(PhantomData<T>);
+
+impl<T> Default for BridgeHook<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Clone + Debug + Serialize + DeserializeOwned + Send + 'static> BridgeHook<T> {
+    fn pump(world: &mut World) {
+        let Some(outgoing) = world.get_resource::<Events<T>>().map(|events| events.readable().to_vec())
+        else {
+            return;
+        };
+        let Some(bridge) = (unsafe { world.get_resource_mut::<EventBridge<T>>() }) else {
+            return;
+        };
+
+        for event in outgoing {
+            if bridge.send(event).is_err() {
+                break;
+            }
+        }
+        let incoming: Vec<T> = std::iter::from_fn(|| bridge.try_recv()).collect();
+
+        let events = unsafe { world.get_resource_mut::<Events<T>>() }.unwrap();
+        incoming.into_iter().for_each(|event| events.inject(event));
+    }
+}
+
+impl<S: Scheduler, E: Executor, T: Clone + Debug + Serialize + DeserializeOwned + Send + 'static>
+    EngineHook<S, E> for BridgeHook<T>
+{
+    fn pre_run(&mut self, world: &mut World, _: &mut S, _: &mut E) {
+        Self::pump(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Cursor,
+        net::TcpListener,
+        time::{Duration, Instant},
+    };
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Ping(u32);
+
+    fn recv_timeout<T: Serialize + DeserializeOwned + Send + 'static>(bridge: &mut EventBridge<T>) -> Option<T> {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(5) {
+            if let Some(event) = bridge.try_recv() {
+                return Some(event);
+            }
+            if !bridge.is_connected() {
+                return None;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        None
+    }
+
+    #[test]
+    fn frame_round_trip() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, &Ping(1)).unwrap();
+        write_frame(&mut buffer, &Ping(2)).unwrap();
+
+        let mut reader = Cursor::new(buffer);
+        assert_eq!(read_frame::<Ping>(&mut reader).unwrap(), Ping(1));
+        assert_eq!(read_frame::<Ping>(&mut reader).unwrap(), Ping(2));
+        assert!(matches!(read_frame::<Ping>(&mut reader), Err(BridgeError::Disconnected)));
+    }
+
+    #[test]
+    fn oversized_and_truncated_frames() {
+        let mut reader = Cursor::new((MAX_FRAME_LEN + 1).to_le_bytes().to_vec());
+        assert!(matches!(
+            read_frame::<Ping>(&mut reader),
+            Err(BridgeError::FrameTooLarge(len)) if len == MAX_FRAME_LEN + 1
+        ));
+
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, &Ping(1)).unwrap();
+        buffer.truncate(buffer.len() - 1);
+        assert!(matches!(read_frame::<Ping>(&mut Cursor::new(buffer)), Err(BridgeError::Io(_))));
+    }
+
+    #[test]
+    fn pair_forwards_both_ways() {
+        let (mut local, mut remote) = EventBridge::pair();
+        local.send(Ping(1)).unwrap();
+        remote.send(Ping(2)).unwrap();
+
+        assert_eq!(remote.try_recv(), Some(Ping(1)));
+        assert_eq!(local.try_recv(), Some(Ping(2)));
+        assert_eq!(local.try_recv(), None);
+
+        drop(remote);
+        assert_eq!(local.try_recv(), None);
+        assert!(!local.is_connected());
+        assert!(matches!(local.send(Ping(3)), Err(BridgeError::Disconnected)));
+    }
+
+    fn events(world: &mut World) -> &mut Events<Ping> {
+        unsafe { world.get_resource_mut::<Events<Ping>>() }.unwrap()
+    }
+
+    #[test]
+    fn pump_does_not_echo_received_events() {
+        let (local, mut remote) = EventBridge::pair();
+        let mut world = World::new();
+        world.store_resource(Events::<Ping>::new());
+        world.store_resource(local);
+
+        remote.send(Ping(1)).unwrap();
+        BridgeHook::<Ping>::pump(&mut world);
+        assert_eq!(events(&mut world).readable(), &[Ping(1)]);
+
+        events(&mut world).send(Ping(2));
+        events(&mut world).update();
+        BridgeHook::<Ping>::pump(&mut world);
+        events(&mut world).update();
+        BridgeHook::<Ping>::pump(&mut world);
+
+        assert_eq!(remote.try_recv(), Some(Ping(2)));
+        assert_eq!(remote.try_recv(), None);
+    }
+
+    #[test]
+    fn tcp_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = EventBridge::<Ping>::connect_tcp(listener.local_addr().unwrap()).unwrap();
+        let mut server = EventBridge::<Ping>::from_tcp(listener.accept().unwrap().0).unwrap();
+
+        client.send(Ping(1)).unwrap();
+        server.send(Ping(2)).unwrap();
+        assert_eq!(recv_timeout(&mut server), Some(Ping(1)));
+        assert_eq!(recv_timeout(&mut client), Some(Ping(2)));
+
+        drop(client);
+        assert_eq!(recv_timeout(&mut server), None);
+        assert!(!server.is_connected());
+    }
+}
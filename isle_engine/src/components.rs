@@ -1,14 +1,21 @@
 use isle_ecs::prelude::Component;
 use isle_math::{rotation::Rotation, vector::d3::Vec3};
+use serde::{Deserialize, Serialize};
 
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize)]
+#[component(serialize, snapshot)]
 pub struct Transform {
     position: Vec3,
     orientation: Rotation,
     scale: Vec3,
+    #[serde(skip, default = "dirty")]
     dirty: bool,
 }
 
+fn dirty() -> bool {
+    true
+}
+
 impl Transform {
     pub fn new(position: Vec3, orientation: Rotation, scale: Vec3) -> Self {
         Self {
@@ -0,0 +1,227 @@
+use std::{any::TypeId, cell::UnsafeCell, fmt::Debug};
+
+use isle_ecs::world::World;
+use isle_event::EventWriter;
+
+/// Double-buffered event queue, events sent during a frame are readable for the whole next frame
+#[derive(Clone)]
+pub struct Events<T: Clone + Debug + 'static> {
+    previous: Vec<T>,
+    current: Vec<T>,
+    frame: u64,
+}
+
+impl<T: Clone + Debug + 'static> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            frame: 0,
+        }
+    }
+}
+
+impl<T: Clone + Debug + 'static> Events<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    /// Events sent last frame, visible to readers this frame
+    pub fn readable(&self) -> &[T] {
+        &self.previous
+    }
+
+    /// Events sent so far this frame
+    pub fn pending(&self) -> &[T] {
+        &self.current
+    }
+
+    /// Number of the last swap, readers use it to tell frames apart
+    ///
+    /// Swaps run by the flow are numbered across the whole world and keep counting when a snapshot
+    /// is restored, so a restored queue never repeats a frame a reader already saw
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Makes `event` readable this frame without sending it on to the next one
+    pub(crate) fn inject(&mut self, event: T) {
+        self.previous.push(event);
+    }
+
+    /// Drops the events sent so far this frame
+    pub fn clear(&mut self) {
+        self.current.clear();
+    }
+
+    /// Makes this frame's events readable and drops last frame's
+    pub fn update(&mut self) {
+        self.swap(self.frame + 1);
+    }
+
+    fn swap(&mut self, frame: u64) {
+        self.previous.clear();
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.frame = frame;
+    }
+}
+
+/// Swaps one `Events<T>` to the given frame, forwarding to listeners when the flag is set
+type EventUpdate = fn(&mut World, u64, bool);
+
+/// Swap functions for every `Events<T>` in the world, run by `Flow::spin` before the schedules
+///
+/// Not part of snapshots, so the swap count keeps going up across restores
+#[derive(Default)]
+pub struct EventUpdates {
+    updates: Vec<(TypeId, EventUpdate)>,
+    swaps: u64,
+}
+
+impl EventUpdates {
+    pub fn register<T: Clone + Debug + 'static>(&mut self) {
+        let type_id = TypeId::of::<T>();
+        if self.updates.iter().all(|(id, _)| *id != type_id) {
+            self.updates.push((type_id, update_events::<T>));
+        }
+    }
+}
+
+/// Swaps `Events<T>` and forwards the now readable events to listeners created with `Flow::get_event_listener`
+fn update_events<T: Clone + Debug + 'static>(world: &mut World, frame: u64, forward: bool) {
+    let Some(events) = (unsafe { world.get_resource_mut::<Events<T>>() }) else {
+        return;
+    };
+    events.swap(frame);
+
+    if !forward {
+        return;
+    }
+
+    if let Some(writer) = world.get_resource::<EventWriter<T>>() {
+        let mut writer = writer.clone();
+        let events = world.get_resource::<Events<T>>().unwrap();
+        events.readable().iter().cloned().for_each(|event| writer.send(event));
+    }
+}
+
+pub(crate) fn update_all(world: &mut World) {
+    run_updates(world, true);
+}
+
+/// Swaps every `Events<T>` without forwarding to listeners, used while resimulating frames they already saw
+pub(crate) fn swap_all(world: &mut World) {
+    run_updates(world, false);
+}
+
+fn run_updates(world: &mut World, forward: bool) {
+    let Some(updates) = (unsafe { world.get_resource_mut::<EventUpdates>() }) else {
+        return;
+    };
+
+    updates.swaps += 1;
+    let frame = updates.swaps;
+    let updates: Vec<EventUpdate> = updates.updates.iter().map(|(_, update)| *update).collect();
+    updates.into_iter().for_each(|update| update(world, frame, forward));
+}
+
+/// Creates `Events<T>` and registers its swap if needed
+///
+/// Events are part of world snapshots so a rollback restores the events in flight at that tick
+pub(crate) fn init_events<T: Clone + Debug + 'static>(world: &UnsafeCell<World>) {
+    let world = unsafe { &mut *world.get() };
+    if world.get_resource::<Events<T>>().is_some() {
+        return;
+    }
+
+    world.store_resource(Events::<T>::new());
+    world.registry_mut().register_resource_snapshot::<Events<T>>();
+    if world.get_resource::<EventUpdates>().is_none() {
+        world.store_resource(EventUpdates::default());
+    }
+    unsafe { world.get_resource_mut::<EventUpdates>() }
+        .unwrap()
+        .register::<T>();
+}
+
+#[cfg(test)]
+mod tests {
+    use isle_ecs::ecs::SystemParam;
+    use isle_event::EventReader;
+
+    use super::*;
+    use crate::params::{Event, EventCursor};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Hit(u32);
+
+    fn read_all(world: &UnsafeCell<World>, cursor: &mut EventCursor) -> Vec<Hit> {
+        <Event<Hit> as SystemParam>::from_world(world, cursor, "").iter().collect()
+    }
+
+    #[test]
+    fn double_buffering() {
+        let mut events = Events::new();
+        events.send(Hit(1));
+        assert_eq!(events.pending(), &[Hit(1)]);
+        assert!(events.readable().is_empty());
+
+        events.update();
+        assert_eq!(events.readable(), &[Hit(1)]);
+        assert!(events.pending().is_empty());
+
+        events.inject(Hit(2));
+        assert_eq!(events.readable(), &[Hit(1), Hit(2)]);
+
+        events.update();
+        assert!(events.readable().is_empty());
+        assert_eq!(events.frame(), 2);
+    }
+
+    #[test]
+    fn readers_see_events_for_one_frame() {
+        let world = UnsafeCell::new(World::new());
+        let mut before = <Event<Hit> as SystemParam>::init_state(&world);
+        let mut after = <Event<Hit> as SystemParam>::init_state(&world);
+        let events = || unsafe { (*world.get()).get_resource_mut::<Events<Hit>>() }.unwrap();
+
+        events().send(Hit(1));
+        assert!(read_all(&world, &mut after).is_empty());
+
+        update_all(unsafe { &mut *world.get() });
+        // Readers running before or after the sender both see last frame's events, each once
+        assert_eq!(read_all(&world, &mut before), vec![Hit(1)]);
+        events().send(Hit(2));
+        assert_eq!(read_all(&world, &mut after), vec![Hit(1)]);
+        assert!(read_all(&world, &mut after).is_empty());
+
+        update_all(unsafe { &mut *world.get() });
+        assert_eq!(read_all(&world, &mut before), vec![Hit(2)]);
+        assert_eq!(read_all(&world, &mut after), vec![Hit(2)]);
+
+        update_all(unsafe { &mut *world.get() });
+        assert!(read_all(&world, &mut before).is_empty());
+    }
+
+    #[test]
+    fn updates_forward_to_listeners() {
+        let world = UnsafeCell::new(World::new());
+        init_events::<Hit>(&world);
+        let world = unsafe { &mut *world.get() };
+        world.store_resource(EventWriter::<Hit>::new());
+        let mut listener = EventReader::from_writer(world.get_resource::<EventWriter<Hit>>().unwrap());
+
+        unsafe { world.get_resource_mut::<Events<Hit>>() }.unwrap().send(Hit(1));
+        update_all(world);
+        assert_eq!(listener.read(), Some(Hit(1)));
+
+        unsafe { world.get_resource_mut::<Events<Hit>>() }.unwrap().send(Hit(2));
+        swap_all(world);
+        assert_eq!(world.get_resource::<Events<Hit>>().unwrap().readable(), &[Hit(2)]);
+        assert_eq!(listener.read(), None);
+    }
+}
@@ -1,8 +1,4 @@
-use std::{
-    cell::UnsafeCell,
-    fmt::Debug,
-    sync::atomic::{AtomicU32, Ordering},
-};
+use std::{cell::UnsafeCell, fmt::Debug, path::PathBuf, time::Duration};
 
 use isle_ecs::{
     ecs::{IntoSystem, System, SystemSet},
@@ -10,13 +6,32 @@ use isle_ecs::{
     prelude::Component,
     world::World,
 };
-use isle_event::{EventReader, EventWriter};
+use isle_event::{EventClock, EventReader, EventWriter, RetentionPolicy};
+use serde::{de::DeserializeOwned, Serialize};
 use winit::{
     error::EventLoopError,
     event_loop::{self, EventLoop},
 };
 
-use crate::{executor::Executor, input::InputMap, plugin::EngineHook, schedule::Scheduler};
+use crate::{
+    asset::{
+        server::update_assets, AssetEvent, AssetLoader, AssetProcessor, AssetReader, AssetServer, Assets, ProcessedCache,
+    },
+    bridge::{BridgeHook, EventBridge},
+    event::{self, init_events, Events},
+    executor::Executor,
+    input::{ActionMap, InputMap, InputRecording, InputReplay, PlayerInputs},
+    params::{get_event_writer, FixedTimestep},
+    plugin::EngineHook,
+    rollback::{Rollback, RollbackError},
+    schedule::Scheduler,
+    window::ImeSettings,
+};
+
+/// Directory the asset server loads from unless `FlowBuilder::with_asset_root` changes it
+pub const DEFAULT_ASSET_ROOT: &str = "assets";
+/// Directory processed assets are cached in unless `FlowBuilder::with_asset_cache` changes it
+pub const DEFAULT_ASSET_CACHE: &str = ".asset_cache";
 
 pub mod stages {
     pub const PRE_RUN: usize = 0;
@@ -34,17 +49,23 @@ pub struct Flow<S: Scheduler, E: Executor> {
     scheduler: S,
     executor: E,
     hooks: Vec<Box<dyn EngineHook<S, E>>>,
-    generation: AtomicU32,
-    next_entity: AtomicU32,
+    /// Runs before every other stage on live frames, skipped while resimulating
+    input_systems: Option<SystemSet>,
 }
 
 impl<S: Scheduler, E: Executor> Flow<S, E> {
     pub fn new() -> FlowBuilder<S, E> {
+        let mut world = World::new();
+        world.store_resource(EventClock::new());
+        let mut asset_server = AssetServer::new(DEFAULT_ASSET_ROOT);
+        asset_server.set_cache(ProcessedCache::new(DEFAULT_ASSET_CACHE));
+        world.store_resource(asset_server);
+
         FlowBuilder {
             scheduler: None,
             executor: None,
             hooks: Vec::new(),
-            world: UnsafeCell::new(World::new()),
+            world: UnsafeCell::new(world),
             system_sets: (0..6).map(|_| SystemSet::new()).collect(),
             run_once_systems: None,
         }
@@ -57,6 +78,14 @@ impl<S: Scheduler, E: Executor> Flow<S, E> {
         unsafe { &mut *self.world.get() }.apply_commands();
     }
 
+    fn run_input(&mut self) {
+        if let Some(system_set) = &mut self.input_systems {
+            let schedule = self.scheduler.get_schedule(&self.world, system_set);
+            self.executor.run(system_set, &self.world, &schedule);
+            unsafe { &mut *self.world.get() }.apply_commands();
+        }
+    }
+
     fn run_schedules(&mut self) {
         self.run_once_systems.take().map(|mut system_set| {
             let schedule = self.scheduler.get_schedule(&self.world, &mut system_set);
@@ -83,6 +112,12 @@ impl<S: Scheduler, E: Executor> Flow<S, E> {
     }
 
     pub fn spin(&mut self) {
+        if let Some(clock) = self.get_resource::<EventClock>() {
+            clock.advance();
+        }
+        event::update_all(self.world.get_mut());
+        update_assets(self.world.get_mut());
+
         self.hooks.iter_mut().for_each(|hook| {
             hook.pre_run(
                 unsafe { &mut *self.world.get() },
@@ -90,6 +125,7 @@ impl<S: Scheduler, E: Executor> Flow<S, E> {
                 &mut self.executor,
             )
         });
+        self.run_input();
         self.run_schedules();
         self.hooks.iter_mut().for_each(|hook| {
             hook.post_run(
@@ -121,29 +157,102 @@ impl<S: Scheduler, E: Executor> Flow<S, E> {
         });
     }
 
-    pub fn run(&mut self) -> Result<(), EventLoopError> {
+    pub fn record(&self, rollback: &mut Rollback) {
+        let world = unsafe { &*self.world.get() };
+        rollback.record(world);
+    }
+
+    /// Restores the snapshot recorded at `from` and replays `steps` fixed steps with the recorded inputs
+    ///
+    /// Live input is not read while replaying, each step sees the recorded `InputMap` with the
+    /// step before it as its previous frame. Events are restored along with the snapshot and
+    /// swapped between steps without being forwarded to listeners again. Recorded frames after
+    /// `from` are discarded. Each replayed step is recorded again
+    pub fn resimulate(
+        &mut self,
+        rollback: &mut Rollback,
+        from: u64,
+        steps: usize,
+    ) -> Result<(), RollbackError> {
+        if self.get_resource::<FixedTimestep>().is_none() {
+            return Err(RollbackError::NoFixedTimestep);
+        }
+
+        let to = from + steps as u64;
+        rollback.check_tick(from)?;
+        rollback.check_tick(to)?;
+
+        let inputs: Vec<(InputMap, Option<PlayerInputs>)> = (from + 1..=to)
+            .map(|tick| {
+                let mut input = rollback.input(tick).unwrap().clone();
+                input.set_previous(rollback.input(tick - 1).unwrap());
+                (input, rollback.players(tick).cloned())
+            })
+            .collect();
+
+        self.world.get_mut().restore(rollback.snapshot(from).unwrap());
+        rollback.rewind(from)?;
+
+        inputs.into_iter().for_each(|(input, players)| {
+            event::swap_all(self.world.get_mut());
+            self.add_resource(input);
+            if let Some(players) = players {
+                self.add_resource(players);
+            }
+            self.run_schedules();
+            self.record(rollback);
+        });
+
+        Ok(())
+    }
+
+    fn init_input(&mut self) {
+        if self.input_systems.is_some() {
+            return;
+        }
+
         self.add_resource(InputMap::new());
-        self.add_system(stages::PRE_RUN, crate::input::update_input);
+        if self.world.get_mut().get_resource::<ActionMap>().is_none() {
+            self.add_resource(ActionMap::new());
+        }
+        if self.world.get_mut().get_resource::<PlayerInputs>().is_none() {
+            self.add_resource(PlayerInputs::new());
+        }
+        if self.world.get_mut().get_resource::<InputReplay>().is_none() {
+            self.add_resource(InputReplay::default());
+        }
+        let mut input_systems = SystemSet::new();
+        input_systems.add_system(crate::input::update_input, &self.world);
+        self.input_systems = Some(input_systems);
+    }
+
+    /// Runs `frames` frames without opening a window, input only comes from gamepads or from
+    /// the recording when the flow was built with `with_input_playback`
+    pub fn run_headless(&mut self, frames: usize) {
+        self.init_input();
+        for _ in 0..frames {
+            self.spin();
+        }
+    }
+
+    pub fn run(&mut self) -> Result<(), EventLoopError> {
+        self.init_input();
         let event_loop = EventLoop::new().unwrap();
         event_loop.set_control_flow(event_loop::ControlFlow::Poll);
         event_loop.run_app(self)
     }
 
     fn get_event_writer<T: Clone + Debug + 'static>(&self) -> &EventWriter<T> {
-        let world = unsafe { &*self.world.get() };
-        let writer = world.get_resource::<EventWriter<T>>().unwrap_or_else(|| {
-            let world = unsafe { &mut *self.world.get() };
-            let writer = EventWriter::<T>::new();
-            world.store_resource(writer);
-            world.get_resource().unwrap()
-        });
-        writer
+        get_event_writer(&self.world)
     }
 
+    /// Queues `event` for `Event<T>` readers next frame
     pub fn send_event<T: Clone + Debug + 'static>(&mut self, event: T) {
-        self.get_event_writer().clone().send(event);
+        init_events::<T>(&self.world);
+        self.get_resource_mut::<Events<T>>().unwrap().send(event);
     }
 
+    /// Listener outside the schedule, receives each frame's events once they become readable
     pub fn get_event_listener<T: Clone + Debug + 'static>(&self) -> EventReader<T> {
         EventReader::from_writer(self.get_event_writer())
     }
@@ -171,11 +280,8 @@ impl<S: Scheduler, E: Executor> Flow<S, E> {
     }
 
     pub fn make_entity(&self) -> Entity {
-        Entity(
-            self.generation.load(Ordering::SeqCst),
-            self.next_entity
-                .fetch_add(1, std::sync::atomic::Ordering::SeqCst),
-        )
+        let world = unsafe { &*self.world.get() };
+        world.make_entity()
     }
 
     pub fn add_resource<T: 'static>(&mut self, resource: T) {
@@ -237,6 +343,113 @@ impl<S: Scheduler, E: Executor> FlowBuilder<S, E> {
         world.store_resource(resource);
         self
     }
+    /// Limits how long event writers created by the flow keep events for listeners that fall
+    /// behind, by default events are kept until every listener read them
+    pub fn with_event_retention(self, max_events: Option<usize>, max_frames: Option<u64>) -> Self {
+        let world = unsafe { &mut *self.world.get() };
+        let clock = world.get_resource::<EventClock>().cloned().unwrap_or_default();
+
+        let mut policy = RetentionPolicy::unbounded();
+        if let Some(max_events) = max_events {
+            policy = policy.with_max_events(max_events);
+        }
+        if let Some(max_frames) = max_frames {
+            policy = policy.with_max_frames(max_frames, &clock);
+        }
+
+        world.store_resource(policy);
+        self
+    }
+    /// Forwards `T` events between the world and `bridge`'s endpoint every spin
+    pub fn with_event_bridge<T>(self, bridge: EventBridge<T>) -> Self
+    where
+        T: Clone + Debug + Serialize + DeserializeOwned + Send + 'static,
+    {
+        init_events::<T>(&self.world);
+        self.with_resource(bridge).with_hook(BridgeHook::<T>::default())
+    }
+    pub fn with_asset_root(self, root: impl Into<PathBuf>) -> Self {
+        let world = unsafe { &mut *self.world.get() };
+        unsafe { world.get_resource_mut::<AssetServer>() }
+            .unwrap()
+            .set_root(root.into());
+        self
+    }
+    /// Makes `reader` loadable through `name://` paths, e.g. a `PackReader` as `pack://`
+    pub fn with_asset_source(self, name: impl Into<String>, reader: impl AssetReader) -> Self {
+        let world = unsafe { &mut *self.world.get() };
+        unsafe { world.get_resource_mut::<AssetServer>() }
+            .unwrap()
+            .add_source(name, reader);
+        self
+    }
+    pub fn with_asset_cache(self, dir: impl Into<PathBuf>) -> Self {
+        let world = unsafe { &mut *self.world.get() };
+        unsafe { world.get_resource_mut::<AssetServer>() }
+            .unwrap()
+            .set_cache(ProcessedCache::new(dir));
+        self
+    }
+    /// Converts matching source files with `processor` before they are loaded
+    pub fn with_asset_processor<P: AssetProcessor>(self, processor: P) -> Self {
+        let world = unsafe { &mut *self.world.get() };
+        unsafe { world.get_resource_mut::<AssetServer>() }
+            .unwrap()
+            .register_processor(processor);
+        self
+    }
+    /// Runtime bindings `Input<T>` and `InputAxis<T>` resolve through before their compiled-in ones
+    pub fn with_action_map(self, actions: ActionMap) -> Self {
+        self.with_resource(actions)
+    }
+    /// Allows IME text composition in the window, toggle it later through `ImeSettings`
+    pub fn with_ime_allowed(self) -> Self {
+        self.with_resource(ImeSettings::new().with_allowed(true))
+    }
+    /// Records every input applied to the `InputMap`, take the recording with `InputReplay::stop`
+    pub fn with_input_recording(self) -> Self {
+        self.with_resource(InputReplay::record())
+    }
+    /// Replaces window and gamepad input with `recording`
+    pub fn with_input_playback(self, recording: InputRecording) -> Self {
+        self.with_resource(InputReplay::play(recording))
+    }
+    /// Device assignments for local multiplayer, read by `Input<T, PLAYER>` and friends
+    pub fn with_player_inputs(self, players: PlayerInputs) -> Self {
+        self.with_resource(players)
+    }
+    /// Reloads assets whose source files changed, checking every `interval`
+    pub fn with_asset_hot_reload(self, interval: Duration) -> Self {
+        let world = unsafe { &mut *self.world.get() };
+        unsafe { world.get_resource_mut::<AssetServer>() }
+            .unwrap()
+            .watch_for_changes(interval);
+        self
+    }
+    /// Registers `loader` with the asset server and adds the `Assets<T>` resource it fills
+    pub fn with_asset_loader<L: AssetLoader>(self, loader: L) -> Self {
+        let world = unsafe { &mut *self.world.get() };
+        if world.get_resource::<Assets<L::Asset>>().is_some() {
+            let server = unsafe { world.get_resource_mut::<AssetServer>() }.unwrap();
+            server.register_loader(loader);
+            return self;
+        }
+
+        let server = unsafe { world.get_resource_mut::<AssetServer>() }.unwrap();
+        let assets = server.register_loader(loader);
+        world.store_resource(assets);
+        init_events::<AssetEvent<L::Asset>>(&self.world);
+        self
+    }
+    /// Adds `Assets<T>` for assets created at runtime with `Assets::add`
+    pub fn with_assets<T: Send + Sync + 'static>(self) -> Self {
+        let world = unsafe { &mut *self.world.get() };
+        let server = unsafe { world.get_resource_mut::<AssetServer>() }.unwrap();
+        let assets = server.add_assets::<T>();
+        world.store_resource(assets);
+        init_events::<AssetEvent<T>>(&self.world);
+        self
+    }
     pub fn with_executor(mut self, executor: E) -> Self {
         self.executor = Some(executor);
         self
@@ -286,15 +499,17 @@ impl<S: Scheduler, E: Executor> FlowBuilder<S, E> {
     pub fn build(mut self) -> Flow<S, E> {
         if let (Some(scheduler), Some(executor)) = (self.scheduler, self.executor) {
             self.world.get_mut().store_resource(gilrs::Gilrs::new().unwrap());
+            if self.world.get_mut().get_resource::<ImeSettings>().is_none() {
+                self.world.get_mut().store_resource(ImeSettings::new());
+            }
             Flow {
                 world: self.world,
                 system_sets: self.system_sets,
                 scheduler,
                 executor,
-                generation: AtomicU32::new(0),
-                next_entity: AtomicU32::new(0),
                 hooks: self.hooks,
                 run_once_systems: self.run_once_systems,
+                input_systems: None,
             }
         } else {
             panic!("FlowBuilder missing required fields");
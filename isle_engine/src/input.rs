@@ -1,16 +1,37 @@
-use std::hash::Hash;
+use std::{
+    collections::VecDeque,
+    hash::Hash,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
 
 pub use isle_engine_macros::{define_axis_binding, define_binding};
 pub use gilrs::{Axis as GilrsAxis, Button as GilrsButton};
 
 use gilrs::{Gilrs, EventType as GilrsEventType};
 use isle_ecs::ecs::ResMut;
+use isle_math::vector::d2::Vec2;
 use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
 use winit::keyboard::KeyCode;
 
-use crate::{params::{Event, EventTrigger}, window::KeyboardEvent};
+pub mod action;
+pub mod axis;
+pub mod gamepad;
+pub mod replay;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub use action::{ActionMap, ActionMapError, AxisBindings, Binding};
+pub use axis::AxisSettings;
+pub use gamepad::{
+    GamepadConnected, GamepadDisconnected, GamepadEvents, GamepadId, InputDevice, PlayerInputs, ANY_PLAYER,
+};
+pub use replay::{InputRecording, InputReplay, RecordedFrame, RecordedInput, RecordingError};
+
+use gamepad::GamepadState;
+
+use crate::{params::Event, window::{KeyboardEvent, MouseEvent}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Key {
     // Letters
     A,
@@ -131,7 +152,7 @@ pub enum Key {
     Unknown
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Axis {
     LeftStickX,
     LeftStickY,
@@ -151,7 +172,7 @@ pub enum Axis {
     Unknown
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Button {
     North,
     East,
@@ -176,20 +197,106 @@ pub enum Button {
     Unknown
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+
+    Unknown
+}
+
+/// Either side of a modifier key, used in chords such as `Modifier::Control + Key::S`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Modifier {
+    Shift,
+    Control,
+    Alt,
+    Super,
+}
+
+impl Modifier {
+    pub fn keys(&self) -> &'static [Key] {
+        match self {
+            Self::Shift => &[Key::LeftShift, Key::RightShift],
+            Self::Control => &[Key::LeftControl, Key::RightControl],
+            Self::Alt => &[Key::LeftAlt, Key::RightAlt],
+            Self::Super => &[Key::Super],
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum InputKind {
     Key(Key),
     Axis(Axis),
     Button(Button),
+    Mouse(MouseButton),
+    Modifier(Modifier),
+}
+
+impl InputKind {
+    /// Whether a press of `input` counts as this input, modifiers match either of their keys
+    pub fn matches(&self, input: InputKind) -> bool {
+        match (self, input) {
+            (Self::Modifier(modifier), InputKind::Key(key)) => modifier.keys().contains(&key),
+            _ => *self == input,
+        }
+    }
+}
+
+/// Inputs pressed one after another within `window`, active on the frame the last step is pressed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sequence {
+    pub steps: &'static [InputKind],
+    pub window: Duration,
 }
 
 pub trait Mapping: Sized {
+    /// Name the binding is stored under in an `ActionMap`
+    const NAME: &'static str = "";
+
     fn keys<'a>() -> &'a [Key];
     fn buttons<'a>() -> &'a [Button];
 
+    fn mouse_buttons<'a>() -> &'a [MouseButton] {
+        &[]
+    }
+
+    /// Groups of inputs that only count while all of them are held
+    fn chords<'a>() -> &'a [&'a [InputKind]] {
+        &[]
+    }
+
+    fn sequences<'a>() -> &'a [Sequence] {
+        &[]
+    }
+
     fn get(input_map: &InputMap) -> bool {
         input_map.check_mapping::<Self>()
     }
+
+    /// Uses the bindings in `actions` when it has some for `NAME`, the compiled-in ones otherwise
+    fn resolve(input_map: &InputMap, actions: Option<&ActionMap>) -> bool {
+        actions
+            .and_then(|actions| actions.check(input_map, Self::NAME))
+            .unwrap_or_else(|| Self::get(input_map))
+    }
+
+    /// How long the longest held of the resolved bindings has been held
+    fn pressed_duration(input_map: &InputMap, actions: Option<&ActionMap>) -> Option<Duration> {
+        let bindings = actions
+            .and_then(|actions| actions.action(Self::NAME))
+            .map(<[Binding]>::to_vec)
+            .unwrap_or_else(action::mapping_bindings::<Self>);
+
+        bindings
+            .iter()
+            .filter_map(|binding| binding.pressed_duration(input_map))
+            .max()
+    }
 }
 
 impl Mapping for () {
@@ -207,21 +314,70 @@ impl Mapping for () {
 }
 
 pub trait AxisMapping: Sized {
+    const NAME: &'static str = "";
+
     type PositiveMapping: Mapping;
     type NegativeMapping: Mapping;
 
     fn axes<'a>() -> &'a [Axis];
 
+    fn settings() -> AxisSettings {
+        AxisSettings::default()
+    }
+
     fn get(input_map: &InputMap) -> f32 {
-        input_map.check_axis_mapping::<Self>()
+        Self::settings().apply(input_map.check_axis_mapping::<Self>())
+    }
+
+    fn resolve(input_map: &InputMap, actions: Option<&ActionMap>) -> f32 {
+        Self::resolve_settings(actions).apply(Self::resolve_raw(input_map, actions))
+    }
+
+    /// Value before `AxisSettings` are applied
+    fn resolve_raw(input_map: &InputMap, actions: Option<&ActionMap>) -> f32 {
+        actions
+            .and_then(|actions| actions.check_axis(input_map, Self::NAME))
+            .unwrap_or_else(|| input_map.check_axis_mapping::<Self>())
+    }
+
+    /// Settings stored in `actions` for `NAME`, the compiled-in ones otherwise
+    fn resolve_settings(actions: Option<&ActionMap>) -> AxisSettings {
+        actions
+            .and_then(|actions| actions.axis(Self::NAME))
+            .and_then(|bindings| bindings.settings)
+            .unwrap_or_else(Self::settings)
     }
 }
 
-#[derive(Default)]
+static NO_INPUT: LazyLock<InputMap> = LazyLock::new(InputMap::default);
+
+/// Number of recent presses kept for sequence bindings
+pub const INPUT_HISTORY_LEN: usize = 32;
+
+/// Input state of the current frame, the state of the previous frame is kept for edge queries
+/// so every system sees the same presses and releases regardless of when it runs
+///
+/// Presses are timed on an input clock that advances once per frame, so recorded input plays
+/// back with the same hold durations and sequence timings
+#[derive(Default, Clone)]
 pub struct InputMap {
+    previous: Option<Box<InputMap>>,
+    /// Start of the live input clock, set by the first `begin_frame`
+    clock_start: Option<Instant>,
+    time: Duration,
+    frame_presses: usize,
+    pressed_at: FxHashMap<InputKind, Duration>,
+    history: VecDeque<(InputKind, Duration)>,
+    calibration: FxHashMap<(GamepadId, Axis), AxisSettings>,
+    gamepads: FxHashMap<GamepadId, GamepadState>,
     keys: FxHashSet<Key>,
     buttons: FxHashSet<Button>,
+    mouse_buttons: FxHashSet<MouseButton>,
     axes: FxHashMap<Axis, f32>,
+    cursor: Option<(f32, f32)>,
+    window_size: (f32, f32),
+    mouse_delta: (f32, f32),
+    scroll: (f32, f32),
 }
 
 impl InputMap {
@@ -239,6 +395,7 @@ impl InputMap {
         } else {
             self.keys.remove(&key);
         }
+        self.track_press(InputKind::Key(key), state);
     }
 
     pub fn set_button(&mut self, button: Button, state: bool) {
@@ -251,6 +408,84 @@ impl InputMap {
         } else {
             self.buttons.remove(&button);
         }
+        self.track_press(InputKind::Button(button), state);
+    }
+
+    pub fn set_mouse_button(&mut self, button: MouseButton, state: bool) {
+        if button == MouseButton::Unknown {
+            return;
+        }
+
+        if state {
+            self.mouse_buttons.insert(button);
+        } else {
+            self.mouse_buttons.remove(&button);
+        }
+        self.track_press(InputKind::Mouse(button), state);
+    }
+
+    /// Cursor position in physical pixels from the top left of a window of `window_size`
+    pub fn set_cursor(&mut self, position: Option<Vec2>, window_size: Vec2) {
+        self.cursor = position.map(|position| (position.0, position.1));
+        self.window_size = (window_size.0, window_size.1);
+    }
+
+    /// Adds raw mouse motion to this frame's delta, which also drives `Axis::MouseX` and `Axis::MouseY`
+    pub fn add_mouse_delta(&mut self, delta: Vec2) {
+        self.mouse_delta.0 += delta.0;
+        self.mouse_delta.1 += delta.1;
+        self.axes.insert(Axis::MouseX, self.mouse_delta.0);
+        self.axes.insert(Axis::MouseY, self.mouse_delta.1);
+    }
+
+    /// Adds to this frame's scroll, in lines
+    pub fn add_scroll(&mut self, delta: Vec2) {
+        self.scroll.0 += delta.0;
+        self.scroll.1 += delta.1;
+    }
+
+    /// Keeps the current state as the previous frame and clears the per-frame mouse delta and
+    /// scroll, run once per frame before the frame's events are applied
+    pub fn begin_frame(&mut self) {
+        let time = self.live_time();
+        self.begin_frame_at(time);
+    }
+
+    /// Like `begin_frame` with the input clock set to `time`, e.g. a recorded frame's time
+    pub fn begin_frame_at(&mut self, time: Duration) {
+        let mut previous = self.previous.take().unwrap_or_default();
+        previous.copy_state(self);
+        self.previous = Some(previous);
+        self.time = time;
+        self.frame_presses = 0;
+
+        self.mouse_delta = (0.0, 0.0);
+        self.scroll = (0.0, 0.0);
+        self.axes.remove(&Axis::MouseX);
+        self.axes.remove(&Axis::MouseY);
+    }
+
+    /// Time since the first frame on the live input clock
+    pub fn live_time(&mut self) -> Duration {
+        self.clock_start.get_or_insert_with(Instant::now).elapsed()
+    }
+
+    /// Input clock time of the current frame
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// Replaces the previous frame, e.g. with the input recorded for the step before a resimulated one
+    pub(crate) fn set_previous(&mut self, previous: &InputMap) {
+        self.previous.get_or_insert_default().copy_state(previous);
+    }
+
+    /// Copies only what edge queries compare, history and per gamepad state are left out
+    fn copy_state(&mut self, from: &InputMap) {
+        self.keys.clone_from(&from.keys);
+        self.buttons.clone_from(&from.buttons);
+        self.mouse_buttons.clone_from(&from.mouse_buttons);
+        self.axes.clone_from(&from.axes);
     }
 
     pub fn set_axis(&mut self, axis: Axis, value: f32) {
@@ -259,6 +494,109 @@ impl InputMap {
         }
 
         self.axes.insert(axis, value);
+        self.track_press(InputKind::Axis(axis), self.get_axis(axis) > 0.0);
+    }
+
+    /// Per gamepad state, `get_button` and `get_axis` report the buttons held on any gamepad and
+    /// the calibrated axis value last reported by any gamepad
+    pub fn set_gamepad_button(&mut self, id: GamepadId, button: Button, state: bool) {
+        let gamepad = self.gamepads.entry(id).or_default();
+        if state {
+            gamepad.buttons.insert(button);
+        } else {
+            gamepad.buttons.remove(&button);
+        }
+
+        let held = self.gamepads.values().any(|gamepad| gamepad.buttons.contains(&button));
+        self.set_button(button, held);
+    }
+
+    pub fn set_gamepad_axis(&mut self, id: GamepadId, axis: Axis, value: f32) {
+        self.gamepads.entry(id).or_default().axes.insert(axis, value);
+        self.set_axis(axis, self.calibrate(id, axis, value));
+    }
+
+    pub fn set_gamepad_input(&mut self, id: GamepadId, input: InputKind, value: f32) {
+        match input {
+            InputKind::Button(button) => self.set_gamepad_button(id, button, value > 0.0),
+            InputKind::Axis(axis) => self.set_gamepad_axis(id, axis, value),
+            input => self.set_input(input, value),
+        }
+    }
+
+    pub fn add_gamepad(&mut self, id: GamepadId) {
+        self.gamepads.entry(id).or_default();
+    }
+
+    /// Releases everything `id` was holding
+    pub fn remove_gamepad(&mut self, id: GamepadId) {
+        let Some(gamepad) = self.gamepads.remove(&id) else {
+            return;
+        };
+
+        for button in gamepad.buttons {
+            let held = self.gamepads.values().any(|gamepad| gamepad.buttons.contains(&button));
+            self.set_button(button, held);
+        }
+        for axis in gamepad.axes.into_keys() {
+            let value = self.gamepads.iter().find_map(|(&id, gamepad)| {
+                gamepad.axes.get(&axis).map(|&value| self.calibrate(id, axis, value))
+            });
+            self.set_axis(axis, value.unwrap_or(0.0));
+        }
+    }
+
+    /// Releases every held key and mouse button
+    pub fn release_keyboard_mouse(&mut self) {
+        let keys: Vec<Key> = self.keys.iter().copied().collect();
+        keys.into_iter().for_each(|key| self.set_key(key, false));
+
+        let buttons: Vec<MouseButton> = self.mouse_buttons.iter().copied().collect();
+        buttons.into_iter().for_each(|button| self.set_mouse_button(button, false));
+    }
+
+    /// Corrects an axis of one gamepad before anything reads it, e.g. a dead zone for a drifting stick
+    pub fn set_calibration(&mut self, id: GamepadId, axis: Axis, settings: AxisSettings) {
+        self.calibration.insert((id, axis), settings);
+        if let Some(&value) = self.gamepads.get(&id).and_then(|gamepad| gamepad.axes.get(&axis)) {
+            self.set_axis(axis, self.calibrate(id, axis, value));
+        }
+    }
+
+    pub fn calibration(&self, id: GamepadId, axis: Axis) -> Option<&AxisSettings> {
+        self.calibration.get(&(id, axis))
+    }
+
+    /// Gives this map the calibration `from` has for `axis` on `id`
+    pub(crate) fn copy_calibration(&mut self, id: GamepadId, axis: Axis, from: &InputMap) {
+        match from.calibration(id, axis) {
+            Some(&settings) => self.calibration.insert((id, axis), settings),
+            None => self.calibration.remove(&(id, axis)),
+        };
+    }
+
+    fn calibrate(&self, id: GamepadId, axis: Axis, value: f32) -> f32 {
+        match self.calibration.get(&(id, axis)) {
+            Some(settings) => settings.apply(value),
+            None => value,
+        }
+    }
+
+    fn track_press(&mut self, input: InputKind, pressed: bool) {
+        if pressed {
+            if self.pressed_at.contains_key(&input) {
+                return;
+            }
+
+            self.pressed_at.insert(input, self.time);
+            if self.history.len() == INPUT_HISTORY_LEN {
+                self.history.pop_front();
+            }
+            self.history.push_back((input, self.time));
+            self.frame_presses += 1;
+        } else {
+            self.pressed_at.remove(&input);
+        }
     }
 
     pub fn set_input(&mut self, input: InputKind, value: f32) {
@@ -266,6 +604,9 @@ impl InputMap {
             InputKind::Key(key) => self.set_key(key, value > 0.0),
             InputKind::Axis(axis) => self.set_axis(axis, value),
             InputKind::Button(button) => self.set_button(button, value > 0.0),
+            InputKind::Mouse(button) => self.set_mouse_button(button, value > 0.0),
+            // Modifiers only name their keys, the keys themselves carry the state
+            InputKind::Modifier(_) => {}
         }
     }
 
@@ -277,22 +618,165 @@ impl InputMap {
         self.buttons.contains(&button)
     }
 
+    pub fn get_mouse_button(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.contains(&button)
+    }
+
+    pub fn has_gamepad(&self, id: GamepadId) -> bool {
+        self.gamepads.contains_key(&id)
+    }
+
+    pub fn gamepads(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.gamepads.keys().copied()
+    }
+
+    pub fn get_gamepad_button(&self, id: GamepadId, button: Button) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|gamepad| gamepad.buttons.contains(&button))
+    }
+
+    /// Calibrated value of `axis` on `id`
+    pub fn get_gamepad_axis(&self, id: GamepadId, axis: Axis) -> f32 {
+        let value = self
+            .gamepads
+            .get(&id)
+            .and_then(|gamepad| gamepad.axes.get(&axis).copied())
+            .unwrap_or(0.0);
+        self.calibrate(id, axis, value)
+    }
+
+    /// `None` while the cursor is outside the window
+    pub fn cursor_position(&self) -> Option<Vec2> {
+        self.cursor.map(|(x, y)| Vec2(x, y))
+    }
+
+    /// Cursor position from 0 at the top left to 1 at the bottom right of the window
+    pub fn cursor_normalized(&self) -> Option<Vec2> {
+        let (width, height) = self.window_size;
+        if width <= 0.0 || height <= 0.0 {
+            return None;
+        }
+
+        self.cursor.map(|(x, y)| Vec2(x / width, y / height))
+    }
+
+    /// Raw mouse motion since the start of the frame, unaffected by cursor acceleration or window edges
+    pub fn mouse_delta(&self) -> Vec2 {
+        Vec2(self.mouse_delta.0, self.mouse_delta.1)
+    }
+
+    /// Scroll since the start of the frame in lines, positive `y` scrolls up
+    pub fn scroll(&self) -> Vec2 {
+        Vec2(self.scroll.0, self.scroll.1)
+    }
+
+    /// Value of `axis`, calibrated when it came from a gamepad
     pub fn get_axis(&self, axis: Axis) -> f32 {
         *self.axes.get(&axis).unwrap_or(&0.0)
     }
 
+    /// 1 or 0 for keys and buttons, the axis value for axes
+    pub fn get_input(&self, input: InputKind) -> f32 {
+        match input {
+            InputKind::Key(key) => self.get_key(key) as i32 as f32,
+            InputKind::Axis(axis) => self.get_axis(axis),
+            InputKind::Button(button) => self.get_button(button) as i32 as f32,
+            InputKind::Mouse(button) => self.get_mouse_button(button) as i32 as f32,
+            InputKind::Modifier(modifier) => modifier.keys().iter().any(|key| self.get_key(*key)) as i32 as f32,
+        }
+    }
+
+    /// State at the end of the previous frame, nothing is pressed before the first frame
+    pub fn previous(&self) -> &InputMap {
+        self.previous.as_deref().unwrap_or(Self::empty())
+    }
+
+    /// Map with nothing pressed
+    pub fn empty() -> &'static InputMap {
+        &NO_INPUT
+    }
+
+    pub fn just_pressed(&self, input: InputKind) -> bool {
+        self.get_input(input) > 0.0 && self.previous().get_input(input) <= 0.0
+    }
+
+    pub fn just_released(&self, input: InputKind) -> bool {
+        self.get_input(input) <= 0.0 && self.previous().get_input(input) > 0.0
+    }
+
+    /// Input clock time since `input` was pressed, `None` while it is released
+    pub fn pressed_duration(&self, input: InputKind) -> Option<Duration> {
+        match input {
+            InputKind::Modifier(modifier) => modifier
+                .keys()
+                .iter()
+                .filter_map(|key| self.pressed_duration(InputKind::Key(*key)))
+                .max(),
+            _ => self.pressed_at.get(&input).map(|at| self.time.saturating_sub(*at)),
+        }
+    }
+
+    /// Presses in the order they happened with their input clock time, at most `INPUT_HISTORY_LEN`
+    pub fn history(&self) -> impl Iterator<Item = (InputKind, Duration)> + '_ {
+        self.history.iter().copied()
+    }
+
+    /// True while every input of `chord` is held
+    pub fn chord_held(&self, chord: &[InputKind]) -> bool {
+        !chord.is_empty() && chord.iter().all(|input| self.get_input(*input) > 0.0)
+    }
+
+    /// True on the frame the last of `steps` was pressed, when the steps were the most recent
+    /// presses in order and the first of them was at most `window` earlier
+    pub fn sequence_completed(&self, steps: &[InputKind], window: Duration) -> bool {
+        if steps.is_empty() || self.history.len() < steps.len() {
+            return false;
+        }
+
+        let recent = self.history.range(self.history.len() - steps.len()..);
+        if !steps.iter().zip(recent).all(|(step, (input, _))| step.matches(*input)) {
+            return false;
+        }
+
+        let (_, first) = self.history[self.history.len() - steps.len()];
+        let (_, last) = self.history[self.history.len() - 1];
+        last.saturating_sub(first) <= window && self.frame_presses > 0
+    }
+
+    pub fn pressed_for(&self, input: InputKind, duration: Duration) -> bool {
+        self.pressed_duration(input).is_some_and(|held| held >= duration)
+    }
+
+    /// Change of `axis` since the previous frame
+    pub fn axis_delta(&self, axis: Axis) -> f32 {
+        self.get_axis(axis) - self.previous().get_axis(axis)
+    }
+
     pub fn check_mapping<M: Mapping>(&self) -> bool {
         let keys = FxHashSet::from_iter(M::keys().iter().copied());
         let buttons = FxHashSet::from_iter(M::buttons().iter().copied());
+        let mouse_buttons = FxHashSet::from_iter(M::mouse_buttons().iter().copied());
 
-        !self.keys.is_disjoint(&keys) || !self.buttons.is_disjoint(&buttons)
+        !self.keys.is_disjoint(&keys)
+            || !self.buttons.is_disjoint(&buttons)
+            || !self.mouse_buttons.is_disjoint(&mouse_buttons)
+            || M::chords().iter().any(|chord| self.chord_held(chord))
+            || M::sequences()
+                .iter()
+                .any(|sequence| self.sequence_completed(sequence.steps, sequence.window))
     }
 
     pub fn check_axis_mapping<M: AxisMapping>(&self) -> f32 {
         let positive: f32 = M::PositiveMapping::get(self).into();
         let negative: f32 = M::NegativeMapping::get(self).into();
 
-        let axis = M::axes()
+        self.strongest_axis(M::axes(), positive - negative)
+    }
+
+    /// Value of the axis furthest from zero, or `fallback` when it is further
+    pub(crate) fn strongest_axis(&self, axes: &[Axis], fallback: f32) -> f32 {
+        let axis = axes
             .iter()
             .map(|axis| self.get_axis(*axis))
             .fold(
@@ -306,7 +790,6 @@ impl InputMap {
                 },
             );
 
-        let fallback = positive - negative;
         if axis.abs() > fallback.abs() {
             axis
         } else {
@@ -315,9 +798,46 @@ impl InputMap {
     }
 }
 
-impl Into<InputKind> for Key {
-    fn into(self) -> InputKind {
-        InputKind::Key(self)
+impl From<Key> for InputKind {
+    fn from(key: Key) -> Self {
+        InputKind::Key(key)
+    }
+}
+
+impl From<Axis> for InputKind {
+    fn from(axis: Axis) -> Self {
+        InputKind::Axis(axis)
+    }
+}
+
+impl From<Button> for InputKind {
+    fn from(button: Button) -> Self {
+        InputKind::Button(button)
+    }
+}
+
+impl From<MouseButton> for InputKind {
+    fn from(button: MouseButton) -> Self {
+        InputKind::Mouse(button)
+    }
+}
+
+impl From<Modifier> for InputKind {
+    fn from(modifier: Modifier) -> Self {
+        InputKind::Modifier(modifier)
+    }
+}
+
+impl From<winit::event::MouseButton> for MouseButton {
+    fn from(button: winit::event::MouseButton) -> Self {
+        match button {
+            winit::event::MouseButton::Left => MouseButton::Left,
+            winit::event::MouseButton::Right => MouseButton::Right,
+            winit::event::MouseButton::Middle => MouseButton::Middle,
+            winit::event::MouseButton::Back => MouseButton::Back,
+            winit::event::MouseButton::Forward => MouseButton::Forward,
+            winit::event::MouseButton::Other(_) => MouseButton::Unknown,
+        }
     }
 }
 
@@ -479,16 +999,341 @@ impl From<GilrsButton> for InputKind {
     }
 }
 
-pub fn update_input(mut event: Event<KeyboardEvent>, mut gilrs: ResMut<Gilrs>, mut input_map: ResMut<InputMap>) {
-    event.iter().for_each(|event| {
-        input_map.set_key(event.key, event.state);
-    });
-    
+#[allow(clippy::too_many_arguments)]
+pub fn update_input(
+    mut event: Event<KeyboardEvent>,
+    mut mouse: Event<MouseEvent>,
+    mut gilrs: ResMut<Gilrs>,
+    mut input_map: ResMut<InputMap>,
+    mut actions: ResMut<ActionMap>,
+    mut players: ResMut<PlayerInputs>,
+    mut replay: ResMut<InputReplay>,
+    mut gamepad_events: GamepadEvents,
+) {
+    let time = replay.frame_time().unwrap_or_else(|| input_map.live_time());
+    input_map.begin_frame_at(time);
+    players.begin_frame(time);
+    replay.record_frame_time(time);
+
+    let inputs = match replay.playback_inputs() {
+        Some(inputs) => {
+            // Live input is dropped so it can't leak into the recording's frames
+            while gilrs.next_event().is_some() {}
+            inputs
+        }
+        None => live_inputs(&mut event, &mut mouse, &mut gilrs, &input_map),
+    };
+
+    for input in inputs {
+        replay.record_input(&input);
+        apply_recorded(input, &mut input_map, &mut players, &mut actions, &mut gamepad_events);
+    }
+    replay.end_frame();
+}
+
+/// Window and gamepad input since the last frame, in the order it is applied
+fn live_inputs(
+    event: &mut Event<KeyboardEvent>,
+    mouse: &mut Event<MouseEvent>,
+    gilrs: &mut Gilrs,
+    input_map: &InputMap,
+) -> Vec<RecordedInput> {
+    let mut inputs: Vec<RecordedInput> = event
+        .iter()
+        .map(|event| RecordedInput::Key {
+            key: event.key,
+            state: event.state,
+        })
+        .collect();
+
+    inputs.extend(mouse.iter().map(|event| match event {
+        MouseEvent::Button { button, state } => RecordedInput::MouseButton { button, state },
+        MouseEvent::Moved { position, window_size } => RecordedInput::Cursor {
+            position: position.map(|position| (position.0, position.1)),
+            window_size: (window_size.0, window_size.1),
+        },
+        MouseEvent::Motion(delta) => RecordedInput::MouseMotion(delta.0, delta.1),
+        MouseEvent::Scroll(delta) => RecordedInput::Scroll(delta.0, delta.1),
+    }));
+
+    let mut connected = FxHashSet::default();
     while let Some(gilrs_event) = gilrs.next_event() {
-        match gilrs_event.event {
-            GilrsEventType::AxisChanged(axis, value, _code) => input_map.set_input(axis.into(), value),
-            GilrsEventType::ButtonChanged(button, value, _code) => input_map.set_input(button.into(), value),
-            _ => (),
+        let id = GamepadId::from(gilrs_event.id);
+
+        if gilrs_event.event == GilrsEventType::Disconnected {
+            connected.remove(&id);
+            inputs.push(RecordedInput::GamepadDisconnected { id });
+            continue;
+        }
+
+        // Gamepads plugged in before startup only show up through their first event
+        if !input_map.has_gamepad(id) && connected.insert(id) {
+            let name = gilrs.gamepad(gilrs_event.id).name().to_string();
+            inputs.push(RecordedInput::GamepadConnected { id, name });
+        }
+
+        let (input, value) = match gilrs_event.event {
+            GilrsEventType::AxisChanged(axis, value, _code) => (axis.into(), value),
+            GilrsEventType::ButtonChanged(button, value, _code) => (button.into(), value),
+            _ => continue,
+        };
+        inputs.push(RecordedInput::Gamepad { id, input, value });
+    }
+
+    inputs
+}
+
+/// Applies an input to the merged map and to the map of the player owning `device`
+fn apply_input(
+    input_map: &mut InputMap,
+    players: &mut PlayerInputs,
+    device: InputDevice,
+    apply: impl Fn(&mut InputMap),
+) {
+    apply(input_map);
+    if let Some(player_map) = players.device_map(device) {
+        apply(player_map);
+    }
+}
+
+fn apply_recorded(
+    input: RecordedInput,
+    input_map: &mut InputMap,
+    players: &mut PlayerInputs,
+    actions: &mut ActionMap,
+    gamepad_events: &mut GamepadEvents,
+) {
+    let keyboard_mouse = InputDevice::KeyboardMouse;
+
+    match input {
+        RecordedInput::Key { key, state } => {
+            apply_input(input_map, players, keyboard_mouse, |map| map.set_key(key, state));
+            actions.capture(InputKind::Key(key), state as i32 as f32);
+        }
+        RecordedInput::MouseButton { button, state } => {
+            apply_input(input_map, players, keyboard_mouse, |map| {
+                map.set_mouse_button(button, state)
+            });
+            actions.capture(InputKind::Mouse(button), state as i32 as f32);
         }
+        RecordedInput::Cursor { position, window_size } => {
+            let position = position.map(|(x, y)| Vec2(x, y));
+            let window_size = Vec2(window_size.0, window_size.1);
+            apply_input(input_map, players, keyboard_mouse, |map| {
+                map.set_cursor(position, window_size)
+            });
+        }
+        RecordedInput::MouseMotion(x, y) => {
+            apply_input(input_map, players, keyboard_mouse, |map| map.add_mouse_delta(Vec2(x, y)));
+        }
+        RecordedInput::Scroll(x, y) => {
+            apply_input(input_map, players, keyboard_mouse, |map| map.add_scroll(Vec2(x, y)));
+        }
+        RecordedInput::GamepadConnected { id, name } => {
+            input_map.add_gamepad(id);
+            let player = players.connect(id);
+            gamepad_events.connected(GamepadConnected { id, name, player });
+        }
+        RecordedInput::GamepadDisconnected { id } => {
+            input_map.remove_gamepad(id);
+            let player = players.disconnect(id);
+            gamepad_events.disconnected(GamepadDisconnected { id, player });
+        }
+        RecordedInput::Gamepad { id, input, value } => {
+            if let (InputKind::Axis(axis), Some(player_map)) =
+                (input, players.device_map(InputDevice::Gamepad(id)))
+            {
+                player_map.copy_calibration(id, axis, input_map);
+            }
+            apply_input(input_map, players, InputDevice::Gamepad(id), |map| {
+                map.set_gamepad_input(id, input, value)
+            });
+            actions.capture(input, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edges_across_frames() {
+        let mut input_map = InputMap::new();
+        input_map.set_key(Key::Space, true);
+        assert!(input_map.just_pressed(Key::Space.into()));
+
+        input_map.begin_frame();
+        assert!(!input_map.just_pressed(Key::Space.into()));
+        assert!(input_map.previous().get_key(Key::Space));
+        assert_eq!(input_map.previous().history().count(), 0);
+
+        input_map.set_key(Key::Space, false);
+        assert!(input_map.just_released(Key::Space.into()));
+
+        input_map.begin_frame();
+        assert!(!input_map.just_released(Key::Space.into()));
+    }
+
+    #[test]
+    fn calibration_per_gamepad() {
+        let (drifting, other) = (GamepadId(0), GamepadId(1));
+        let mut input_map = InputMap::new();
+        input_map.set_gamepad_axis(drifting, Axis::LeftStickX, 0.1);
+        assert_eq!(input_map.get_axis(Axis::LeftStickX), 0.1);
+
+        input_map.set_calibration(drifting, Axis::LeftStickX, AxisSettings::new().with_dead_zone(0.2));
+        assert_eq!(input_map.get_axis(Axis::LeftStickX), 0.0);
+        assert_eq!(input_map.get_gamepad_axis(drifting, Axis::LeftStickX), 0.0);
+        assert_eq!(input_map.previous().get_axis(Axis::LeftStickX), 0.0);
+
+        input_map.set_gamepad_axis(other, Axis::LeftStickX, 0.1);
+        assert_eq!(input_map.get_axis(Axis::LeftStickX), 0.1);
+        assert_eq!(input_map.get_gamepad_axis(other, Axis::LeftStickX), 0.1);
+
+        input_map.begin_frame();
+        input_map.remove_gamepad(other);
+        assert_eq!(input_map.get_axis(Axis::LeftStickX), 0.0);
+        assert_eq!(input_map.previous().get_axis(Axis::LeftStickX), 0.1);
+    }
+
+    #[test]
+    fn mouse_button_edges() {
+        let mut input_map = InputMap::new();
+        input_map.set_mouse_button(MouseButton::Unknown, true);
+        assert!(!input_map.get_mouse_button(MouseButton::Unknown));
+
+        input_map.set_mouse_button(MouseButton::Left, true);
+        assert!(input_map.just_pressed(MouseButton::Left.into()));
+        assert_eq!(input_map.get_input(MouseButton::Left.into()), 1.0);
+
+        input_map.begin_frame();
+        assert!(input_map.get_mouse_button(MouseButton::Left));
+        assert!(!input_map.just_pressed(MouseButton::Left.into()));
+
+        input_map.set_mouse_button(MouseButton::Left, false);
+        assert!(input_map.just_released(MouseButton::Left.into()));
+        assert!(!input_map.get_mouse_button(MouseButton::Right));
+    }
+
+    #[test]
+    fn cursor_position() {
+        let mut input_map = InputMap::new();
+        assert_eq!(input_map.cursor_position(), None);
+        assert_eq!(input_map.cursor_normalized(), None);
+
+        input_map.set_cursor(Some(Vec2(200.0, 150.0)), Vec2(800.0, 600.0));
+        assert_eq!(input_map.cursor_position(), Some(Vec2(200.0, 150.0)));
+        assert_eq!(input_map.cursor_normalized(), Some(Vec2(0.25, 0.25)));
+
+        // The position outlives the frame, unlike motion and scroll
+        input_map.begin_frame();
+        assert_eq!(input_map.cursor_position(), Some(Vec2(200.0, 150.0)));
+
+        input_map.set_cursor(Some(Vec2(1.0, 1.0)), Vec2(0.0, 600.0));
+        assert_eq!(input_map.cursor_normalized(), None);
+
+        input_map.set_cursor(None, Vec2(800.0, 600.0));
+        assert_eq!(input_map.cursor_position(), None);
+        assert_eq!(input_map.cursor_normalized(), None);
+    }
+
+    #[test]
+    fn mouse_delta_and_scroll_reset_each_frame() {
+        let mut input_map = InputMap::new();
+        input_map.add_mouse_delta(Vec2(3.0, -1.0));
+        input_map.add_mouse_delta(Vec2(2.0, -1.0));
+        input_map.add_scroll(Vec2(0.0, 1.0));
+        input_map.add_scroll(Vec2(0.5, 1.0));
+        assert_eq!(input_map.mouse_delta(), Vec2(5.0, -2.0));
+        assert_eq!(input_map.get_axis(Axis::MouseX), 5.0);
+        assert_eq!(input_map.get_axis(Axis::MouseY), -2.0);
+        assert_eq!(input_map.scroll(), Vec2(0.5, 2.0));
+
+        input_map.begin_frame();
+        assert_eq!(input_map.mouse_delta(), Vec2(0.0, 0.0));
+        assert_eq!(input_map.scroll(), Vec2(0.0, 0.0));
+        assert_eq!(input_map.get_axis(Axis::MouseX), 0.0);
+        assert_eq!(input_map.axis_delta(Axis::MouseX), -5.0);
+        assert_eq!(input_map.previous().get_axis(Axis::MouseY), -2.0);
+    }
+
+    #[test]
+    fn chords_and_modifiers() {
+        let save = [InputKind::Modifier(Modifier::Control), InputKind::Key(Key::S)];
+        let mut input_map = InputMap::new();
+        assert!(!input_map.chord_held(&[]));
+
+        input_map.set_key(Key::S, true);
+        assert!(!input_map.chord_held(&save));
+
+        input_map.set_key(Key::RightControl, true);
+        assert!(input_map.chord_held(&save));
+        assert_eq!(input_map.get_input(Modifier::Control.into()), 1.0);
+        assert!(input_map.pressed_duration(Modifier::Control.into()).is_some());
+        assert!(Binding::Chord(save.to_vec()).is_active(&input_map));
+
+        input_map.set_key(Key::RightControl, false);
+        input_map.set_key(Key::LeftControl, true);
+        assert!(input_map.chord_held(&save));
+
+        input_map.set_key(Key::S, false);
+        assert!(!input_map.chord_held(&save));
+        assert!(input_map.pressed_duration(Key::S.into()).is_none());
+    }
+
+    #[test]
+    fn sequences_complete_on_their_last_step() {
+        let window = Duration::from_secs(60);
+        let steps = [InputKind::Key(Key::Down), InputKind::Modifier(Modifier::Shift)];
+        let tap = |input_map: &mut InputMap, key| {
+            input_map.set_key(key, true);
+            input_map.set_key(key, false);
+        };
+
+        let mut input_map = InputMap::new();
+        input_map.begin_frame();
+        tap(&mut input_map, Key::Down);
+        assert!(!input_map.sequence_completed(&steps, window));
+        input_map.begin_frame();
+        tap(&mut input_map, Key::RightShift);
+        assert!(input_map.sequence_completed(&steps, window));
+
+        // Only on the frame of the last step
+        input_map.begin_frame();
+        assert!(!input_map.sequence_completed(&steps, window));
+
+        // Out of order or interrupted
+        tap(&mut input_map, Key::LeftShift);
+        tap(&mut input_map, Key::Down);
+        assert!(!input_map.sequence_completed(&steps, window));
+        tap(&mut input_map, Key::W);
+        tap(&mut input_map, Key::LeftShift);
+        assert!(!input_map.sequence_completed(&steps, window));
+
+        tap(&mut input_map, Key::Down);
+        tap(&mut input_map, Key::LeftShift);
+        assert!(input_map.sequence_completed(&steps, window));
+        assert!(!input_map.sequence_completed(&[], window));
+    }
+
+    #[test]
+    fn sequence_timeout_window() {
+        let steps = [InputKind::Key(Key::Down), InputKind::Key(Key::Right)];
+        let mut input_map = InputMap::new();
+        input_map.begin_frame_at(Duration::ZERO);
+        input_map.set_key(Key::Down, true);
+        input_map.begin_frame_at(Duration::from_millis(30));
+        input_map.set_key(Key::Right, true);
+        assert_eq!(input_map.pressed_duration(Key::Down.into()), Some(Duration::from_millis(30)));
+
+        assert!(!input_map.sequence_completed(&steps, Duration::from_millis(10)));
+        assert!(input_map.sequence_completed(&steps, Duration::from_secs(60)));
+        let binding = Binding::Sequence {
+            steps: steps.to_vec(),
+            window: Duration::from_millis(10),
+        };
+        assert!(!binding.is_active(&input_map));
+        assert!(binding.pressed_duration(&input_map).is_none());
     }
 }
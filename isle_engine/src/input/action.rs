@@ -0,0 +1,571 @@
+use std::{collections::BTreeMap, fmt::Display, fs, io, path::Path, str::FromStr, time::Duration};
+
+use serde::{de::IntoDeserializer, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Axis, AxisMapping, AxisSettings, Button, InputKind, InputMap, Key, Mapping, Modifier, MouseButton};
+
+/// Axis values past this count as input while listening for a new binding
+pub const LISTEN_AXIS_THRESHOLD: f32 = 0.5;
+
+#[derive(Debug)]
+pub enum ActionMapError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+}
+
+impl std::error::Error for ActionMapError {}
+
+impl Display for ActionMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Failed to access action map file: {err}"),
+            Self::Parse(err) => write!(
+                f,
+                "Failed to parse action map: {err}\nHint: bindings are written as \"Key::W\", \"Button::South\", \"Mouse::Left\", \"Axis::LeftStickX\", chords as \"Modifier::Control + Key::S\" and sequences as {{ sequence = [\"Key::Down\", \"Key::Right\"], within_ms = 300 }}"
+            ),
+            Self::Serialize(err) => write!(f, "Failed to serialize action map: {err}"),
+        }
+    }
+}
+
+impl From<io::Error> for ActionMapError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl Display for InputKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "Key::{key:?}"),
+            Self::Axis(axis) => write!(f, "Axis::{axis:?}"),
+            Self::Button(button) => write!(f, "Button::{button:?}"),
+            Self::Mouse(button) => write!(f, "Mouse::{button:?}"),
+            Self::Modifier(modifier) => write!(f, "Modifier::{modifier:?}"),
+        }
+    }
+}
+
+impl Serialize for InputKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl FromStr for InputKind {
+    type Err = serde::de::value::Error;
+
+    fn from_str(binding: &str) -> Result<Self, Self::Err> {
+        use serde::de::Error;
+
+        let (kind, name) = binding
+            .trim()
+            .split_once("::")
+            .ok_or_else(|| Self::Err::custom(format!("binding {binding} is missing its kind")))?;
+        let name = name.into_deserializer();
+
+        match kind {
+            "Key" => Key::deserialize(name).map(InputKind::Key),
+            "Button" => Button::deserialize(name).map(InputKind::Button),
+            "Axis" => Axis::deserialize(name).map(InputKind::Axis),
+            "Mouse" => MouseButton::deserialize(name).map(InputKind::Mouse),
+            "Modifier" => Modifier::deserialize(name).map(InputKind::Modifier),
+            _ => Err(Self::Err::custom(format!(
+                "unknown binding kind {kind}, expected Key, Button, Mouse, Modifier or Axis"
+            ))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for InputKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let binding = String::deserialize(deserializer)?;
+        binding.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// One way to trigger an action
+#[derive(Debug, Clone, PartialEq)]
+pub enum Binding {
+    Input(InputKind),
+    /// Every input held at once, e.g. `Modifier::Control + Key::S`
+    Chord(Vec<InputKind>),
+    /// Inputs pressed one after another, see `InputMap::sequence_completed`
+    Sequence { steps: Vec<InputKind>, window: Duration },
+}
+
+impl Binding {
+    pub fn is_active(&self, input_map: &InputMap) -> bool {
+        match self {
+            Self::Input(input) => input_map.get_input(*input) > 0.0,
+            Self::Chord(chord) => input_map.chord_held(chord),
+            Self::Sequence { steps, window } => input_map.sequence_completed(steps, *window),
+        }
+    }
+
+    /// A chord counts as held since its last input was pressed, a sequence since its last step
+    pub fn pressed_duration(&self, input_map: &InputMap) -> Option<Duration> {
+        match self {
+            Self::Input(input) => input_map.pressed_duration(*input),
+            Self::Chord(chord) => chord
+                .iter()
+                .map(|input| input_map.pressed_duration(*input))
+                .min()
+                .flatten(),
+            Self::Sequence { steps, .. } if self.is_active(input_map) => {
+                input_map.pressed_duration(*steps.last()?)
+            }
+            Self::Sequence { .. } => None,
+        }
+    }
+}
+
+impl From<InputKind> for Binding {
+    fn from(input: InputKind) -> Self {
+        Self::Input(input)
+    }
+}
+
+impl Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |inputs: &[InputKind], separator: &str| {
+            inputs.iter().map(ToString::to_string).collect::<Vec<_>>().join(separator)
+        };
+
+        match self {
+            Self::Input(input) => write!(f, "{input}"),
+            Self::Chord(chord) => write!(f, "{}", join(chord, " + ")),
+            Self::Sequence { steps, window } => {
+                write!(f, "[{}] within {}ms", join(steps, ", "), window.as_millis())
+            }
+        }
+    }
+}
+
+/// Inputs and chords are written as strings, sequences as tables
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum BindingRepr {
+    Text(String),
+    Sequence { sequence: Vec<InputKind>, within_ms: u64 },
+}
+
+impl Serialize for Binding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Sequence { steps, window } => BindingRepr::Sequence {
+                sequence: steps.clone(),
+                within_ms: window.as_millis() as u64,
+            }
+            .serialize(serializer),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match BindingRepr::deserialize(deserializer)? {
+            BindingRepr::Text(text) => {
+                let mut inputs = text
+                    .split('+')
+                    .map(str::parse)
+                    .collect::<Result<Vec<InputKind>, _>>()
+                    .map_err(serde::de::Error::custom)?;
+
+                Ok(if inputs.len() == 1 {
+                    Binding::Input(inputs.remove(0))
+                } else {
+                    Binding::Chord(inputs)
+                })
+            }
+            BindingRepr::Sequence { sequence, within_ms } => Ok(Binding::Sequence {
+                steps: sequence,
+                window: Duration::from_millis(within_ms),
+            }),
+        }
+    }
+}
+
+/// Inputs driving an axis action, axes win over the key and button fallbacks when they are further from zero
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AxisBindings {
+    /// Written as `"Axis::LeftStickX"` like other bindings, the bare `"LeftStickX"` is accepted too
+    #[serde(default, serialize_with = "serialize_axes", deserialize_with = "deserialize_axes")]
+    pub axes: Vec<Axis>,
+    #[serde(default)]
+    pub positive: Vec<Binding>,
+    #[serde(default)]
+    pub negative: Vec<Binding>,
+    /// Replaces the compiled-in `AxisMapping::settings`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<AxisSettings>,
+}
+
+fn serialize_axes<S: Serializer>(axes: &[Axis], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(axes.iter().map(|axis| InputKind::Axis(*axis)))
+}
+
+fn deserialize_axes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Axis>, D::Error> {
+    use serde::de::Error;
+
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|axis| {
+            let binding = match axis.contains("::") {
+                true => axis.parse(),
+                false => format!("Axis::{}", axis.trim()).parse(),
+            };
+            match binding.map_err(D::Error::custom)? {
+                InputKind::Axis(axis) => Ok(axis),
+                binding => Err(D::Error::custom(format!("{binding} is not an axis"))),
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn mapping_bindings<M: Mapping>() -> Vec<Binding> {
+    let keys = M::keys().iter().copied().map(InputKind::Key);
+    let buttons = M::buttons().iter().copied().map(InputKind::Button);
+    let mouse_buttons = M::mouse_buttons().iter().copied().map(InputKind::Mouse);
+    let chords = M::chords().iter().map(|chord| Binding::Chord(chord.to_vec()));
+    let sequences = M::sequences().iter().map(|sequence| Binding::Sequence {
+        steps: sequence.steps.to_vec(),
+        window: sequence.window,
+    });
+
+    keys.chain(buttons)
+        .chain(mouse_buttons)
+        .map(Binding::Input)
+        .chain(chords)
+        .chain(sequences)
+        .collect()
+}
+
+fn axis_mapping_bindings<M: AxisMapping>() -> AxisBindings {
+    AxisBindings {
+        axes: M::axes().to_vec(),
+        positive: mapping_bindings::<M::PositiveMapping>(),
+        negative: mapping_bindings::<M::NegativeMapping>(),
+        settings: Some(M::settings()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Listen {
+    Action(String),
+    /// Bindings to start from when the axis action has no runtime bindings yet
+    Axis(String, AxisBindings),
+}
+
+/// Runtime bindings for named actions, `Input<T>` and `InputAxis<T>` use the bindings stored
+/// under their binding's name and fall back to the ones compiled in with `define_binding!`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActionMap {
+    #[serde(default)]
+    actions: BTreeMap<String, Vec<Binding>>,
+    #[serde(default)]
+    axes: BTreeMap<String, AxisBindings>,
+    #[serde(skip)]
+    listening: Option<Listen>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores the compiled-in bindings of `M`, e.g. to write a default config file
+    pub fn with_mapping<M: Mapping>(mut self) -> Self {
+        self.bind(M::NAME, mapping_bindings::<M>());
+        self
+    }
+
+    pub fn with_axis_mapping<M: AxisMapping>(mut self) -> Self {
+        self.bind_axis(M::NAME, axis_mapping_bindings::<M>());
+        self
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, ActionMapError> {
+        toml::from_str(source).map_err(ActionMapError::Parse)
+    }
+
+    pub fn to_toml(&self) -> Result<String, ActionMapError> {
+        toml::to_string_pretty(self).map_err(ActionMapError::Serialize)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ActionMapError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ActionMapError> {
+        fs::write(path, self.to_toml()?)?;
+        Ok(())
+    }
+
+    pub fn action(&self, name: &str) -> Option<&[Binding]> {
+        self.actions.get(name).map(Vec::as_slice)
+    }
+
+    pub fn axis(&self, name: &str) -> Option<&AxisBindings> {
+        self.axes.get(name)
+    }
+
+    /// Replaces the bindings of `name`, an empty list unbinds it without falling back to the defaults
+    pub fn bind(&mut self, name: impl Into<String>, bindings: Vec<Binding>) {
+        self.actions.insert(name.into(), bindings);
+    }
+
+    pub fn bind_axis(&mut self, name: impl Into<String>, bindings: AxisBindings) {
+        self.axes.insert(name.into(), bindings);
+    }
+
+    /// Removes the runtime bindings of `name` so its compiled-in bindings apply again
+    pub fn reset(&mut self, name: &str) {
+        self.actions.remove(name);
+        self.axes.remove(name);
+    }
+
+    /// Binds the next pressed key, button or mouse button to the action `name`, replacing its bindings
+    pub fn listen(&mut self, name: impl Into<String>) {
+        self.listening = Some(Listen::Action(name.into()));
+    }
+
+    /// Binds the next axis moved past `LISTEN_AXIS_THRESHOLD` to the axis action of `M`, replacing its
+    /// axes. Without runtime bindings the compiled-in key and button fallbacks of `M` are kept
+    pub fn listen_axis<M: AxisMapping>(&mut self) {
+        self.listening = Some(Listen::Axis(M::NAME.into(), axis_mapping_bindings::<M>()));
+    }
+
+    /// Name of the action waiting for its next input
+    pub fn listening(&self) -> Option<&str> {
+        match &self.listening {
+            Some(Listen::Action(name) | Listen::Axis(name, _)) => Some(name),
+            None => None,
+        }
+    }
+
+    pub fn cancel_listen(&mut self) {
+        self.listening = None;
+    }
+
+    /// Completes a pending `listen` with `input`, returns whether it was bound
+    pub fn capture(&mut self, input: InputKind, value: f32) -> bool {
+        let known = !matches!(
+            input,
+            InputKind::Key(Key::Unknown)
+                | InputKind::Button(Button::Unknown)
+                | InputKind::Mouse(MouseButton::Unknown)
+                | InputKind::Axis(Axis::Unknown)
+                | InputKind::Modifier(_)
+        );
+        let bound = match (&self.listening, input) {
+            _ if !known => false,
+            (Some(Listen::Action(name)), InputKind::Key(_) | InputKind::Button(_) | InputKind::Mouse(_))
+                if value > 0.0 =>
+            {
+                self.actions.insert(name.clone(), vec![Binding::Input(input)]);
+                true
+            }
+            (Some(Listen::Axis(name, defaults)), InputKind::Axis(axis)) if value.abs() > LISTEN_AXIS_THRESHOLD => {
+                self.axes.entry(name.clone()).or_insert_with(|| defaults.clone()).axes = vec![axis];
+                true
+            }
+            _ => false,
+        };
+
+        if bound {
+            self.listening = None;
+        }
+        bound
+    }
+
+    pub(crate) fn check(&self, input_map: &InputMap, name: &str) -> Option<bool> {
+        let bindings = self.actions.get(name)?;
+        Some(bindings.iter().any(|binding| binding.is_active(input_map)))
+    }
+
+    pub(crate) fn check_axis(&self, input_map: &InputMap, name: &str) -> Option<f32> {
+        let bindings = self.axes.get(name)?;
+        let pressed = |bindings: &[Binding]| bindings.iter().any(|binding| binding.is_active(input_map));
+        let fallback = pressed(&bindings.positive) as i32 as f32 - pressed(&bindings.negative) as i32 as f32;
+
+        Some(input_map.strongest_axis(&bindings.axes, fallback))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Jump;
+
+    impl Mapping for Jump {
+        const NAME: &'static str = "jump";
+
+        fn keys<'a>() -> &'a [Key] {
+            &[Key::Space]
+        }
+
+        fn buttons<'a>() -> &'a [Button] {
+            &[Button::South]
+        }
+    }
+
+    struct Right;
+
+    impl Mapping for Right {
+        fn keys<'a>() -> &'a [Key] {
+            &[Key::D]
+        }
+
+        fn buttons<'a>() -> &'a [Button] {
+            &[]
+        }
+    }
+
+    struct MoveX;
+
+    impl AxisMapping for MoveX {
+        const NAME: &'static str = "move_x";
+
+        type PositiveMapping = Right;
+        type NegativeMapping = ();
+
+        fn axes<'a>() -> &'a [Axis] {
+            &[Axis::LeftStickX]
+        }
+
+        fn settings() -> AxisSettings {
+            AxisSettings::new().with_dead_zone(0.1)
+        }
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let mut actions = ActionMap::new().with_mapping::<Jump>().with_axis_mapping::<MoveX>();
+        actions.bind(
+            "save",
+            vec![Binding::Chord(vec![
+                InputKind::Modifier(Modifier::Control),
+                InputKind::Key(Key::S),
+            ])],
+        );
+        actions.bind(
+            "dash",
+            vec![Binding::Sequence {
+                steps: vec![InputKind::Key(Key::Right), InputKind::Key(Key::Right)],
+                window: Duration::from_millis(250),
+            }],
+        );
+
+        let toml = actions.to_toml().unwrap();
+        assert!(toml.contains(r#"axes = ["Axis::LeftStickX"]"#));
+        assert_eq!(ActionMap::from_toml(&toml).unwrap(), actions);
+
+        let path = std::env::temp_dir().join(format!("isle_actions_{}.toml", std::process::id()));
+        actions.save(&path).unwrap();
+        let loaded = ActionMap::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), actions);
+    }
+
+    #[test]
+    fn parse_hand_written_bindings() {
+        let actions = ActionMap::from_toml(
+            r#"
+            [actions]
+            jump = ["Key::Space", "Mouse::Left"]
+            save = ["Modifier::Control + Key::S"]
+            dash = [{ sequence = ["Key::Down", "Key::Right"], within_ms = 300 }]
+
+            [axes.move_x]
+            axes = ["Axis::LeftStickX", "RightStickX"]
+            positive = ["Key::D"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            actions.action("jump"),
+            Some([InputKind::Key(Key::Space).into(), InputKind::Mouse(MouseButton::Left).into()].as_slice())
+        );
+        assert_eq!(
+            actions.action("save"),
+            Some([Binding::Chord(vec![InputKind::Modifier(Modifier::Control), InputKind::Key(Key::S)])].as_slice())
+        );
+        assert_eq!(
+            actions.action("dash"),
+            Some(
+                [Binding::Sequence {
+                    steps: vec![InputKind::Key(Key::Down), InputKind::Key(Key::Right)],
+                    window: Duration::from_millis(300),
+                }]
+                .as_slice()
+            )
+        );
+        let move_x = actions.axis("move_x").unwrap();
+        assert_eq!(move_x.axes, [Axis::LeftStickX, Axis::RightStickX]);
+        assert!(move_x.negative.is_empty() && move_x.settings.is_none());
+
+        assert!(matches!(
+            ActionMap::from_toml(r#"actions = { jump = ["Space"] }"#),
+            Err(ActionMapError::Parse(_))
+        ));
+        assert!(matches!(
+            ActionMap::from_toml(r#"actions = { jump = ["Key::Spacebar"] }"#),
+            Err(ActionMapError::Parse(_))
+        ));
+        assert!(matches!(
+            ActionMap::from_toml(r#"axes.move_x = { axes = ["Key::D"] }"#),
+            Err(ActionMapError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn rebinding() {
+        let mut actions = ActionMap::new();
+        let mut input_map = InputMap::new();
+        input_map.set_key(Key::Space, true);
+        assert!(Jump::resolve(&input_map, Some(&actions)));
+
+        actions.listen("jump");
+        assert_eq!(actions.listening(), Some("jump"));
+        assert!(!actions.capture(InputKind::Key(Key::Unknown), 1.0));
+        assert!(!actions.capture(InputKind::Modifier(Modifier::Shift), 1.0));
+        assert!(!actions.capture(InputKind::Key(Key::E), 0.0));
+        assert!(!actions.capture(InputKind::Axis(Axis::LeftStickX), 1.0));
+        assert!(actions.capture(InputKind::Key(Key::E), 1.0));
+        assert_eq!(actions.listening(), None);
+        assert_eq!(actions.action("jump"), Some([InputKind::Key(Key::E).into()].as_slice()));
+
+        assert!(!Jump::resolve(&input_map, Some(&actions)));
+        input_map.set_key(Key::E, true);
+        assert!(Jump::resolve(&input_map, Some(&actions)));
+
+        actions.bind("jump", Vec::new());
+        assert!(!Jump::resolve(&input_map, Some(&actions)));
+        actions.reset("jump");
+        assert!(Jump::resolve(&input_map, Some(&actions)));
+
+        actions.listen_axis::<MoveX>();
+        assert_eq!(actions.listening(), Some("move_x"));
+        assert!(!actions.capture(InputKind::Axis(Axis::RightStickX), LISTEN_AXIS_THRESHOLD));
+        assert!(!actions.capture(InputKind::Key(Key::D), 1.0));
+        assert!(actions.capture(InputKind::Axis(Axis::RightStickX), -0.8));
+        assert_eq!(actions.axis("move_x").unwrap().axes, [Axis::RightStickX]);
+
+        input_map.set_axis(Axis::RightStickX, -0.55);
+        assert!((MoveX::resolve(&input_map, Some(&actions)) + 0.5).abs() < 1e-5);
+
+        // The compiled-in key fallback still drives the rebound axis
+        input_map.set_axis(Axis::RightStickX, 0.0);
+        input_map.set_key(Key::D, true);
+        assert_eq!(MoveX::resolve(&input_map, Some(&actions)), 1.0);
+
+        actions.listen("jump");
+        actions.cancel_listen();
+        assert!(!actions.capture(InputKind::Key(Key::Q), 1.0));
+    }
+}
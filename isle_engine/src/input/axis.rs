@@ -0,0 +1,154 @@
+use isle_math::vector::d2::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Shapes raw axis values, applied in order: dead zone, response curve, sensitivity, inversion
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AxisSettings {
+    /// Values this close to zero read as zero, the rest is rescaled to start from zero
+    pub dead_zone: f32,
+    /// Exponent of the response curve, above 1 gives finer control near the center
+    pub exponent: f32,
+    pub sensitivity: f32,
+    pub invert: bool,
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.0,
+            exponent: 1.0,
+            sensitivity: 1.0,
+            invert: false,
+        }
+    }
+}
+
+impl AxisSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_dead_zone(mut self, dead_zone: f32) -> Self {
+        self.dead_zone = dead_zone;
+        self
+    }
+
+    pub fn with_exponent(mut self, exponent: f32) -> Self {
+        self.exponent = exponent;
+        self
+    }
+
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+
+    pub fn with_invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    /// Applies the axial dead zone and the curve to a single axis
+    pub fn apply(&self, value: f32) -> f32 {
+        let magnitude = self.rescale(value.abs());
+        self.finish(value.signum() * magnitude.powf(self.exponent))
+    }
+
+    /// Applies a radial dead zone to a stick, the larger of both dead zones is used and the
+    /// length is capped at 1 so diagonals are no faster than straight movement
+    pub fn apply_radial(x: &AxisSettings, y: &AxisSettings, value: Vec2) -> Vec2 {
+        let length = value.0.hypot(value.1);
+        let dead_zone = x.dead_zone.max(y.dead_zone);
+        if length <= dead_zone || dead_zone >= 1.0 {
+            return Vec2(0.0, 0.0);
+        }
+
+        let scaled = ((length - dead_zone) / (1.0 - dead_zone)).min(1.0);
+        let (dir_x, dir_y) = (value.0 / length, value.1 / length);
+        Vec2(
+            x.finish(dir_x * scaled.powf(x.exponent)),
+            y.finish(dir_y * scaled.powf(y.exponent)),
+        )
+    }
+
+    /// Values past 1, e.g. mouse deltas, are left as they are
+    fn rescale(&self, magnitude: f32) -> f32 {
+        if magnitude <= self.dead_zone {
+            0.0
+        } else if magnitude < 1.0 {
+            (magnitude - self.dead_zone) / (1.0 - self.dead_zone)
+        } else {
+            magnitude
+        }
+    }
+
+    fn finish(&self, value: f32) -> f32 {
+        let value = value * self.sensitivity;
+        if self.invert {
+            -value
+        } else {
+            value
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn dead_zone_edges() {
+        let settings = AxisSettings::new().with_dead_zone(0.2);
+        assert_eq!(settings.apply(0.2), 0.0);
+        assert_eq!(settings.apply(-0.2), 0.0);
+        assert!(settings.apply(0.21) > 0.0);
+        assert_close(settings.apply(0.6), 0.5);
+        assert_close(settings.apply(-0.6), -0.5);
+        assert_close(settings.apply(1.0), 1.0);
+        // Past 1, e.g. mouse deltas, is not rescaled
+        assert_eq!(settings.apply(3.0), 3.0);
+    }
+
+    #[test]
+    fn curve_endpoints() {
+        let settings = AxisSettings::new().with_dead_zone(0.1).with_exponent(2.0);
+        assert_eq!(settings.apply(0.0), 0.0);
+        assert_close(settings.apply(1.0), 1.0);
+        assert_close(settings.apply(-1.0), -1.0);
+        assert_close(settings.apply(0.55), 0.25);
+
+        let settings = settings.with_sensitivity(2.0).with_invert(true);
+        assert_close(settings.apply(1.0), -2.0);
+        assert_close(settings.apply(-1.0), 2.0);
+    }
+
+    #[test]
+    fn radial_dead_zone_and_clamp() {
+        let x = AxisSettings::new().with_dead_zone(0.1);
+        let y = AxisSettings::new().with_dead_zone(0.2);
+
+        // The larger dead zone applies to the stick as a whole
+        assert_eq!(AxisSettings::apply_radial(&x, &y, Vec2(0.15, 0.0)), Vec2(0.0, 0.0));
+        assert_eq!(AxisSettings::apply_radial(&x, &y, Vec2(0.12, 0.12)), Vec2(0.0, 0.0));
+
+        // Full diagonals are capped to length 1
+        let diagonal = AxisSettings::apply_radial(&x, &y, Vec2(1.0, 1.0));
+        assert_close(diagonal.0.hypot(diagonal.1), 1.0);
+        assert_close(diagonal.0, diagonal.1);
+
+        let straight = AxisSettings::apply_radial(&x, &y, Vec2(0.0, -1.0));
+        assert_close(straight.0, 0.0);
+        assert_close(straight.1, -1.0);
+
+        let halfway = AxisSettings::apply_radial(&x, &y, Vec2(0.6, 0.0));
+        assert_close(halfway.0, 0.5);
+
+        let disabled = AxisSettings::new().with_dead_zone(1.0);
+        assert_eq!(AxisSettings::apply_radial(&disabled, &y, Vec2(1.0, 0.0)), Vec2(0.0, 0.0));
+    }
+}
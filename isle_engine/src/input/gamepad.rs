@@ -0,0 +1,305 @@
+use std::{cell::UnsafeCell, time::Duration};
+
+use isle_ecs::{
+    ecs::{RefType, SystemParam},
+    prelude::TypeSet,
+    world::World,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+
+use super::{Axis, Button, InputMap};
+use crate::event::{init_events, Events};
+
+/// Player index of params that read the merged input of every device
+pub const ANY_PLAYER: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GamepadId(pub usize);
+
+impl From<gilrs::GamepadId> for GamepadId {
+    fn from(id: gilrs::GamepadId) -> Self {
+        Self(id.into())
+    }
+}
+
+/// Sent the first time a gamepad reports in, `player` is the player it was assigned to
+#[derive(Debug, Clone)]
+pub struct GamepadConnected {
+    pub id: GamepadId,
+    pub name: String,
+    pub player: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GamepadDisconnected {
+    pub id: GamepadId,
+    pub player: Option<usize>,
+}
+
+/// Sends `GamepadConnected` and `GamepadDisconnected` as a single system param
+pub struct GamepadEvents<'a> {
+    connected: &'a mut Events<GamepadConnected>,
+    disconnected: &'a mut Events<GamepadDisconnected>,
+}
+
+impl GamepadEvents<'_> {
+    pub fn connected(&mut self, event: GamepadConnected) {
+        self.connected.send(event);
+    }
+
+    pub fn disconnected(&mut self, event: GamepadDisconnected) {
+        self.disconnected.send(event);
+    }
+}
+
+impl SystemParam for GamepadEvents<'_> {
+    type State = ();
+    type Item<'new> = GamepadEvents<'new>;
+
+    fn init_state(world: &UnsafeCell<World>) -> Self::State {
+        init_events::<GamepadConnected>(world);
+        init_events::<GamepadDisconnected>(world);
+    }
+
+    fn from_world<'w>(world: &'w UnsafeCell<World>, _: &'w mut Self::State, _: &str) -> Self::Item<'w> {
+        GamepadEvents {
+            connected: unsafe { (*world.get()).get_resource_mut::<Events<GamepadConnected>>() }.unwrap(),
+            disconnected: unsafe { (*world.get()).get_resource_mut::<Events<GamepadDisconnected>>() }.unwrap(),
+        }
+    }
+
+    fn collect_types(types: &mut impl TypeSet) {
+        types.insert_type::<Events<GamepadConnected>>(RefType::Mutable);
+        types.insert_type::<Events<GamepadDisconnected>>(RefType::Mutable);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputDevice {
+    KeyboardMouse,
+    Gamepad(GamepadId),
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct GamepadState {
+    pub(crate) buttons: FxHashSet<Button>,
+    pub(crate) axes: FxHashMap<Axis, f32>,
+}
+
+/// One `InputMap` per local player, fed only by the devices assigned to that player
+///
+/// Keyboard and mouse start out on player 0, gamepads are given to the first player without a
+/// gamepad when they connect unless auto assignment is turned off. Assignments survive
+/// disconnects so a controller that reconnects returns to the same player
+#[derive(Clone)]
+pub struct PlayerInputs {
+    players: Vec<InputMap>,
+    devices: FxHashMap<InputDevice, usize>,
+    auto_assign: bool,
+}
+
+impl Default for PlayerInputs {
+    fn default() -> Self {
+        Self {
+            players: vec![InputMap::default()],
+            devices: FxHashMap::from_iter([(InputDevice::KeyboardMouse, 0)]),
+            auto_assign: true,
+        }
+    }
+}
+
+impl PlayerInputs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_auto_assign(mut self, auto_assign: bool) -> Self {
+        self.auto_assign = auto_assign;
+        self
+    }
+
+    pub fn with_device(mut self, device: InputDevice, player: usize) -> Self {
+        self.assign(device, player);
+        self
+    }
+
+    /// Moves `device` to `player`, adding players up to `player` as needed
+    pub fn assign(&mut self, device: InputDevice, player: usize) {
+        let connected = match (device, self.player_of(device)) {
+            (InputDevice::Gamepad(id), Some(previous)) => self.players[previous].has_gamepad(id),
+            _ => false,
+        };
+        self.unassign(device);
+
+        self.ensure_player(player);
+        self.devices.insert(device, player);
+        if let (InputDevice::Gamepad(id), true) = (device, connected) {
+            self.players[player].add_gamepad(id);
+        }
+    }
+
+    /// Returns the player `device` was assigned to
+    pub fn unassign(&mut self, device: InputDevice) -> Option<usize> {
+        let player = self.devices.remove(&device)?;
+        match device {
+            InputDevice::Gamepad(id) => self.players[player].remove_gamepad(id),
+            InputDevice::KeyboardMouse => self.players[player].release_keyboard_mouse(),
+        }
+        Some(player)
+    }
+
+    pub fn player_of(&self, device: InputDevice) -> Option<usize> {
+        self.devices.get(&device).copied()
+    }
+
+    pub fn devices(&self, player: usize) -> impl Iterator<Item = InputDevice> + '_ {
+        self.devices
+            .iter()
+            .filter(move |(_, assigned)| **assigned == player)
+            .map(|(device, _)| *device)
+    }
+
+    pub fn player(&self, player: usize) -> Option<&InputMap> {
+        self.players.get(player)
+    }
+
+    pub fn player_mut(&mut self, player: usize) -> Option<&mut InputMap> {
+        self.players.get_mut(player)
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    fn ensure_player(&mut self, player: usize) {
+        if player >= self.players.len() {
+            self.players.resize_with(player + 1, InputMap::default);
+        }
+    }
+
+    fn first_player_without_gamepad(&self) -> usize {
+        (0..)
+            .find(|player| {
+                !self
+                    .devices
+                    .iter()
+                    .any(|(device, assigned)| matches!(device, InputDevice::Gamepad(_)) && assigned == player)
+            })
+            .unwrap()
+    }
+
+    pub(crate) fn begin_frame(&mut self, time: Duration) {
+        self.players.iter_mut().for_each(|player| player.begin_frame_at(time));
+    }
+
+    pub(crate) fn device_map(&mut self, device: InputDevice) -> Option<&mut InputMap> {
+        let player = *self.devices.get(&device)?;
+        self.players.get_mut(player)
+    }
+
+    /// Assigns a newly connected gamepad, returns its player
+    pub(crate) fn connect(&mut self, id: GamepadId) -> Option<usize> {
+        let device = InputDevice::Gamepad(id);
+        let player = match self.player_of(device) {
+            Some(player) => player,
+            None if self.auto_assign => {
+                let player = self.first_player_without_gamepad();
+                self.assign(device, player);
+                player
+            }
+            None => return None,
+        };
+
+        self.players[player].add_gamepad(id);
+        Some(player)
+    }
+
+    pub(crate) fn disconnect(&mut self, id: GamepadId) -> Option<usize> {
+        let player = self.player_of(InputDevice::Gamepad(id))?;
+        self.players[player].remove_gamepad(id);
+        Some(player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Key, MouseButton};
+
+    #[test]
+    fn gamepads_go_to_players_without_one() {
+        let mut players = PlayerInputs::new();
+        assert_eq!(players.connect(GamepadId(0)), Some(0));
+        assert_eq!(players.connect(GamepadId(1)), Some(1));
+        assert_eq!(players.len(), 2);
+        assert!(players.player(0).unwrap().has_gamepad(GamepadId(0)));
+        assert!(!players.player(0).unwrap().has_gamepad(GamepadId(1)));
+
+        let mut devices: Vec<_> = players.devices(0).collect();
+        devices.sort_by_key(|device| *device != InputDevice::KeyboardMouse);
+        assert_eq!(devices, [InputDevice::KeyboardMouse, InputDevice::Gamepad(GamepadId(0))]);
+    }
+
+    #[test]
+    fn reconnect_returns_to_the_same_player() {
+        let mut players = PlayerInputs::new();
+        players.connect(GamepadId(0));
+        players.connect(GamepadId(1));
+        players
+            .device_map(InputDevice::Gamepad(GamepadId(0)))
+            .unwrap()
+            .set_gamepad_button(GamepadId(0), Button::South, true);
+
+        assert_eq!(players.disconnect(GamepadId(0)), Some(0));
+        assert!(!players.player(0).unwrap().has_gamepad(GamepadId(0)));
+        assert!(!players.player(0).unwrap().get_button(Button::South));
+        assert_eq!(players.player_of(InputDevice::Gamepad(GamepadId(0))), Some(0));
+
+        // Player 0 keeps its slot while its gamepad is away
+        assert_eq!(players.connect(GamepadId(2)), Some(2));
+        assert_eq!(players.connect(GamepadId(0)), Some(0));
+        assert!(players.player(0).unwrap().has_gamepad(GamepadId(0)));
+
+        assert_eq!(players.disconnect(GamepadId(5)), None);
+    }
+
+    #[test]
+    fn manual_assignment() {
+        let mut players = PlayerInputs::new()
+            .with_auto_assign(false)
+            .with_device(InputDevice::Gamepad(GamepadId(3)), 2);
+        assert_eq!(players.len(), 3);
+        assert_eq!(players.connect(GamepadId(0)), None);
+        assert_eq!(players.connect(GamepadId(3)), Some(2));
+
+        let held = InputDevice::Gamepad(GamepadId(3));
+        players.device_map(held).unwrap().set_gamepad_button(GamepadId(3), Button::East, true);
+
+        // Moving a connected gamepad releases its input on the old player
+        players.assign(held, 1);
+        assert!(!players.player(2).unwrap().has_gamepad(GamepadId(3)));
+        assert!(!players.player(2).unwrap().get_button(Button::East));
+        assert!(players.player(1).unwrap().has_gamepad(GamepadId(3)));
+
+        let keyboard = players.device_map(InputDevice::KeyboardMouse).unwrap();
+        keyboard.set_key(Key::D, true);
+        keyboard.set_mouse_button(MouseButton::Left, true);
+
+        // Moving the keyboard and mouse releases what they held on the old player
+        players.assign(InputDevice::KeyboardMouse, 4);
+        assert!(!players.player(0).unwrap().get_key(Key::D));
+        assert!(!players.player(0).unwrap().get_mouse_button(MouseButton::Left));
+        assert_eq!(players.len(), 5);
+        assert_eq!(players.player_of(InputDevice::KeyboardMouse), Some(4));
+        assert!(players.device_map(InputDevice::Gamepad(GamepadId(0))).is_none());
+
+        assert_eq!(players.unassign(held), Some(1));
+        assert!(!players.player(1).unwrap().has_gamepad(GamepadId(3)));
+        assert_eq!(players.connect(GamepadId(3)), None);
+    }
+}
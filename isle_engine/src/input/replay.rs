@@ -0,0 +1,387 @@
+use std::{fmt::Display, fs, io, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use super::{GamepadId, InputKind, Key, MouseButton};
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// Frame numbers must be strictly increasing, `frame` did not follow `previous`
+    UnorderedFrames { previous: u64, frame: u64 },
+}
+
+impl std::error::Error for RecordingError {}
+
+impl Display for RecordingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Failed to access input recording: {err}"),
+            Self::Json(err) => write!(
+                f,
+                "Failed to parse input recording: {err}\nHint: recordings are written by `InputRecording::save`"
+            ),
+            Self::UnorderedFrames { previous, frame } => write!(
+                f,
+                "Input recording frame {frame} follows frame {previous}\nHint: frames must be sorted without duplicates"
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for RecordingError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+/// One change `update_input` applied to the `InputMap`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RecordedInput {
+    Key { key: Key, state: bool },
+    MouseButton { button: MouseButton, state: bool },
+    Cursor { position: Option<(f32, f32)>, window_size: (f32, f32) },
+    MouseMotion(f32, f32),
+    Scroll(f32, f32),
+    GamepadConnected { id: GamepadId, name: String },
+    GamepadDisconnected { id: GamepadId },
+    Gamepad { id: GamepadId, input: InputKind, value: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub frame: u64,
+    pub inputs: Vec<RecordedInput>,
+}
+
+/// Inputs by frame, frames without input are left out
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    pub frames: Vec<RecordedFrame>,
+    /// Input clock of every frame in nanoseconds, including frames without input
+    #[serde(default)]
+    pub frame_times: Vec<u64>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, frame: u64, input: RecordedInput) {
+        match self.frames.last_mut() {
+            Some(last) if last.frame == frame => last.inputs.push(input),
+            _ => self.frames.push(RecordedFrame {
+                frame,
+                inputs: vec![input],
+            }),
+        }
+    }
+
+    /// Frame after the last recorded input
+    pub fn len_frames(&self) -> u64 {
+        self.frames.last().map_or(0, |last| last.frame + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        let recording: Self = serde_json::from_slice(&fs::read(path)?)?;
+        match recording.frames.windows(2).find(|pair| pair[0].frame >= pair[1].frame) {
+            Some(pair) => Err(RecordingError::UnorderedFrames {
+                previous: pair[0].frame,
+                frame: pair[1].frame,
+            }),
+            None => Ok(recording),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+}
+
+/// Where `update_input` gets its input from, live devices by default
+///
+/// While recording every applied input is stored with the frame it was applied on, during
+/// playback the window and gamepads are ignored and the recording is applied instead. The input
+/// clock of each frame is recorded as well so press timings replay exactly
+#[derive(Debug, Default)]
+pub enum InputReplay {
+    #[default]
+    Live,
+    Recording {
+        recording: InputRecording,
+        frame: u64,
+    },
+    Playback {
+        recording: InputRecording,
+        frame: u64,
+        next: usize,
+    },
+}
+
+impl InputReplay {
+    pub fn record() -> Self {
+        Self::Recording {
+            recording: InputRecording::new(),
+            frame: 0,
+        }
+    }
+
+    pub fn play(recording: InputRecording) -> Self {
+        Self::Playback {
+            recording,
+            frame: 0,
+            next: 0,
+        }
+    }
+
+    /// Frames of input handled since recording or playback started
+    pub fn frame(&self) -> u64 {
+        match self {
+            Self::Live => 0,
+            Self::Recording { frame, .. } | Self::Playback { frame, .. } => *frame,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self, Self::Playback { .. })
+    }
+
+    /// True once playback applied the last recorded frame
+    pub fn is_finished(&self) -> bool {
+        match self {
+            Self::Playback { recording, next, .. } => *next >= recording.frames.len(),
+            _ => false,
+        }
+    }
+
+    pub fn recording(&self) -> Option<&InputRecording> {
+        match self {
+            Self::Live => None,
+            Self::Recording { recording, .. } | Self::Playback { recording, .. } => Some(recording),
+        }
+    }
+
+    /// Goes back to live input, returning the recording
+    pub fn stop(&mut self) -> Option<InputRecording> {
+        match std::mem::take(self) {
+            Self::Live => None,
+            Self::Recording { recording, .. } | Self::Playback { recording, .. } => Some(recording),
+        }
+    }
+
+    /// Recorded input clock of the current frame during playback, `None` for the live clock
+    pub(crate) fn frame_time(&self) -> Option<Duration> {
+        match self {
+            Self::Playback { recording, frame, .. } => recording
+                .frame_times
+                .get(*frame as usize)
+                .map(|nanos| Duration::from_nanos(*nanos)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn record_frame_time(&mut self, time: Duration) {
+        if let Self::Recording { recording, .. } = self {
+            recording.frame_times.push(time.as_nanos() as u64);
+        }
+    }
+
+    /// Recorded inputs of the current frame during playback, `None` for live input
+    pub(crate) fn playback_inputs(&mut self) -> Option<Vec<RecordedInput>> {
+        let Self::Playback {
+            recording,
+            frame,
+            next,
+        } = self
+        else {
+            return None;
+        };
+
+        match recording.frames.get(*next) {
+            Some(recorded) if recorded.frame == *frame => {
+                *next += 1;
+                Some(recorded.inputs.clone())
+            }
+            _ => Some(Vec::new()),
+        }
+    }
+
+    pub(crate) fn record_input(&mut self, input: &RecordedInput) {
+        if let Self::Recording { recording, frame } = self {
+            recording.push(*frame, input.clone());
+        }
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        if let Self::Recording { frame, .. } | Self::Playback { frame, .. } = self {
+            *frame += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use isle_ecs::{executor::Executor, schedule::Scheduler};
+    use isle_math::vector::d2::Vec2;
+
+    use super::*;
+    use crate::{
+        flow::Flow,
+        input::InputMap,
+        window::{KeyboardEvent, MouseEvent},
+    };
+
+    fn flow(replay: InputReplay) -> Flow<Scheduler, Executor> {
+        Flow::new()
+            .with_scheduler(Scheduler)
+            .with_executor(Executor)
+            .with_resource(replay)
+            .build()
+    }
+
+    /// Everything systems can observe
+    #[allow(clippy::type_complexity)]
+    fn observed(
+        input_map: &InputMap,
+    ) -> (bool, bool, bool, bool, Option<Vec2>, Vec2, Vec2, Vec<(InputKind, Duration)>, Option<Duration>) {
+        (
+            input_map.get_key(Key::W),
+            input_map.just_pressed(Key::W.into()),
+            input_map.just_released(Key::W.into()),
+            input_map.get_mouse_button(MouseButton::Left),
+            input_map.cursor_position(),
+            input_map.mouse_delta(),
+            input_map.scroll(),
+            input_map.history().collect(),
+            input_map.pressed_duration(MouseButton::Left.into()),
+        )
+    }
+
+    fn window_frame(flow: &mut Flow<Scheduler, Executor>, frame: usize) {
+        let window_size = Vec2(800.0, 600.0);
+        match frame {
+            0 => {
+                flow.send_event(KeyboardEvent { key: Key::W, state: true });
+                flow.send_event(MouseEvent::Moved {
+                    position: Some(Vec2(10.0, 20.0)),
+                    window_size,
+                });
+            }
+            1 => {
+                flow.send_event(MouseEvent::Button { button: MouseButton::Left, state: true });
+                flow.send_event(MouseEvent::Motion(Vec2(3.0, 4.0)));
+            }
+            3 => {
+                flow.send_event(MouseEvent::Scroll(Vec2(0.0, 1.0)));
+                flow.send_event(KeyboardEvent { key: Key::W, state: false });
+            }
+            4 => {
+                flow.send_event(MouseEvent::Button { button: MouseButton::Left, state: false });
+                flow.send_event(MouseEvent::Moved { position: None, window_size });
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn playback_matches_recorded_frames() {
+        const FRAMES: usize = 6;
+
+        let mut recorder = flow(InputReplay::record());
+        let recorded: Vec<_> = (0..FRAMES)
+            .map(|frame| {
+                window_frame(&mut recorder, frame);
+                // Playback has to reproduce hold durations without these pauses
+                std::thread::sleep(Duration::from_millis(5));
+                recorder.run_headless(1);
+                observed(recorder.get_resource::<InputMap>().unwrap())
+            })
+            .collect();
+        assert!(recorded[0].1 && recorded[1].3 && recorded[3].2);
+
+        let recording = recorder.get_resource_mut::<InputReplay>().unwrap().stop().unwrap();
+        assert_eq!(recording.frames.iter().map(|frame| frame.frame).collect::<Vec<_>>(), [0, 1, 3, 4]);
+        assert_eq!(recording.frame_times.len(), FRAMES);
+        assert!(recorded[3].8.is_some_and(|held| held >= Duration::from_millis(10)));
+
+        let path = std::env::temp_dir().join(format!("isle_recording_{}.json", std::process::id()));
+        recording.save(&path).unwrap();
+        let loaded = InputRecording::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded, recording);
+
+        let mut player = flow(InputReplay::play(loaded));
+        for (frame, expected) in recorded.iter().enumerate() {
+            // Live input is ignored during playback
+            player.send_event(KeyboardEvent { key: Key::Space, state: true });
+            player.run_headless(1);
+            let input_map = player.get_resource::<InputMap>().unwrap();
+            assert_eq!(&observed(input_map), expected, "frame {frame}");
+            assert!(!input_map.get_key(Key::Space));
+        }
+        assert!(player.get_resource::<InputReplay>().unwrap().is_finished());
+    }
+
+    #[test]
+    fn unordered_frames_fail_to_load() {
+        let path = std::env::temp_dir().join(format!("isle_unordered_{}.json", std::process::id()));
+        let frame = |frame| RecordedFrame { frame, inputs: Vec::new() };
+
+        let load = |frames: Vec<RecordedFrame>| {
+            InputRecording { frames, frame_times: Vec::new() }.save(&path).unwrap();
+            InputRecording::load(&path)
+        };
+        let unsorted = load(vec![frame(0), frame(3), frame(2)]);
+        let duplicate = load(vec![frame(1), frame(1)]);
+        let sorted = load(vec![frame(1), frame(2)]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(unsorted, Err(RecordingError::UnorderedFrames { previous: 3, frame: 2 })));
+        assert!(matches!(duplicate, Err(RecordingError::UnorderedFrames { previous: 1, frame: 1 })));
+        assert_eq!(sorted.unwrap().len_frames(), 3);
+    }
+
+    #[test]
+    fn playback_skips_frames_without_input() {
+        let press = |state| RecordedInput::Key { key: Key::Space, state };
+        let mut recording = InputRecording::new();
+        recording.push(1, press(true));
+        recording.push(1, press(false));
+        recording.push(4, press(true));
+        assert_eq!(recording.len_frames(), 5);
+
+        let mut replay = InputReplay::play(recording);
+        let frames: Vec<_> = (0..6)
+            .map(|_| {
+                let inputs = replay.playback_inputs().unwrap();
+                replay.end_frame();
+                inputs
+            })
+            .collect();
+
+        assert_eq!(
+            frames,
+            [vec![], vec![press(true), press(false)], vec![], vec![], vec![press(true)], vec![]]
+        );
+        assert!(replay.is_finished());
+        assert_eq!(replay.frame(), 6);
+
+        replay.record_input(&press(false));
+        assert_eq!(replay.recording().unwrap().frames.len(), 2);
+        assert!(InputReplay::Live.playback_inputs().is_none());
+    }
+}
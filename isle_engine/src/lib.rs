@@ -1,10 +1,13 @@
 pub mod asset;
+pub mod bridge;
 pub mod components;
+pub mod event;
 pub mod executor;
 pub mod flow;
 pub mod input;
 pub mod params;
 pub mod plugin;
+pub mod rollback;
 pub mod schedule;
 pub mod window;
 
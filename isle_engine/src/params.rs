@@ -1,4 +1,10 @@
-use std::{cell::UnsafeCell, collections::HashSet, fmt::Debug, ops::Deref, time::Instant};
+use std::{
+    cell::UnsafeCell,
+    collections::HashSet,
+    fmt::Debug,
+    ops::Deref,
+    time::{Duration, Instant},
+};
 
 use isle_ecs::{
     ecs::{BorrowSignature, RefType, SystemParam},
@@ -7,20 +13,65 @@ use isle_ecs::{
     world::{self, World},
 };
 
-use isle_event::{EventReader, EventWriter};
+use isle_event::{EventWriter, RetentionPolicy};
+
+use crate::{
+    event::{init_events, Events},
+    input::{ActionMap, AxisMapping, AxisSettings, InputMap, Mapping, PlayerInputs, ANY_PLAYER},
+};
+
+use isle_math::vector::d2::Vec2;
+
+/// Merged `InputMap` for `ANY_PLAYER`, the map of `player` in `PlayerInputs` otherwise
+fn player_input_map(world: &World, player: usize) -> &InputMap {
+    if player == ANY_PLAYER {
+        return world.get_resource::<InputMap>().unwrap();
+    }
+
+    world
+        .get_resource::<PlayerInputs>()
+        .and_then(|players| players.player(player))
+        .unwrap_or(InputMap::empty())
+}
 
-use crate::input::{AxisMapping, InputMap, Mapping};
+fn init_input_map(world: &UnsafeCell<World>) {
+    let world = unsafe { &mut *world.get() };
+    if world.get_resource::<InputMap>().is_none() {
+        world.store_resource(InputMap::new());
+    }
+}
 
+/// State of a binding this frame, edges are relative to the previous frame kept by `InputMap`
+///
+/// Reads every device by default, `Input<T, 1>` only reads the devices assigned to player 1
 #[derive(Clone, Copy)]
-pub struct Input<T: Mapping> {
+pub struct Input<T: Mapping, const PLAYER: usize = ANY_PLAYER> {
     state: bool,
-    just_changed: bool,
+    previous: bool,
+    held: Option<Duration>,
     _phantom: std::marker::PhantomData<T>,
 }
 
-impl<T: Mapping> Input<T> {
+impl<T: Mapping, const PLAYER: usize> Input<T, PLAYER> {
     pub fn just_changed(&self) -> bool {
-        self.just_changed
+        self.state != self.previous
+    }
+
+    pub fn just_pressed(&self) -> bool {
+        self.state && !self.previous
+    }
+
+    pub fn just_released(&self) -> bool {
+        !self.state && self.previous
+    }
+
+    /// True once any of the binding's inputs has been held for at least `duration`
+    pub fn pressed_for(&self, duration: Duration) -> bool {
+        self.state && self.held.is_some_and(|held| held >= duration)
+    }
+
+    pub fn pressed_duration(&self) -> Option<Duration> {
+        self.held
     }
 
     pub fn state(&self) -> bool {
@@ -28,7 +79,7 @@ impl<T: Mapping> Input<T> {
     }
 }
 
-impl<T: Mapping> Deref for Input<T> {
+impl<T: Mapping, const PLAYER: usize> Deref for Input<T, PLAYER> {
     type Target = bool;
 
     fn deref(&self) -> &Self::Target {
@@ -36,74 +87,61 @@ impl<T: Mapping> Deref for Input<T> {
     }
 }
 
-pub struct InputState<T: Mapping> {
-    last_state: bool,
-    _phantom: std::marker::PhantomData<T>,
-}
-
-impl<T: Mapping + 'static> SystemParam for Input<T> {
-    type State = InputState<T>;
-    type Item<'a> = Input<T>;
+impl<T: Mapping + 'static, const PLAYER: usize> SystemParam for Input<T, PLAYER> {
+    type State = ();
+    type Item<'a> = Input<T, PLAYER>;
 
     fn init_state(world: &std::cell::UnsafeCell<isle_ecs::world::World>) -> Self::State {
-        let world_ref = unsafe { &*world.get() };
-        let input_map = world_ref.get_resource::<InputMap>().unwrap_or_else(|| {
-            let world = unsafe { &mut *world.get() };
-            let input_map = InputMap::new();
-            world.store_resource(input_map);
-            world.get_resource().unwrap()
-        });
-        InputState {
-            last_state: T::get(input_map),
-            _phantom: std::marker::PhantomData,
-        }
+        init_input_map(world);
     }
 
     fn collect_types(types: &mut impl isle_ecs::prelude::TypeSet) {
         types.insert_type::<T>(RefType::Immutable);
+        types.insert_type::<ActionMap>(RefType::Immutable);
+        types.insert_type::<PlayerInputs>(RefType::Immutable);
     }
 
     fn from_world<'w>(
         world: &'w std::cell::UnsafeCell<world::World>,
-        state: &'w mut Self::State,
+        _: &'w mut Self::State,
         _: &str,
     ) -> Self::Item<'w> {
-        let input_map = unsafe { &*world.get() }.get_resource::<InputMap>().unwrap();
-        let input_state = T::get(input_map);
-        let just_changed = input_state != state.last_state;
-        state.last_state = input_state;
+        let world = unsafe { &*world.get() };
+        let input_map = player_input_map(world, PLAYER);
+        let actions = world.get_resource::<ActionMap>();
 
         Input {
-            state: input_state,
-            just_changed,
+            state: T::resolve(input_map, actions),
+            previous: T::resolve(input_map.previous(), actions),
+            held: T::pressed_duration(input_map, actions),
             _phantom: std::marker::PhantomData,
         }
     }
 }
 
-pub struct InputAxis<T: AxisMapping> {
+pub struct InputAxis<T: AxisMapping, const PLAYER: usize = ANY_PLAYER> {
     value: f32,
+    previous: f32,
     _phantom: std::marker::PhantomData<T>,
 }
 
-impl<T: AxisMapping> InputAxis<T> {
+impl<T: AxisMapping, const PLAYER: usize> InputAxis<T, PLAYER> {
     pub fn value(&self) -> f32 {
         self.value
     }
+
+    /// Change of the value since the previous frame
+    pub fn delta(&self) -> f32 {
+        self.value - self.previous
+    }
 }
 
-impl<T: AxisMapping + 'static> SystemParam for InputAxis<T> {
+impl<T: AxisMapping + 'static, const PLAYER: usize> SystemParam for InputAxis<T, PLAYER> {
     type State = ();
-    type Item<'new> = InputAxis<T>;
+    type Item<'new> = InputAxis<T, PLAYER>;
 
     fn init_state(world: &std::cell::UnsafeCell<isle_ecs::world::World>) -> Self::State {
-        let world_ref = unsafe { &*world.get() };
-        world_ref.get_resource::<InputMap>().unwrap_or_else(|| {
-            let world = unsafe { &mut *world.get() };
-            let input_map = InputMap::new();
-            world.store_resource(input_map);
-            world.get_resource().unwrap()
-        });
+        init_input_map(world);
     }
 
     fn from_world<'w>(
@@ -111,20 +149,89 @@ impl<T: AxisMapping + 'static> SystemParam for InputAxis<T> {
         _: &'w mut Self::State,
         _: &str,
     ) -> Self::Item<'w> {
-        let input_map = unsafe { &*world.get() }.get_resource::<InputMap>().unwrap();
-        let value = T::get(input_map);
+        let world = unsafe { &*world.get() };
+        let input_map = player_input_map(world, PLAYER);
+        let actions = world.get_resource::<ActionMap>();
 
         InputAxis {
-            value,
+            value: T::resolve(input_map, actions),
+            previous: T::resolve(input_map.previous(), actions),
             _phantom: std::marker::PhantomData,
         }
     }
 
     fn collect_types(types: &mut impl isle_ecs::prelude::TypeSet) {
         types.insert_type::<T>(RefType::Immutable);
+        types.insert_type::<ActionMap>(RefType::Immutable);
+        types.insert_type::<PlayerInputs>(RefType::Immutable);
+    }
+}
+
+/// Two axis mappings read as a stick, with a radial dead zone instead of one per axis
+pub struct InputAxis2<X: AxisMapping, Y: AxisMapping, const PLAYER: usize = ANY_PLAYER> {
+    value: Vec2,
+    _phantom: std::marker::PhantomData<(X, Y)>,
+}
+
+impl<X: AxisMapping, Y: AxisMapping, const PLAYER: usize> InputAxis2<X, Y, PLAYER> {
+    pub fn value(&self) -> Vec2 {
+        self.value
+    }
+
+    pub fn x(&self) -> f32 {
+        self.value.0
+    }
+
+    pub fn y(&self) -> f32 {
+        self.value.1
     }
 }
 
+impl<X: AxisMapping + 'static, Y: AxisMapping + 'static, const PLAYER: usize> SystemParam
+    for InputAxis2<X, Y, PLAYER>
+{
+    type State = ();
+    type Item<'new> = InputAxis2<X, Y, PLAYER>;
+
+    fn init_state(world: &std::cell::UnsafeCell<isle_ecs::world::World>) -> Self::State {
+        init_input_map(world);
+    }
+
+    fn from_world<'w>(
+        world: &'w std::cell::UnsafeCell<world::World>,
+        _: &'w mut Self::State,
+        _: &str,
+    ) -> Self::Item<'w> {
+        let world = unsafe { &*world.get() };
+        let input_map = player_input_map(world, PLAYER);
+        let actions = world.get_resource::<ActionMap>();
+        let raw = Vec2(
+            X::resolve_raw(input_map, actions),
+            Y::resolve_raw(input_map, actions),
+        );
+
+        InputAxis2 {
+            value: AxisSettings::apply_radial(
+                &X::resolve_settings(actions),
+                &Y::resolve_settings(actions),
+                raw,
+            ),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    fn collect_types(types: &mut impl isle_ecs::prelude::TypeSet) {
+        types.insert_type::<X>(RefType::Immutable);
+        types.insert_type::<Y>(RefType::Immutable);
+        types.insert_type::<ActionMap>(RefType::Immutable);
+        types.insert_type::<PlayerInputs>(RefType::Immutable);
+    }
+}
+
+/// When present, `Tick::delta` reports this fixed step instead of wall clock time
+#[derive(Debug, Clone, Copy)]
+pub struct FixedTimestep(pub f32);
+
 pub struct Tick {
     delta: f32,
 }
@@ -143,100 +250,161 @@ impl SystemParam for Tick {
         Instant::now()
     }
     fn from_world<'w>(
-        _: &'w std::cell::UnsafeCell<world::World>,
+        world: &'w std::cell::UnsafeCell<world::World>,
         state: &'w mut Self::State,
         _: &str,
     ) -> Self::Item<'w> {
-        let delta = state.elapsed().as_secs_f32();
+        let elapsed = state.elapsed().as_secs_f32();
         *state = Instant::now();
 
+        let world = unsafe { &*world.get() };
+        let delta = world
+            .get_resource::<FixedTimestep>()
+            .map_or(elapsed, |timestep| timestep.0);
+
         Tick { delta }
     }
     fn collect_types(types: &mut impl isle_ecs::prelude::TypeSet) {
         types.insert_type::<Instant>(RefType::Immutable);
+        types.insert_type::<FixedTimestep>(RefType::Immutable);
     }
 }
 
+/// Fetches the writer for `T`, creating it with the world's `RetentionPolicy` if needed
+pub(crate) fn get_event_writer<T: Clone + Debug + 'static>(world: &UnsafeCell<World>) -> &EventWriter<T> {
+    let world_ref = unsafe { &*world.get() };
+    world_ref.get_resource::<EventWriter<T>>().unwrap_or_else(|| {
+        let world = unsafe { &mut *world.get() };
+        let policy = world.get_resource::<RetentionPolicy>().cloned().unwrap_or_default();
+        world.store_resource(EventWriter::<T>::with_retention(policy));
+        world.get_resource().unwrap()
+    })
+}
+
+/// Per-system read position in `Events<T>`, reset when the buffers swap
+pub struct EventCursor {
+    frame: u64,
+    read: usize,
+}
+
 pub struct Event<'a, T: Clone + Debug + 'static> {
-    reader: &'a mut EventReader<T>,
+    events: &'a Events<T>,
+    cursor: &'a mut EventCursor,
 }
 
 impl<T: Clone + Debug + 'static> Event<'_, T> {
+    fn unread(&self) -> &[T] {
+        &self.events.readable()[self.cursor.read..]
+    }
+
     pub fn read(&mut self) -> Option<T> {
-        self.reader.read()
+        let event = self.unread().first().cloned();
+        if event.is_some() {
+            self.cursor.read += 1;
+        }
+        event
     }
 
     pub fn iter(&mut self) -> impl Iterator<Item = T> + '_ {
-        self.reader.iter()
+        let events: &[T] = self.events.readable();
+        let read: &mut usize = &mut self.cursor.read;
+        std::iter::from_fn(move || {
+            let event = events.get(*read).cloned();
+            *read += event.is_some() as usize;
+            event
+        })
+    }
+
+    /// Number of events this system has not read yet this frame
+    pub fn len(&self) -> usize {
+        self.unread().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unread().is_empty()
+    }
+
+    /// Marks every remaining event as read for this system
+    pub fn clear(&mut self) {
+        self.cursor.read = self.events.readable().len();
     }
 }
 
 impl<'a, T: Clone + Debug + 'static> SystemParam for Event<'a, T> {
-    type State = EventReader<T>;
+    type State = EventCursor;
     type Item<'new> = Event<'new, T>;
     fn collect_types(types: &mut impl isle_ecs::prelude::TypeSet) {
-        types.insert_type::<T>(RefType::Immutable);
+        types.insert_type::<Events<T>>(RefType::Immutable);
     }
     fn from_world<'w>(
-        _: &'w std::cell::UnsafeCell<isle_ecs::world::World>,
+        world: &'w std::cell::UnsafeCell<isle_ecs::world::World>,
         state: &'w mut Self::State,
         _: &str,
     ) -> Self::Item<'w> {
-        Event { reader: state }
+        let events = unsafe { &*world.get() }.get_resource::<Events<T>>().unwrap();
+        if state.frame != events.frame() {
+            state.frame = events.frame();
+            state.read = 0;
+        }
+
+        Event {
+            events,
+            cursor: state,
+        }
     }
     fn init_state(world: &std::cell::UnsafeCell<isle_ecs::world::World>) -> Self::State {
-        let world_ref = unsafe { &*world.get() };
-        let writer = world_ref
-            .get_resource::<EventWriter<T>>()
-            .unwrap_or_else(|| {
-                let world = unsafe { &mut *world.get() };
-                let writer = EventWriter::<T>::new();
-                world.store_resource(writer);
-                world.get_resource().unwrap()
-            });
-
-        EventReader::from_writer(writer)
+        init_events::<T>(world);
+        let events = unsafe { &*world.get() }.get_resource::<Events<T>>().unwrap();
+        EventCursor {
+            frame: events.frame(),
+            read: 0,
+        }
     }
 }
 
 pub struct EventTrigger<'a, T: Clone + Debug + 'static> {
-    writer: &'a mut EventWriter<T>,
+    events: &'a mut Events<T>,
 }
 
 impl<T: Clone + Debug + 'static> EventTrigger<'_, T> {
     pub fn send(&mut self, event: T) {
-        self.writer.send(event);
+        self.events.send(event);
+    }
+
+    /// Number of events sent so far this frame
+    pub fn len(&self) -> usize {
+        self.events.pending().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.pending().is_empty()
+    }
+
+    /// Drops the events sent so far this frame before readers see them
+    pub fn clear(&mut self) {
+        self.events.clear();
     }
 }
 
 impl<'a, T: Clone + Debug + 'static> SystemParam for EventTrigger<'a, T> {
-    type State = EventWriter<T>;
+    type State = ();
     type Item<'new> = EventTrigger<'new, T>;
 
     fn init_state(world: &std::cell::UnsafeCell<isle_ecs::world::World>) -> Self::State {
-        let world_ref = unsafe { &*world.get() };
-        let writer = world_ref
-            .get_resource::<EventWriter<T>>()
-            .unwrap_or_else(|| {
-                let world = unsafe { &mut *world.get() };
-                let writer = EventWriter::<T>::new();
-                world.store_resource(writer);
-                world.get_resource().unwrap()
-            });
-
-        writer.clone()
+        init_events::<T>(world);
     }
 
     fn from_world<'w>(
-        _: &'w std::cell::UnsafeCell<isle_ecs::world::World>,
-        state: &'w mut Self::State,
+        world: &'w std::cell::UnsafeCell<isle_ecs::world::World>,
+        _: &'w mut Self::State,
         _: &str,
     ) -> Self::Item<'w> {
-        EventTrigger { writer: state }
+        let events = unsafe { (*world.get()).get_resource_mut::<Events<T>>() }.unwrap();
+        EventTrigger { events }
     }
 
     fn collect_types(types: &mut impl isle_ecs::prelude::TypeSet) {
-        types.insert_type::<T>(RefType::Immutable);
+        types.insert_type::<Events<T>>(RefType::Mutable);
     }
 }
 
@@ -0,0 +1,245 @@
+use std::{collections::VecDeque, fmt::Display};
+
+use isle_ecs::world::{snapshot::WorldSnapshot, World};
+
+use crate::input::{InputMap, PlayerInputs};
+
+#[derive(Debug)]
+pub enum RollbackError {
+    TickUnavailable { tick: u64, oldest: u64, newest: u64 },
+    NoFixedTimestep,
+}
+
+impl std::error::Error for RollbackError {}
+
+impl Display for RollbackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TickUnavailable {
+                tick,
+                oldest,
+                newest,
+            } => write!(
+                f,
+                "Tick {tick} is not recorded, history covers ticks {oldest} to {newest}\nHint: increase the rollback capacity"
+            ),
+            Self::NoFixedTimestep => write!(
+                f,
+                "Resimulation requires a fixed timestep\nHint: add the FixedTimestep resource to the flow"
+            ),
+        }
+    }
+}
+
+struct RollbackFrame {
+    tick: u64,
+    snapshot: WorldSnapshot,
+    input: InputMap,
+    players: Option<PlayerInputs>,
+}
+
+/// Ring buffer of world snapshots and the inputs that produced them
+pub struct Rollback {
+    frames: VecDeque<RollbackFrame>,
+    capacity: usize,
+    tick: u64,
+}
+
+impl Rollback {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            tick: 0,
+        }
+    }
+
+    /// The most recently recorded tick
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn oldest_tick(&self) -> Option<u64> {
+        self.frames.front().map(|frame| frame.tick)
+    }
+
+    /// Records the world state after a step, along with the `InputMap` used for it
+    pub fn record(&mut self, world: &World) {
+        self.tick += 1;
+        self.push_frame(world);
+    }
+
+    pub fn snapshot(&self, tick: u64) -> Option<&WorldSnapshot> {
+        self.frame(tick).map(|frame| &frame.snapshot)
+    }
+
+    pub fn input(&self, tick: u64) -> Option<&InputMap> {
+        self.frame(tick).map(|frame| &frame.input)
+    }
+
+    pub fn players(&self, tick: u64) -> Option<&PlayerInputs> {
+        self.frame(tick).and_then(|frame| frame.players.as_ref())
+    }
+
+    /// Replaces the recorded input of a past tick, e.g. once a remote peer's input arrives
+    pub fn set_input(&mut self, tick: u64, input: InputMap) -> Result<(), RollbackError> {
+        let index = self.index(tick)?;
+        self.frames[index].input = input;
+        Ok(())
+    }
+
+    pub(crate) fn check_tick(&self, tick: u64) -> Result<(), RollbackError> {
+        self.index(tick).map(|_| ())
+    }
+
+    /// Drops every frame after `tick`, returning to it as the current tick
+    pub(crate) fn rewind(&mut self, tick: u64) -> Result<(), RollbackError> {
+        let index = self.index(tick)?;
+        self.frames.truncate(index + 1);
+        self.tick = tick;
+        Ok(())
+    }
+
+    fn push_frame(&mut self, world: &World) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(RollbackFrame {
+            tick: self.tick,
+            snapshot: world.snapshot(),
+            input: world.get_resource::<InputMap>().cloned().unwrap_or_default(),
+            players: world.get_resource::<PlayerInputs>().cloned(),
+        });
+    }
+
+    fn frame(&self, tick: u64) -> Option<&RollbackFrame> {
+        self.index(tick).ok().map(|index| &self.frames[index])
+    }
+
+    fn index(&self, tick: u64) -> Result<usize, RollbackError> {
+        let oldest = self.oldest_tick().unwrap_or(self.tick);
+        if tick < oldest || tick > self.tick || self.frames.is_empty() {
+            return Err(RollbackError::TickUnavailable {
+                tick,
+                oldest,
+                newest: self.tick,
+            });
+        }
+
+        Ok((tick - oldest) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use isle_ecs::ecs::ResMut;
+
+    use super::*;
+    use crate::{
+        flow::Flow,
+        input::{Button, InputRecording, Key, Mapping, RecordedInput},
+        params::{Event, EventTrigger, FixedTimestep, Input, Tick},
+    };
+
+    struct Jump;
+
+    impl Mapping for Jump {
+        fn keys<'a>() -> &'a [Key] {
+            &[Key::Space]
+        }
+
+        fn buttons<'a>() -> &'a [Button] {
+            &[]
+        }
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct Jumped;
+
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct Sim {
+        jumps: u32,
+        jump_events: u32,
+        height: f32,
+    }
+
+    fn jump(jump: Input<Jump>, tick: Tick, mut sim: ResMut<Sim>, mut jumped: EventTrigger<Jumped>) {
+        if jump.just_pressed() {
+            sim.jumps += 1;
+            jumped.send(Jumped);
+        }
+        if jump.state() {
+            sim.height += tick.delta();
+        }
+    }
+
+    fn count_jumps(mut jumped: Event<Jumped>, mut sim: ResMut<Sim>) {
+        sim.jump_events += jumped.iter().count() as u32;
+    }
+
+    fn press(recording: &mut InputRecording, frame: u64, state: bool) {
+        recording.push(frame, RecordedInput::Key { key: Key::Space, state });
+    }
+
+    #[test]
+    fn resimulation_matches_recorded_frames() {
+        let mut recording = InputRecording::new();
+        press(&mut recording, 1, true);
+        press(&mut recording, 3, false);
+        press(&mut recording, 5, true);
+        press(&mut recording, 6, false);
+        press(&mut recording, 8, true);
+
+        let mut flow = Flow::new()
+            .with_scheduler(isle_ecs::schedule::Scheduler)
+            .with_executor(isle_ecs::executor::Executor)
+            .with_resource(FixedTimestep(0.5))
+            .with_resource(Sim::default())
+            .with_input_playback(recording)
+            .build();
+        unsafe { &mut *flow.get_world().get() }
+            .registry_mut()
+            .register_resource_snapshot::<Sim>();
+        flow.add_system(crate::flow::stages::RUN, jump);
+        flow.add_system(crate::flow::stages::POST_RUN, count_jumps);
+
+        let mut rollback = Rollback::new(16);
+        for _ in 0..10 {
+            flow.run_headless(1);
+            flow.record(&mut rollback);
+        }
+
+        let live = flow.get_resource::<Sim>().cloned().unwrap();
+        assert_eq!(live.jumps, 3);
+        assert_eq!(live.jump_events, 3);
+
+        // Tick 2 is the frame of the first press, its event is still in flight; from tick 9 the
+        // last jump's event is read in the only replayed step
+        for from in [1, 2, 5, 8, 9] {
+            flow.resimulate(&mut rollback, from, (10 - from) as usize).unwrap();
+            assert_eq!(flow.get_resource::<Sim>(), Some(&live), "resimulated from tick {from}");
+            assert_eq!(rollback.tick(), 10);
+            assert_eq!(rollback.snapshot(10).unwrap().get_resource::<Sim>(), Some(&live));
+        }
+    }
+
+    #[test]
+    fn unrecorded_ticks_are_rejected() {
+        let mut world = World::new();
+        let mut rollback = Rollback::new(2);
+        (0..3).for_each(|_| rollback.record(&world));
+
+        assert_eq!(rollback.oldest_tick(), Some(2));
+        assert!(matches!(
+            rollback.check_tick(1),
+            Err(RollbackError::TickUnavailable { tick: 1, oldest: 2, newest: 3 })
+        ));
+
+        world.store_resource(InputMap::new());
+        rollback.set_input(3, InputMap::new()).unwrap();
+        rollback.rewind(2).unwrap();
+        assert_eq!(rollback.tick(), 2);
+        assert!(rollback.input(3).is_none());
+    }
+}
@@ -3,15 +3,26 @@ use std::sync::OnceLock;
 use isle_math::vector::d2::Vec2;
 use winit::{
     application::ApplicationHandler,
-    event::{ElementState, KeyEvent, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{DeviceEvent, DeviceId, ElementState, Ime, KeyEvent, MouseScrollDelta, WindowEvent},
     keyboard::PhysicalKey,
     window::{Window, WindowAttributes},
 };
 
-use crate::{executor::Executor, flow::Flow, input::Key, schedule::Scheduler};
+use crate::{
+    executor::Executor,
+    flow::Flow,
+    input::{Key, MouseButton},
+    schedule::Scheduler,
+};
+
+pub use winit::keyboard::Key as LogicalKey;
 
 pub static WINDOW: OnceLock<Window> = OnceLock::new();
 
+/// Pixel scroll deltas from touchpads are divided by this to match the line deltas of mouse wheels
+pub const SCROLL_PIXELS_PER_LINE: f32 = 20.0;
+
 #[derive(Debug, Clone, Copy)]
 pub struct ReconfigureSurface(pub Vec2);
 
@@ -21,6 +32,98 @@ pub struct KeyboardEvent {
     pub key: Key,
 }
 
+#[derive(Debug, Clone, Copy)]
+pub enum MouseEvent {
+    Button { button: MouseButton, state: bool },
+    /// Cursor position in physical pixels from the top left, `None` once it left the window
+    Moved { position: Option<Vec2>, window_size: Vec2 },
+    /// Raw device motion, keeps reporting while the cursor is grabbed or at the window edge
+    Motion(Vec2),
+    /// Scroll in lines, positive `y` scrolls up
+    Scroll(Vec2),
+}
+
+/// Text typed with a key press after the keyboard layout and dead keys are applied, usually a
+/// single character
+#[derive(Debug, Clone)]
+pub struct ReceivedCharacter {
+    pub text: String,
+    pub logical_key: LogicalKey,
+    pub repeat: bool,
+}
+
+/// Text composition through an input method, only sent while IME is allowed
+#[derive(Debug, Clone)]
+pub enum ImeEvent {
+    Enabled,
+    /// Text being composed, `cursor` is the byte range of the selection in `text`
+    Preedit {
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+    /// Finished text to insert, replaces the last preedit
+    Commit(String),
+    Disabled,
+}
+
+/// Input method state of the window, changes are applied to the window at the end of the frame
+///
+/// While allowed the platform input method can compose text, e.g. for CJK input, and text may
+/// arrive as `ImeEvent::Commit` instead of `ReceivedCharacter`
+#[derive(Debug, Clone, Default)]
+pub struct ImeSettings {
+    allowed: bool,
+    cursor_area: Option<(Vec2, Vec2)>,
+    changed: bool,
+}
+
+impl ImeSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_allowed(mut self, allowed: bool) -> Self {
+        self.set_allowed(allowed);
+        self
+    }
+
+    pub fn allowed(&self) -> bool {
+        self.allowed
+    }
+
+    pub fn set_allowed(&mut self, allowed: bool) {
+        self.changed |= self.allowed != allowed;
+        self.allowed = allowed;
+    }
+
+    pub fn cursor_area(&self) -> Option<(Vec2, Vec2)> {
+        self.cursor_area
+    }
+
+    /// Area of the text field being edited in physical pixels, the input method places its
+    /// candidate window next to it
+    pub fn set_cursor_area(&mut self, position: Vec2, size: Vec2) {
+        self.changed |= self.cursor_area != Some((position, size));
+        self.cursor_area = Some((position, size));
+    }
+
+    fn apply(&mut self, window: &Window) {
+        window.set_ime_allowed(self.allowed);
+        if let Some((position, size)) = self.cursor_area {
+            window.set_ime_cursor_area(
+                PhysicalPosition::new(position.0, position.1),
+                PhysicalSize::new(size.0, size.1),
+            );
+        }
+        self.changed = false;
+    }
+}
+
+fn window_size(window: &Window) -> Vec2 {
+    let size = window.inner_size();
+    Vec2(size.width as f32, size.height as f32)
+}
+
 impl<S: Scheduler, E: Executor> ApplicationHandler for Flow<S, E> {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         WINDOW
@@ -30,7 +133,11 @@ impl<S: Scheduler, E: Executor> ApplicationHandler for Flow<S, E> {
                     .unwrap(),
             )
             .unwrap();
-        let size = WINDOW.get().unwrap().inner_size();
+        let window = WINDOW.get().unwrap();
+        if let Some(ime) = self.get_resource_mut::<ImeSettings>() {
+            ime.apply(window);
+        }
+        let size = window.inner_size();
         self.send_event(ReconfigureSurface(Vec2(
             size.width as f32,
             size.height as f32,
@@ -52,7 +159,10 @@ impl<S: Scheduler, E: Executor> ApplicationHandler for Flow<S, E> {
         match event {
             WindowEvent::RedrawRequested => {
                 self.spin();
-                WINDOW.get().unwrap().request_redraw();
+                if let Some(ime) = self.get_resource_mut::<ImeSettings>().filter(|ime| ime.changed) {
+                    ime.apply(window);
+                }
+                window.request_redraw();
             }
             WindowEvent::CloseRequested => {
                 event_loop.exit();
@@ -64,20 +174,168 @@ impl<S: Scheduler, E: Executor> ApplicationHandler for Flow<S, E> {
                 )));
             }
 
-            WindowEvent::KeyboardInput {
-                event:
-                    KeyEvent {
-                        state,
-                        physical_key: PhysicalKey::Code(key_code),
-                        ..
-                    },
-                ..
-            } => self.send_event(KeyboardEvent {
+            WindowEvent::KeyboardInput { event, .. } => {
+                let KeyEvent {
+                    state,
+                    physical_key,
+                    logical_key,
+                    text,
+                    repeat,
+                    ..
+                } = event;
+
+                if let PhysicalKey::Code(key_code) = physical_key {
+                    self.send_event(KeyboardEvent {
+                        state: state == ElementState::Pressed,
+                        key: key_code.into(),
+                    });
+                }
+
+                if let (ElementState::Pressed, Some(text)) = (state, text) {
+                    self.send_event(ReceivedCharacter {
+                        text: text.to_string(),
+                        logical_key,
+                        repeat,
+                    });
+                }
+            }
+            WindowEvent::Ime(ime) => self.send_event(match ime {
+                Ime::Enabled => ImeEvent::Enabled,
+                Ime::Preedit(text, cursor) => ImeEvent::Preedit { text, cursor },
+                Ime::Commit(text) => ImeEvent::Commit(text),
+                Ime::Disabled => ImeEvent::Disabled,
+            }),
+
+            WindowEvent::MouseInput { state, button, .. } => self.send_event(MouseEvent::Button {
+                button: button.into(),
                 state: state == ElementState::Pressed,
-                key: key_code.into(),
             }),
+            WindowEvent::CursorMoved { position, .. } => self.send_event(MouseEvent::Moved {
+                position: Some(Vec2(position.x as f32, position.y as f32)),
+                window_size: window_size(window),
+            }),
+            WindowEvent::CursorLeft { .. } => self.send_event(MouseEvent::Moved {
+                position: None,
+                window_size: window_size(window),
+            }),
+            WindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => Vec2(x, y),
+                    MouseScrollDelta::PixelDelta(position) => Vec2(
+                        position.x as f32 / SCROLL_PIXELS_PER_LINE,
+                        position.y as f32 / SCROLL_PIXELS_PER_LINE,
+                    ),
+                };
+                self.send_event(MouseEvent::Scroll(delta));
+            }
 
             _ => (),
         }
     }
+
+    fn device_event(
+        &mut self,
+        _: &winit::event_loop::ActiveEventLoop,
+        _: DeviceId,
+        event: DeviceEvent,
+    ) {
+        if let DeviceEvent::MouseMotion { delta: (x, y) } = event {
+            self.send_event(MouseEvent::Motion(Vec2(x as f32, y as f32)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use isle_ecs::ecs::ResMut;
+
+    use super::*;
+    use crate::{flow::stages, params::Event};
+
+    #[derive(Debug, Default)]
+    struct TextField {
+        text: String,
+        preedit: Option<String>,
+        repeats: usize,
+    }
+
+    fn type_text(
+        mut characters: Event<ReceivedCharacter>,
+        mut ime: Event<ImeEvent>,
+        mut field: ResMut<TextField>,
+    ) {
+        for character in characters.iter() {
+            field.text.push_str(&character.text);
+            field.repeats += character.repeat as usize;
+        }
+        for event in ime.iter() {
+            match event {
+                ImeEvent::Preedit { text, .. } => field.preedit = Some(text),
+                ImeEvent::Commit(text) => {
+                    field.text.push_str(&text);
+                    field.preedit = None;
+                }
+                ImeEvent::Enabled | ImeEvent::Disabled => {}
+            }
+        }
+    }
+
+    #[test]
+    fn text_and_ime_events_reach_systems() {
+        let mut flow = Flow::new()
+            .with_scheduler(isle_ecs::schedule::Scheduler)
+            .with_executor(isle_ecs::executor::Executor)
+            .with_resource(TextField::default())
+            .with_ime_allowed()
+            .build();
+        flow.add_system(stages::RUN, type_text);
+        assert!(flow.get_resource::<ImeSettings>().unwrap().allowed());
+
+        let character = |text: &str, repeat| ReceivedCharacter {
+            text: text.into(),
+            logical_key: LogicalKey::Character(text.into()),
+            repeat,
+        };
+        flow.send_event(character("h", false));
+        flow.send_event(character("i", false));
+        flow.send_event(character("i", true));
+        flow.send_event(ImeEvent::Enabled);
+        flow.send_event(ImeEvent::Preedit {
+            text: "にほ".into(),
+            cursor: Some((6, 6)),
+        });
+        flow.run_headless(1);
+
+        let field = flow.get_resource::<TextField>().unwrap();
+        assert_eq!(field.text, "hii");
+        assert_eq!(field.repeats, 1);
+        assert_eq!(field.preedit.as_deref(), Some("にほ"));
+
+        flow.send_event(ImeEvent::Commit("日本".into()));
+        flow.send_event(ImeEvent::Disabled);
+        flow.run_headless(2);
+
+        let field = flow.get_resource::<TextField>().unwrap();
+        assert_eq!(field.text, "hii日本");
+        assert_eq!(field.preedit, None);
+    }
+
+    #[test]
+    fn ime_settings_track_changes() {
+        let mut ime = ImeSettings::new();
+        ime.set_allowed(false);
+        assert!(!ime.changed);
+
+        ime.set_allowed(true);
+        assert!(ime.allowed() && ime.changed);
+
+        ime.changed = false;
+        ime.set_cursor_area(Vec2(10.0, 20.0), Vec2(100.0, 16.0));
+        assert!(ime.changed);
+        assert_eq!(ime.cursor_area(), Some((Vec2(10.0, 20.0), Vec2(100.0, 16.0))));
+
+        ime.changed = false;
+        ime.set_cursor_area(Vec2(10.0, 20.0), Vec2(100.0, 16.0));
+        assert!(!ime.changed);
+    }
 }
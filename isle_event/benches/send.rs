@@ -0,0 +1,36 @@
+use std::thread;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use isle_event::EventWriter;
+
+const EVENTS: usize = 10_000;
+
+fn run(writers: usize, send: fn(&mut EventWriter<usize>, usize)) {
+    let writer = EventWriter::<usize>::new();
+
+    thread::scope(|scope| {
+        (0..writers).for_each(|_| {
+            let mut writer = writer.clone();
+            scope.spawn(move || (0..EVENTS).for_each(|i| send(&mut writer, i)));
+        });
+    });
+}
+
+fn send_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("send");
+
+    for writers in [1, 4, 8] {
+        group.throughput(Throughput::Elements((writers * EVENTS) as u64));
+        group.bench_with_input(BenchmarkId::new("mutex", writers), &writers, |b, &writers| {
+            b.iter(|| run(writers, |writer, i| writer.send(i)))
+        });
+        group.bench_with_input(BenchmarkId::new("lock_free", writers), &writers, |b, &writers| {
+            b.iter(|| run(writers, |writer, i| writer.send_lock_free(i)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, send_throughput);
+criterion_main!(benches);
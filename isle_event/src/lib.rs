@@ -1,83 +1,414 @@
+use arc_swap::ArcSwap;
 use std::{
     fmt::Debug,
     iter,
-    sync::{Arc, Mutex, OnceLock},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock, Weak,
+    },
 };
 
 type NodeReference<T> = Arc<OnceLock<EventNode<T>>>;
 
+/// Writers release old events every this many events
+const TRIM_INTERVAL: u64 = 64;
+/// Writers publish the shared tail hint every this many events
+const TAIL_INTERVAL: u64 = 16;
+
 #[derive(Clone, Debug)]
 struct EventNode<T: Clone + Debug + 'static> {
     event: T,
+    seq: u64,
+    frame: u64,
     next: NodeReference<T>,
 }
 
 impl<T: Clone + Debug + 'static> EventNode<T> {
-    fn new(event: T) -> Self {
+    fn new(event: T, seq: u64, frame: u64) -> Self {
         Self {
             event,
+            seq,
+            frame,
             next: Arc::new(OnceLock::new()),
         }
     }
+}
 
-    // pub fn push(&self, event: T) -> NodeReference<T> {
-    //     let result = if let Some(next) = self.next.get() {
-    //         Ok(next.push(event.clone()))
-    //     } else {
-    //         self.next
-    //             .set(Self::new(event.clone()))
-    //             .map(|_| self.next.clone())
-    //     };
+/// Frame counter shared between writers, advanced once per frame by the owner of the loop
+#[derive(Clone, Debug, Default)]
+pub struct EventClock {
+    frame: Arc<AtomicU64>,
+}
 
-    //     if let Ok(node) = result {
-    //         node
-    //     } else {
-    //         self.push(event)
-    //     }
-    // }
+impl EventClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame.load(Ordering::Acquire)
+    }
+
+    pub fn advance(&self) -> u64 {
+        self.frame.fetch_add(1, Ordering::AcqRel) + 1
+    }
+}
+
+/// Limits how many events a channel keeps for readers that fall behind
+///
+/// Without limits events are kept until every reader has read them
+#[derive(Clone, Debug, Default)]
+pub struct RetentionPolicy {
+    max_events: Option<usize>,
+    max_frames: Option<(u64, EventClock)>,
+}
+
+impl RetentionPolicy {
+    pub fn unbounded() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_events(mut self, max_events: usize) -> Self {
+        self.max_events = Some(max_events);
+        self
+    }
+
+    /// Drops events sent more than `max_frames` frames of `clock` ago
+    pub fn with_max_frames(mut self, max_frames: u64, clock: &EventClock) -> Self {
+        self.max_frames = Some((max_frames, clock.clone()));
+        self
+    }
+
+    fn expired(&self, frame: u64) -> bool {
+        self.max_frames
+            .as_ref()
+            .is_some_and(|(max_frames, clock)| frame + max_frames < clock.frame())
+    }
+}
+
+struct ChannelState<T: Clone + Debug + 'static> {
+    oldest: NodeReference<T>,
+    oldest_seq: u64,
+}
+
+/// Shared hint to the end of the list, may lag behind while writers race
+struct Tail<T: Clone + Debug + 'static> {
+    slot: NodeReference<T>,
+    seq: u64,
+}
+
+/// Walks from `slot` to the first unset slot, `seq` is the sequence number `slot` would hold
+fn find_tail<T: Clone + Debug + 'static>(mut slot: NodeReference<T>, mut seq: u64) -> (NodeReference<T>, u64) {
+    while let Some(node) = slot.get() {
+        seq = node.seq + 1;
+        let next = node.next.clone();
+        slot = next;
+    }
+
+    (slot, seq)
+}
+
+struct Channel<T: Clone + Debug + 'static> {
+    state: Mutex<ChannelState<T>>,
+    /// Mirrors `ChannelState::oldest_seq` so readers notice a trimmed head without locking, the
+    /// head may still be kept alive by the tail hint or another reader
+    oldest_seq: AtomicU64,
+    tail: ArcSwap<Tail<T>>,
+    readers: Mutex<Vec<Weak<AtomicU64>>>,
+    policy: RetentionPolicy,
+}
+
+impl<T: Clone + Debug + 'static> Channel<T> {
+    fn new(policy: RetentionPolicy) -> Self {
+        let slot = NodeReference::default();
+        Self {
+            state: Mutex::new(ChannelState {
+                oldest: slot.clone(),
+                oldest_seq: 0,
+            }),
+            oldest_seq: AtomicU64::new(0),
+            tail: ArcSwap::from_pointee(Tail { slot, seq: 0 }),
+            readers: Mutex::new(Vec::new()),
+            policy,
+        }
+    }
+
+    fn tail(&self) -> (NodeReference<T>, u64) {
+        let tail = self.tail.load();
+        find_tail(tail.slot.clone(), tail.seq)
+    }
+
+    /// Moves the shared hint forward, a writer that lost the race must not rewind it
+    fn advance_tail(&self, slot: NodeReference<T>, seq: u64) {
+        let current = self.tail.load();
+        if current.seq < seq {
+            self.tail.compare_and_swap(&current, Arc::new(Tail { slot, seq }));
+        }
+    }
+
+    fn register(&self, seq: u64) -> Arc<AtomicU64> {
+        let cursor = Arc::new(AtomicU64::new(seq));
+        self.readers.lock().unwrap().push(Arc::downgrade(&cursor));
+        cursor
+    }
+
+    fn slowest_reader(readers: &mut Vec<Weak<AtomicU64>>) -> Option<u64> {
+        readers.retain(|cursor| cursor.strong_count() > 0);
+        readers
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|cursor| cursor.load(Ordering::Acquire))
+            .min()
+    }
+
+    /// Releases events every reader has read and events outside the retention policy
+    fn trim(&self) {
+        let slowest = Self::slowest_reader(&mut self.readers.lock().unwrap());
+        let mut state = self.state.lock().unwrap();
+        self.trim_locked(&mut state, slowest);
+    }
+
+    /// Like `trim` but skips if another thread is already trimming
+    fn try_trim(&self) {
+        let Ok(mut readers) = self.readers.try_lock() else {
+            return;
+        };
+        let slowest = Self::slowest_reader(&mut readers);
+        drop(readers);
+
+        if let Ok(mut state) = self.state.try_lock() {
+            self.trim_locked(&mut state, slowest);
+        }
+    }
+
+    fn trim_locked(&self, state: &mut ChannelState<T>, slowest: Option<u64>) {
+        let (_, next_seq) = self.tail();
+        let mut keep_from = slowest.unwrap_or(next_seq);
+        if let Some(max_events) = self.policy.max_events {
+            keep_from = keep_from.max(next_seq.saturating_sub(max_events as u64));
+        }
+
+        while let Some(node) = state.oldest.get() {
+            if node.seq >= keep_from && !self.policy.expired(node.frame) {
+                break;
+            }
+
+            let next = node.next.clone();
+            state.oldest = next;
+            state.oldest_seq += 1;
+        }
+
+        self.oldest_seq.store(state.oldest_seq, Ordering::Release);
+        // A hint left behind the trimmed events would keep them alive
+        self.advance_tail(state.oldest.clone(), state.oldest_seq);
+    }
 }
 
 #[derive(Clone)]
 pub struct EventWriter<T: Clone + Debug + 'static> {
-    last: Arc<Mutex<NodeReference<T>>>,
+    channel: Arc<Channel<T>>,
+    hint: Weak<OnceLock<EventNode<T>>>,
+    hint_seq: u64,
+}
+
+impl<T: Clone + Debug + 'static> Default for EventWriter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T: Clone + Debug + 'static> EventWriter<T> {
     pub fn new() -> Self {
+        Self::with_retention(RetentionPolicy::unbounded())
+    }
+
+    pub fn with_retention(policy: RetentionPolicy) -> Self {
         Self {
-            last: Default::default(),
+            channel: Arc::new(Channel::new(policy)),
+            hint: Weak::new(),
+            hint_seq: 0,
         }
     }
 
+    pub fn retention(&self) -> &RetentionPolicy {
+        &self.channel.policy
+    }
+
     pub fn send(&mut self, event: T) {
-        let mut last = self.last.lock().unwrap();
-        last.set(EventNode::new(event))
-            .expect("Last node in invalid state");
-        *last = last.get().unwrap().next.clone();
+        let channel = self.channel.clone();
+        let guard = channel.state.lock().unwrap();
+        let seq = self.append(event);
+        drop(guard);
+
+        if seq.is_multiple_of(TRIM_INTERVAL) {
+            self.channel.trim();
+        }
+    }
+
+    /// Appends without taking the channel lock, concurrent writers race on the tail instead
+    pub fn send_lock_free(&mut self, event: T) {
+        if self.append(event).is_multiple_of(TRIM_INTERVAL) {
+            self.channel.try_trim();
+        }
+    }
+
+    /// Appends after the tail, `OnceLock::set` acts as the compare and swap so
+    /// concurrent appends retry on the next free slot
+    fn append(&mut self, event: T) -> u64 {
+        let frame = self
+            .channel
+            .policy
+            .max_frames
+            .as_ref()
+            .map_or(0, |(_, clock)| clock.frame());
+
+        let (mut slot, mut seq) = match self.hint.upgrade() {
+            Some(slot) => find_tail(slot, self.hint_seq),
+            None => self.channel.tail(),
+        };
+        let mut node = EventNode::new(event, seq, frame);
+
+        while let Err(rejected) = slot.set(node) {
+            (slot, seq) = find_tail(slot, seq);
+            node = rejected;
+            node.seq = seq;
+        }
+
+        let next = slot.get().unwrap().next.clone();
+        self.hint = Arc::downgrade(&next);
+        self.hint_seq = seq + 1;
+
+        if seq.is_multiple_of(TAIL_INTERVAL) {
+            self.channel.advance_tail(next, seq + 1);
+        }
+
+        seq
+    }
+
+    /// Number of events currently held for readers
+    pub fn retained(&self) -> usize {
+        self.channel.trim();
+        let state = self.channel.state.lock().unwrap();
+        let (_, next_seq) = self.channel.tail();
+        (next_seq - state.oldest_seq) as usize
     }
 
+    #[cfg(test)]
     fn last(&self) -> NodeReference<T> {
-        self.last.lock().unwrap().clone()
+        self.channel.tail().0
     }
 }
 
-#[derive(Clone)]
 pub struct EventReader<T: Clone + Debug + 'static> {
-    head: NodeReference<T>,
+    head: Weak<OnceLock<EventNode<T>>>,
+    seq: u64,
+    cursor: Arc<AtomicU64>,
+    channel: Arc<Channel<T>>,
+    missed: u64,
+    trimmed_seq: u64,
+}
+
+impl<T: Clone + Debug + 'static> Clone for EventReader<T> {
+    fn clone(&self) -> Self {
+        Self {
+            head: self.head.clone(),
+            seq: self.seq,
+            cursor: self.channel.register(self.seq),
+            channel: self.channel.clone(),
+            missed: self.missed,
+            trimmed_seq: self.trimmed_seq,
+        }
+    }
 }
 
 impl<T: Clone + Debug + 'static> EventReader<T> {
     pub fn from_writer(writer: &EventWriter<T>) -> Self {
+        let channel = writer.channel.clone();
+        let (head, seq) = channel.tail();
+        let head = Arc::downgrade(&head);
+
         Self {
-            head: writer.last().clone(),
+            head,
+            seq,
+            cursor: channel.register(seq),
+            channel,
+            missed: 0,
+            trimmed_seq: seq,
+        }
+    }
+
+    /// Total number of events dropped by the retention policy before this reader got to them
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+
+    fn resync(&mut self) -> NodeReference<T> {
+        let state = self.channel.state.lock().unwrap();
+        self.missed += state.oldest_seq.saturating_sub(self.seq);
+        self.seq = state.oldest_seq;
+        state.oldest.clone()
+    }
+
+    /// First unread slot, events the retention policy expired are skipped without trimming
+    fn head(&mut self) -> NodeReference<T> {
+        let trimmed = self.seq < self.channel.oldest_seq.load(Ordering::Acquire);
+        let mut head = match self.head.upgrade() {
+            Some(head) if !trimmed => head,
+            _ => self.resync(),
+        };
+
+        while let Some(node) = head.get() {
+            if !self.channel.policy.expired(node.frame) {
+                break;
+            }
+
+            self.missed += 1;
+            self.seq = node.seq + 1;
+            let next = node.next.clone();
+            head = next;
+        }
+
+        self.cursor.store(self.seq, Ordering::Release);
+        head
+    }
+
+    /// Releases events this reader moved past, every `TRIM_INTERVAL` events and once caught up
+    fn trim(&mut self, caught_up: bool) {
+        let due = self.seq.is_multiple_of(TRIM_INTERVAL) || (caught_up && self.seq != self.trimmed_seq);
+        if due {
+            self.trimmed_seq = self.seq;
+            self.channel.try_trim();
         }
     }
 
     pub fn read(&mut self) -> Option<T> {
-        self.head.get().cloned().map(|node| {
-            self.head = node.next;
-            node.event
-        })
+        let head = self.head();
+
+        let Some(node) = head.get() else {
+            self.head = Arc::downgrade(&head);
+            drop(head);
+            self.trim(true);
+            return None;
+        };
+
+        self.head = Arc::downgrade(&node.next);
+        self.seq = node.seq + 1;
+        self.cursor.store(self.seq, Ordering::Release);
+        let event = node.event.clone();
+
+        drop(head);
+        self.trim(false);
+
+        Some(event)
+    }
+
+    /// Returns the next event without advancing past it
+    pub fn peek(&mut self) -> Option<T> {
+        let head = self.head();
+
+        let event = head.get().map(|node| node.event.clone());
+        self.head = Arc::downgrade(&head);
+        event
     }
 
     pub fn iter(&mut self) -> impl Iterator<Item = T> + '_ {
@@ -85,6 +416,127 @@ impl<T: Clone + Debug + 'static> EventReader<T> {
     }
 }
 
+/// Anything events can be read from, composable like an iterator without buffering events
+pub trait EventStream {
+    type Item;
+
+    fn read(&mut self) -> Option<Self::Item>;
+
+    /// Returns the next event without advancing past it
+    fn peek(&mut self) -> Option<Self::Item>;
+
+    fn iter(&mut self) -> impl Iterator<Item = Self::Item> + '_
+    where
+        Self: Sized,
+    {
+        iter::from_fn(move || self.read())
+    }
+
+    /// Skips events `predicate` rejects, they are consumed from the underlying stream
+    fn filter<F: FnMut(&Self::Item) -> bool>(self, predicate: F) -> Filter<Self, F>
+    where
+        Self: Sized,
+    {
+        Filter {
+            stream: self,
+            predicate,
+        }
+    }
+
+    /// Transforms each event, `peek` calls `f` on the peeked event as well
+    fn map<U, F: FnMut(Self::Item) -> U>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized,
+    {
+        Map { stream: self, f }
+    }
+
+    /// Reads every pending event of `self` before those of `other`
+    fn merge<S: EventStream<Item = Self::Item>>(self, other: S) -> Merge<Self, S>
+    where
+        Self: Sized,
+    {
+        Merge {
+            first: self,
+            second: other,
+        }
+    }
+}
+
+impl<T: Clone + Debug + 'static> EventStream for EventReader<T> {
+    type Item = T;
+
+    fn read(&mut self) -> Option<T> {
+        EventReader::read(self)
+    }
+
+    fn peek(&mut self) -> Option<T> {
+        EventReader::peek(self)
+    }
+}
+
+pub struct Filter<S, F> {
+    stream: S,
+    predicate: F,
+}
+
+impl<S: EventStream, F: FnMut(&S::Item) -> bool> EventStream for Filter<S, F> {
+    type Item = S::Item;
+
+    fn read(&mut self) -> Option<S::Item> {
+        loop {
+            let event = self.stream.read()?;
+            if (self.predicate)(&event) {
+                return Some(event);
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<S::Item> {
+        loop {
+            let event = self.stream.peek()?;
+            if (self.predicate)(&event) {
+                return Some(event);
+            }
+            self.stream.read();
+        }
+    }
+}
+
+pub struct Map<S, F> {
+    stream: S,
+    f: F,
+}
+
+impl<S: EventStream, U, F: FnMut(S::Item) -> U> EventStream for Map<S, F> {
+    type Item = U;
+
+    fn read(&mut self) -> Option<U> {
+        self.stream.read().map(&mut self.f)
+    }
+
+    fn peek(&mut self) -> Option<U> {
+        self.stream.peek().map(&mut self.f)
+    }
+}
+
+pub struct Merge<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: EventStream, B: EventStream<Item = A::Item>> EventStream for Merge<A, B> {
+    type Item = A::Item;
+
+    fn read(&mut self) -> Option<A::Item> {
+        self.first.read().or_else(|| self.second.read())
+    }
+
+    fn peek(&mut self) -> Option<A::Item> {
+        self.first.peek().or_else(|| self.second.peek())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::thread;
@@ -107,36 +559,158 @@ mod test {
     fn test_write_read() {
         let (writer, mut reader) = make_channel();
 
-        let head_ref = Arc::downgrade(&reader.head);
+        let head_ref = reader.head.clone();
 
         let events: Vec<_> = reader.iter().map(|Event(i)| i).collect();
         let comp: Vec<usize> = (0..5).collect();
 
         assert_eq!(events, comp);
-        assert!(Arc::ptr_eq(&writer.last(), &reader.head));
+        assert!(Arc::ptr_eq(&writer.last(), &reader.head.upgrade().unwrap()));
         assert!(head_ref.upgrade().is_none());
-        assert!(reader.head.get().is_none());
+        assert!(reader.head.upgrade().unwrap().get().is_none());
     }
 
     #[test]
     fn test_mt_read() {
         let (writer, mut reader) = make_channel();
-        let head_ref = Arc::downgrade(&reader.head);
+        let head_ref = reader.head.clone();
 
         let mut reader_clone = reader.clone();
         let writer_clone = writer.clone();
 
         let thread = thread::spawn(move || {
             reader_clone.iter().for_each(|_| {});
-            assert!(Arc::ptr_eq(&writer_clone.last(), &reader_clone.head));
+            assert!(Arc::ptr_eq(&writer_clone.last(), &reader_clone.head.upgrade().unwrap()));
         });
 
         let head_deref = head_ref.upgrade().unwrap();
-        assert!(Arc::ptr_eq(&reader.head, &head_deref));
+        assert!(Arc::ptr_eq(&reader.head.upgrade().unwrap(), &head_deref));
         let Event(i) = reader.read().unwrap();
         assert_eq!(i, 0);
         drop(head_deref);
         thread.join().unwrap();
+        writer.retained();
         assert!(head_ref.upgrade().is_none());
+
+        reader.iter().for_each(|_| {});
+
+        const WRITERS: usize = 8;
+        const EVENTS: usize = 1000;
+
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|w| {
+                let mut writer = writer.clone();
+                thread::spawn(move || {
+                    (0..EVENTS).for_each(|i| match i % 2 {
+                        0 => writer.send_lock_free(Event(w * EVENTS + i)),
+                        _ => writer.send(Event(w * EVENTS + i)),
+                    })
+                })
+            })
+            .collect();
+
+        let mut received = Vec::with_capacity(WRITERS * EVENTS);
+        while received.len() < WRITERS * EVENTS {
+            received.extend(reader.iter().map(|Event(i)| i));
+        }
+        writers.into_iter().for_each(|writer| writer.join().unwrap());
+
+        assert_eq!(reader.missed(), 0);
+        assert!(reader.read().is_none());
+        (0..WRITERS).for_each(|w| {
+            let own: Vec<_> = received.iter().copied().filter(|i| i / EVENTS == w).collect();
+            assert_eq!(own, (w * EVENTS..(w + 1) * EVENTS).collect::<Vec<_>>());
+        });
+    }
+
+    #[test]
+    fn test_no_readers() {
+        let (mut writer, reader) = make_channel();
+        drop(reader);
+
+        writer.send(Event(5));
+        assert_eq!(writer.retained(), 0);
+    }
+
+    #[test]
+    fn test_max_events() {
+        let mut writer = EventWriter::with_retention(RetentionPolicy::unbounded().with_max_events(3));
+        let mut reader = EventReader::from_writer(&writer);
+
+        (0..10).map(Event).for_each(|event| writer.send(event));
+        assert_eq!(writer.retained(), 3);
+
+        let events: Vec<_> = reader.iter().map(|Event(i)| i).collect();
+        assert_eq!(events, vec![7, 8, 9]);
+        assert_eq!(reader.missed(), 7);
+    }
+
+    #[test]
+    fn test_max_events_behind_tail_hint() {
+        let mut writer = EventWriter::with_retention(RetentionPolicy::unbounded().with_max_events(4));
+        (0..17).map(Event).for_each(|event| writer.send(event));
+
+        // The tail hint was last published after event 16, which is where this reader starts
+        let mut reader = EventReader::from_writer(&writer);
+        (17..31).map(Event).for_each(|event| writer.send(event));
+        assert_eq!(writer.retained(), 4);
+
+        let events: Vec<_> = reader.iter().map(|Event(i)| i).collect();
+        assert_eq!(events, vec![27, 28, 29, 30]);
+        assert_eq!(reader.missed(), 10);
+    }
+
+    #[test]
+    fn test_max_frames() {
+        let clock = EventClock::new();
+        let mut writer =
+            EventWriter::with_retention(RetentionPolicy::unbounded().with_max_frames(1, &clock));
+        let mut reader = EventReader::from_writer(&writer);
+
+        writer.send(Event(0));
+        clock.advance();
+        writer.send(Event(1));
+        clock.advance();
+
+        let events: Vec<_> = reader.iter().map(|Event(i)| i).collect();
+        assert_eq!(events, vec![1]);
+        assert_eq!(reader.missed(), 1);
+    }
+
+    #[test]
+    fn test_max_frames_without_trim() {
+        let clock = EventClock::new();
+        let mut writer =
+            EventWriter::with_retention(RetentionPolicy::unbounded().with_max_frames(1, &clock));
+        let mut reader = EventReader::from_writer(&writer);
+
+        writer.send(Event(0));
+        writer.send(Event(1));
+        clock.advance();
+        clock.advance();
+        writer.send(Event(2));
+
+        assert!(matches!(reader.peek(), Some(Event(2))));
+        assert_eq!(reader.missed(), 2);
+        assert_eq!(reader.iter().map(|Event(i)| i).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(writer.retained(), 0);
+    }
+
+    #[test]
+    fn test_combinators() {
+        let (mut writer, reader) = make_channel();
+        let mut other_writer = EventWriter::<Event>::new();
+        let other = EventReader::from_writer(&other_writer);
+        other_writer.send(Event(10));
+
+        let mut evens = reader.clone().filter(|Event(i)| i % 2 == 0).map(|Event(i)| i * 10);
+        assert_eq!(evens.peek(), Some(0));
+        assert_eq!(evens.iter().collect::<Vec<_>>(), vec![0, 20, 40]);
+
+        let mut merged = reader.filter(|Event(i)| *i >= 3).merge(other);
+        writer.send(Event(5));
+        let events: Vec<_> = merged.iter().map(|Event(i)| i).collect();
+        assert_eq!(events, vec![3, 4, 5, 10]);
+        assert!(merged.peek().is_none());
     }
 }
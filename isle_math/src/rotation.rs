@@ -18,6 +18,7 @@ pub mod quaternion {
     use super::Rotation;
 
     #[derive(Clone, Copy, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Quaternion(pub f32, pub f32, pub f32, pub f32);
 
     impl Quaternion {
@@ -152,6 +153,7 @@ pub mod quaternion {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Rotation {
     Euler(Vec3),
     Quaternion(quaternion::Quaternion),
@@ -311,6 +313,7 @@ impl Mul<Vec3> for Rotation {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Angle {
     Radians(f32),
     Degrees(f32),
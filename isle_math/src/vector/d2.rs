@@ -5,6 +5,7 @@ use crate::matrix::Matrix;
 use super::d3::Vec3;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vec2(pub f32, pub f32);
 
 impl Vec2 {
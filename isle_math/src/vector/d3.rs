@@ -5,6 +5,7 @@ use crate::matrix::Matrix;
 use super::d4::Vec4;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vec3(pub f32, pub f32, pub f32);
 
 impl Vec3 {
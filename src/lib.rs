@@ -10,7 +10,7 @@ pub mod prelude {
 }
 
 pub mod defaults {
-    use isle_engine::{asset::AssetManager, flow::FlowBuilder};
+    use isle_engine::flow::FlowBuilder;
 
     type Scheduler = isle_ecs::schedule::Scheduler;
     type Executor = isle_ecs::executor::Executor;
@@ -34,7 +34,6 @@ pub mod defaults {
         fn with_default_plugins(mut self) -> Self {
             self = self.with_plugin(isle_engine::plugin::default_plugins);
             self = renderer(self);
-            self = self.with_hook(AssetManager);
 
             self
         }